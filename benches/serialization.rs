@@ -0,0 +1,149 @@
+//! Compares writing a transaction payload through the `serde_json::Value` tree with
+//! writing it directly, run with `cargo bench --bench serialization`
+use {
+    agave_geyser_plugin_interface::geyser_plugin_interface::ReplicaTransactionInfoV2,
+    serde_json::json,
+    solana_geyser_plugin_nats::{
+        config::PayloadFormat, serializer::TransactionSerializer,
+        transaction_writer::TransactionWriter,
+    },
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{AccountMeta, CompiledInstruction, Instruction},
+        message::{Message, SimpleAddressLoader, VersionedMessage},
+        pubkey::Pubkey,
+        signature::Signature,
+        system_instruction,
+        transaction::{MessageHash, SanitizedTransaction, VersionedTransaction},
+    },
+    solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta},
+    std::{
+        collections::HashSet,
+        hint::black_box,
+        time::{Duration, Instant},
+    },
+};
+
+const ITERATIONS: u32 = 20_000;
+
+/// A swap-sized transaction: compute budget, a transfer and a program call over eight
+/// accounts, with inner instructions and a page of logs
+fn create_transaction() -> (SanitizedTransaction, TransactionStatusMeta) {
+    let payer = Pubkey::new_unique();
+    let accounts = (0..8)
+        .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+        .collect();
+    let message = Message::new(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+            ComputeBudgetInstruction::set_compute_unit_price(10_000),
+            system_instruction::transfer(&payer, &Pubkey::new_unique(), 1_000_000),
+            Instruction {
+                program_id: Pubkey::new_unique(),
+                accounts,
+                data: vec![7; 64],
+            },
+        ],
+        Some(&payer),
+    );
+    let transaction = SanitizedTransaction::try_create(
+        VersionedTransaction {
+            message: VersionedMessage::Legacy(message),
+            signatures: vec![Signature::new_unique()],
+        },
+        MessageHash::Compute,
+        Some(false),
+        SimpleAddressLoader::Disabled,
+        &HashSet::new(),
+    )
+    .unwrap();
+
+    let account_count = transaction.message().account_keys().len();
+    let meta = TransactionStatusMeta {
+        status: Ok(()),
+        fee: 15_000,
+        pre_balances: vec![1_000_000; account_count],
+        post_balances: vec![985_000; account_count],
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 3,
+            instructions: (0..6)
+                .map(|position| InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(
+                        position + 1,
+                        vec![3; 32],
+                        vec![0, 1, 2, 3],
+                    ),
+                    stack_height: Some(2),
+                })
+                .collect(),
+        }]),
+        log_messages: Some(
+            (0..30)
+                .map(|line| format!("Program log: instruction step {line} completed"))
+                .collect(),
+        ),
+        compute_units_consumed: Some(120_000),
+        ..TransactionStatusMeta::default()
+    };
+    (transaction, meta)
+}
+
+fn run(name: &str, mut encode: impl FnMut() -> Vec<u8>) -> Duration {
+    // Warm up caches and the writer's buffer
+    for _ in 0..ITERATIONS / 10 {
+        black_box(encode());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(encode());
+    }
+    let per_payload = start.elapsed() / ITERATIONS;
+    println!(
+        "{name:<24} {:>8.2} us/payload",
+        per_payload.as_secs_f64() * 1e6
+    );
+    per_payload
+}
+
+fn main() {
+    let (transaction, meta) = create_transaction();
+    let transaction_info = ReplicaTransactionInfoV2 {
+        signature: &transaction.signatures()[0],
+        is_vote: false,
+        transaction: &transaction,
+        transaction_status_meta: &meta,
+        index: 42,
+    };
+    let slot = 250_000_000;
+
+    for format in [
+        PayloadFormat::Json,
+        PayloadFormat::Msgpack,
+        PayloadFormat::Cbor,
+    ] {
+        let value_path = run(&format!("value tree ({format:?})"), || {
+            let mut value =
+                TransactionSerializer::serialize_transaction_v2(&transaction_info, slot).unwrap();
+            let object = value.as_object_mut().unwrap();
+            object.insert(
+                "orderingKey".to_string(),
+                json!(TransactionSerializer::ordering_key(slot, Some(42))),
+            );
+            object.insert("sequence".to_string(), json!(1));
+            TransactionSerializer::set_schema_version(&mut value);
+            TransactionSerializer::encode(&value, format, "transaction").unwrap()
+        });
+        let writer_path = run(&format!("direct writer ({format:?})"), || {
+            TransactionWriter::new(&transaction, &meta, slot, Some(42), false)
+                .with_sequence(1)
+                .encode(format)
+                .unwrap()
+        });
+        println!(
+            "{:<24} {:>8.2}x\n",
+            "speedup",
+            value_path.as_secs_f64() / writer_path.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use {
+    crate::{config::AddressRateLimitConfig, rate_limiter::TokenBucket},
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashMap, sync::Mutex, time::Instant},
+};
+
+/// Limits the transactions published per second for each address of the filter, so one
+/// hyperactive wallet cannot drown out the other addresses sharing its subject
+pub struct AddressRateLimiter {
+    messages_per_sec: u64,
+    buckets: Mutex<HashMap<Pubkey, TokenBucket>>,
+}
+
+impl AddressRateLimiter {
+    /// Create an address rate limiter, or `None` when no limit is configured
+    pub fn new(config: &AddressRateLimitConfig) -> Option<Self> {
+        (config.messages_per_sec > 0).then(|| Self {
+            messages_per_sec: config.messages_per_sec,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Account for a transaction selected through `addresses` at `now`, returning whether
+    /// it is published. Every matched address is charged, and the transaction is dropped
+    /// only when all of them are over their limit, so a busy address cannot suppress the
+    /// transactions it shares with a quiet one
+    pub fn allow(&self, addresses: &[&Pubkey], now: Instant) -> bool {
+        if addresses.is_empty() {
+            return true;
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut allowed = false;
+        for address in addresses {
+            let bucket = buckets
+                .entry(**address)
+                .or_insert_with(|| TokenBucket::new(self.messages_per_sec, now));
+            allowed |= bucket.try_take(now);
+        }
+        allowed
+    }
+
+    /// Number of addresses with a rate limit bucket
+    pub fn tracked_addresses(&self) -> usize {
+        self.buckets.lock().unwrap().len()
+    }
+}
@@ -0,0 +1,96 @@
+use {
+    crate::connection::NatsMessage,
+    log::{debug, warn},
+    serde_json::Value,
+    std::{collections::BTreeMap, sync::Mutex},
+};
+
+/// A serialized transaction waiting for its block metadata
+struct PendingTransaction {
+    index: Option<usize>,
+    transaction: Value,
+    /// Messages about the transaction, only published along with its block
+    notifications: Vec<NatsMessage>,
+}
+
+/// The buffered transactions of a slot, along with the messages about them
+#[derive(Default)]
+pub struct AssembledSlot {
+    /// Transactions ordered by their index in the block
+    pub transactions: Vec<Value>,
+    pub notifications: Vec<NatsMessage>,
+}
+
+/// Buffers serialized transactions per slot until the block metadata arrives
+pub struct BlockAssembler {
+    pending: Mutex<BTreeMap<u64, Vec<PendingTransaction>>>,
+    max_buffered_slots: usize,
+}
+
+impl BlockAssembler {
+    /// Create a new block assembler buffering at most `max_buffered_slots` slots
+    pub fn new(max_buffered_slots: usize) -> Self {
+        Self {
+            pending: Mutex::new(BTreeMap::new()),
+            max_buffered_slots: max_buffered_slots.max(1),
+        }
+    }
+
+    /// Buffer a serialized transaction for the given slot, along with the messages to
+    /// publish once its block is
+    pub fn add_transaction(
+        &self,
+        slot: u64,
+        index: Option<usize>,
+        transaction: Value,
+        notifications: Vec<NatsMessage>,
+    ) {
+        let mut pending = self.pending.lock().unwrap();
+
+        pending.entry(slot).or_default().push(PendingTransaction {
+            index,
+            transaction,
+            notifications,
+        });
+
+        // Slots on abandoned forks never receive block metadata, so evict the oldest ones
+        while pending.len() > self.max_buffered_slots {
+            if let Some((evicted_slot, transactions)) = pending.pop_first() {
+                warn!(
+                    "Discarding {} buffered transactions for slot {evicted_slot}: no block metadata received",
+                    transactions.len()
+                );
+            }
+        }
+    }
+
+    /// Remove and return the buffered transactions for a slot, ordered by their index in the block
+    pub fn take_slot(&self, slot: u64) -> AssembledSlot {
+        let mut transactions = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(&slot)
+            .unwrap_or_default();
+
+        // Transactions without an index keep their arrival order
+        transactions.sort_by_key(|pending| pending.index.unwrap_or(usize::MAX));
+
+        debug!(
+            "Assembled {} transactions for slot {slot}",
+            transactions.len()
+        );
+
+        let mut assembled = AssembledSlot::default();
+        for pending in transactions {
+            assembled.transactions.push(pending.transaction);
+            assembled.notifications.extend(pending.notifications);
+        }
+        assembled
+    }
+
+    /// Number of slots currently buffered
+    pub fn buffered_slots(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
@@ -0,0 +1,93 @@
+use {
+    crate::config::CircuitBreakerConfig,
+    log::{info, warn},
+    solana_metrics::datapoint_warn,
+    std::{
+        sync::atomic::{AtomicU32, AtomicU64, Ordering},
+        time::{Duration, Instant},
+    },
+};
+
+/// Stops work from being queued for a cooldown after repeated connection or publish failures
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: AtomicU32,
+    /// Reference point of `opened_at`
+    created_at: Instant,
+    /// Nanoseconds after `created_at` the circuit opened at, 0 while it is closed, so
+    /// checking it on every transaction does not take a lock
+    opened_at: AtomicU64,
+}
+
+impl CircuitBreaker {
+    /// Create a circuit breaker, or `None` when it is disabled
+    pub fn new(config: &CircuitBreakerConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            consecutive_failures: AtomicU32::new(0),
+            created_at: Instant::now(),
+            opened_at: AtomicU64::new(0),
+        })
+    }
+
+    /// Record a failed connection attempt or publish, opening the circuit once the
+    /// threshold is reached; a failure after the cooldown opens it again right away
+    pub fn record_failure(&self) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < self.failure_threshold {
+            return;
+        }
+
+        let opened_at = self.opened_at.load(Ordering::Relaxed);
+        if self.is_open_since(opened_at) {
+            return;
+        }
+        // Of concurrent failures, only the one that opens the circuit reports it
+        let now = self.now().max(1);
+        if self
+            .opened_at
+            .compare_exchange(opened_at, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+
+        warn!(
+            "Opening circuit after {failures} consecutive NATS failures, dropping work for {:?}",
+            self.cooldown
+        );
+        datapoint_warn!(
+            "geyser_nats_circuit",
+            ("event", "opened", String),
+            ("failures", failures as i64, i64),
+        );
+    }
+
+    /// Record a successful connection or publish, closing the circuit
+    pub fn record_success(&self) {
+        if self.consecutive_failures.swap(0, Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        if self.opened_at.swap(0, Ordering::Relaxed) != 0 {
+            info!("Closing circuit, NATS publishing recovered");
+        }
+    }
+
+    /// Whether work should be dropped instead of serialized and queued
+    pub fn is_open(&self) -> bool {
+        self.is_open_since(self.opened_at.load(Ordering::Relaxed))
+    }
+
+    /// Whether a circuit opened at `opened_at` is still within its cooldown
+    fn is_open_since(&self, opened_at: u64) -> bool {
+        opened_at != 0 && Duration::from_nanos(self.now().saturating_sub(opened_at)) < self.cooldown
+    }
+
+    /// Nanoseconds since the circuit breaker was created
+    fn now(&self) -> u64 {
+        self.created_at.elapsed().as_nanos() as u64
+    }
+}
@@ -0,0 +1,92 @@
+use {
+    log::{debug, warn},
+    std::{collections::BTreeMap, sync::Mutex},
+};
+
+/// Commitment levels a published transaction is upgraded to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Commitment {
+    Confirmed,
+    Rooted,
+}
+
+impl Commitment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Confirmed => "confirmed",
+            Self::Rooted => "rooted",
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrackedSignatures {
+    signatures: Vec<String>,
+    confirmed: bool,
+}
+
+/// Tracks the signatures published at `processed` until their slot is rooted
+pub struct CommitmentTracker {
+    slots: Mutex<BTreeMap<u64, TrackedSignatures>>,
+    max_tracked_slots: usize,
+}
+
+impl CommitmentTracker {
+    /// Create a commitment tracker remembering at most `max_tracked_slots` slots
+    pub fn new(max_tracked_slots: usize) -> Self {
+        Self {
+            slots: Mutex::new(BTreeMap::new()),
+            max_tracked_slots: max_tracked_slots.max(1),
+        }
+    }
+
+    /// Record a transaction published at `processed` for a slot
+    pub fn record_transaction(&self, slot: u64, signature: String) {
+        let mut slots = self.slots.lock().unwrap();
+        slots.entry(slot).or_default().signatures.push(signature);
+
+        while slots.len() > self.max_tracked_slots {
+            if let Some((evicted_slot, tracked)) = slots.pop_first() {
+                warn!(
+                    "No longer tracking commitment of slot {evicted_slot} with {} transactions",
+                    tracked.signatures.len()
+                );
+            }
+        }
+    }
+
+    /// Upgrade a slot to a commitment level, returning its signatures the first time
+    pub fn upgrade(&self, slot: u64, commitment: Commitment) -> Option<Vec<String>> {
+        let mut slots = self.slots.lock().unwrap();
+
+        match commitment {
+            Commitment::Confirmed => {
+                let tracked = slots.get_mut(&slot)?;
+                if tracked.confirmed {
+                    return None;
+                }
+                tracked.confirmed = true;
+                Some(tracked.signatures.clone())
+            }
+            Commitment::Rooted => {
+                // Slots below a root are either rooted already or on a dropped fork
+                let remaining = slots.split_off(&slot);
+                if !slots.is_empty() {
+                    debug!("Forgetting {} slots below root {slot}", slots.len());
+                }
+                *slots = remaining;
+                slots.remove(&slot).map(|tracked| tracked.signatures)
+            }
+        }
+    }
+
+    /// Forget a slot that will never reach a higher commitment
+    pub fn forget(&self, slot: u64) {
+        self.slots.lock().unwrap().remove(&slot);
+    }
+
+    /// Number of slots currently tracked
+    pub fn tracked_slots(&self) -> usize {
+        self.slots.lock().unwrap().len()
+    }
+}
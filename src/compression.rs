@@ -0,0 +1,57 @@
+use {
+    crate::{
+        config::{CompressionAlgorithm, CompressionConfig},
+        connection::NatsMessage,
+        serializer::SerializationError,
+    },
+    flate2::{write::GzEncoder, Compression},
+    std::io::Write,
+};
+
+/// Compresses message payloads above a size threshold
+pub struct PayloadCompressor {
+    algorithm: CompressionAlgorithm,
+    min_bytes: usize,
+}
+
+impl PayloadCompressor {
+    /// Create a payload compressor, or `None` when compression is disabled
+    pub fn new(config: &CompressionConfig) -> Option<Self> {
+        match config.algorithm {
+            CompressionAlgorithm::None => None,
+            algorithm => Some(Self {
+                algorithm,
+                min_bytes: config.min_bytes,
+            }),
+        }
+    }
+
+    /// Compress the payload of a message large enough, marking it with `Content-Encoding`
+    pub fn compress(&self, mut message: NatsMessage) -> Result<NatsMessage, SerializationError> {
+        if message.payload.len() < self.min_bytes {
+            return Ok(message);
+        }
+
+        message.payload = self.compress_payload(&message.payload)?;
+        match self.algorithm.content_encoding() {
+            Some(encoding) => Ok(message.with_header("Content-Encoding", encoding)),
+            None => Ok(message),
+        }
+    }
+
+    /// Compress a payload with the configured algorithm
+    pub fn compress_payload(&self, payload: &[u8]) -> Result<Vec<u8>, SerializationError> {
+        let compressed = match self.algorithm {
+            CompressionAlgorithm::None => Ok(payload.to_vec()),
+            CompressionAlgorithm::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(payload).and_then(|()| encoder.finish())
+            }
+            CompressionAlgorithm::Zstd => zstd::encode_all(payload, 0),
+        };
+
+        compressed.map_err(|e| SerializationError::SerializationFailed {
+            msg: format!("Failed to compress payload: {e}"),
+        })
+    }
+}
@@ -0,0 +1,126 @@
+use {
+    crate::{
+        config::{ConfigurationManager, NatsPluginConfig},
+        processor::TransactionProcessor,
+    },
+    log::{error, info, warn},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// Watches the config file, and the address files it lists, and applies filter, route and
+/// transaction subject changes to the processor without tearing down the NATS connection
+pub struct ConfigWatcher {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Start checking the config file every `interval`, starting from the configuration
+    /// the plugin was loaded with
+    pub fn start(
+        path: PathBuf,
+        interval: Duration,
+        config: NatsPluginConfig,
+        processor: Arc<TransactionProcessor>,
+    ) -> Self {
+        info!("Watching {} for configuration changes", path.display());
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_clone = shutdown.clone();
+        let handle = thread::spawn(move || {
+            let mut config = config;
+            let mut modified = Self::modified(&path, &config);
+            let mut last_check = Instant::now();
+            while !shutdown_clone.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+                if last_check.elapsed() < interval {
+                    continue;
+                }
+                last_check = Instant::now();
+
+                let current = Self::modified(&path, &config);
+                if current == modified {
+                    continue;
+                }
+                modified = current;
+                if let Some(reloaded) = Self::reload(&path, &config, &processor) {
+                    config = reloaded;
+                    // The reloaded configuration can list other address files
+                    modified = Self::modified(&path, &config);
+                }
+            }
+        });
+
+        Self {
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// Modification times of the config file and the address files it lists, `None` for
+    /// files that cannot be read
+    fn modified(path: &Path, config: &NatsPluginConfig) -> Vec<Option<SystemTime>> {
+        std::iter::once(path)
+            .chain(config.address_files().map(Path::new))
+            .map(|path| {
+                fs::metadata(path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// Load the changed config file and apply it, keeping the current configuration when
+    /// the file is invalid
+    fn reload(
+        path: &Path,
+        config: &NatsPluginConfig,
+        processor: &TransactionProcessor,
+    ) -> Option<NatsPluginConfig> {
+        let path_str = path.to_str()?;
+        let reloaded = match ConfigurationManager::load_config(path_str) {
+            Ok(reloaded) => reloaded,
+            Err(e) => {
+                warn!("Ignoring changed configuration {path_str}: {e}");
+                return None;
+            }
+        };
+
+        if config.requires_restart(&reloaded) {
+            warn!(
+                "Configuration {path_str} changed settings besides the filter, routes and \
+                 transaction subjects, which take effect after a restart"
+            );
+        }
+        if let Err(e) = processor.reload_filters(&reloaded) {
+            warn!("Ignoring changed configuration {path_str}: {e}");
+            return None;
+        }
+        Some(reloaded)
+    }
+
+    /// Stop the watcher thread
+    pub fn stop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if let Err(e) = handle.join() {
+                error!("Error joining config watcher thread: {e:?}");
+            }
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -1,17 +1,43 @@
 use {
-    crossbeam_channel::{Receiver, Sender},
-    log::{debug, error, info},
+    crate::{
+        circuit_breaker::CircuitBreaker,
+        config::{
+            split_url_credentials, split_url_scheme, subject_matches, AuthConfig,
+            ConsumerAcksConfig, OverflowPolicy, PayloadOffloadConfig, RateLimitConfig,
+            SlotCheckpointConfig,
+        },
+        consumer_acks::ConsumerAcks,
+        lifecycle_events::LifecycleEvents,
+        payload_offload::PayloadOffloader,
+        rate_limiter::RateLimiter,
+        serializer::TransactionSerializer,
+        slot_checkpoint::SlotCheckpoint,
+        stats::PluginStats,
+    },
+    async_nats::{
+        connection::State, jetstream, Client, ConnectOptions, Event, HeaderMap, ServerAddr,
+        ServerError, Subscriber,
+    },
+    crossbeam_channel::{Receiver, SendTimeoutError, Sender, TrySendError},
+    futures::{future, stream::FuturesUnordered, FutureExt, StreamExt},
+    log::{debug, error, info, warn},
+    sha2::{Digest, Sha256},
+    solana_metrics::{datapoint_error, datapoint_info, datapoint_warn},
     std::{
-        io::{BufRead, BufReader, BufWriter, Write},
-        net::{SocketAddr, TcpStream, ToSocketAddrs},
+        any::Any,
+        future::Future,
+        net::{SocketAddr, ToSocketAddrs},
+        panic::{self, AssertUnwindSafe},
+        pin::Pin,
         sync::{
-            atomic::{AtomicBool, Ordering},
-            Arc,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex,
         },
         thread,
-        time::Duration,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
     },
     thiserror::Error,
+    tokio::sync::Notify,
 };
 
 #[derive(Error, Debug)]
@@ -29,16 +55,554 @@ pub enum ConnectionError {
     SendFailed { msg: String },
 }
 
+/// Header holding when a message was queued, in nanoseconds since the Unix epoch
+pub const ENQUEUED_AT_HEADER: &str = "Enqueued-At";
+
+/// Header holding when a message was handed to the client, in nanoseconds since the Unix epoch
+pub const PUBLISHED_AT_HEADER: &str = "Published-At";
+
+/// Header naming the network a message was published for
+pub const NETWORK_HEADER: &str = "Solana-Network";
+
+/// Header holding the hex SHA-256 digest of a message payload, as published
+pub const PAYLOAD_DIGEST_HEADER: &str = "Solana-Payload-Sha256";
+
 #[derive(Debug, Clone)]
 pub struct NatsMessage {
     pub subject: String,
     pub payload: Vec<u8>,
+    /// Headers sent with the message
+    pub headers: Vec<(String, String)>,
+    /// Slot the message belongs to, tracked per subject once published
+    pub slot: Option<u64>,
+    /// When the message was created, used to measure publish latency
+    pub created_at: Instant,
+}
+
+impl NatsMessage {
+    pub fn new(subject: String, payload: Vec<u8>) -> Self {
+        Self {
+            subject,
+            payload,
+            headers: Vec::new(),
+            slot: None,
+            created_at: Instant::now(),
+        }
+    }
+
+    /// Set the slot the message belongs to
+    pub fn with_slot(mut self, slot: u64) -> Self {
+        self.slot = Some(slot);
+        self
+    }
+
+    /// Add a header to the message
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Hex SHA-256 digest of the payload, as printed by `sha256sum`
+    pub fn payload_digest(&self) -> String {
+        format!("{:x}", Sha256::digest(&self.payload))
+    }
+
+    /// Set a header, replacing its value if the message already has it
+    pub fn set_header(&mut self, name: &str, value: String) {
+        match self.headers.iter_mut().find(|(header, _)| header == name) {
+            Some((_, current)) => *current = value,
+            None => self.headers.push((name.to_string(), value)),
+        }
+    }
+}
+
+/// Current time in nanoseconds since the Unix epoch, 19 digits wide until 2286 so a
+/// restamped header keeps its size
+fn unix_nanos() -> String {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// Handles requests received on subjects the connection subscribes to
+pub trait RequestHandler: Send + Sync {
+    /// Subjects to subscribe to once connected
+    fn subjects(&self) -> Vec<String>;
+
+    /// Handle a request received on `subject` with its headers, returning the reply payload
+    /// if any
+    fn handle_request(
+        &self,
+        subject: &str,
+        headers: &[(String, String)],
+        payload: &[u8],
+    ) -> Option<Vec<u8>>;
+}
+
+/// Options for a NATS connection
+#[derive(Clone)]
+pub struct ConnectionOptions {
+    /// Maximum number of connection retries
+    pub max_retries: u32,
+
+    /// Connection timeout in seconds
+    pub timeout_secs: u64,
+
+    /// Maximum number of queued messages, 0 for unbounded
+    pub queue_capacity: usize,
+
+    /// Queued bytes above which backpressure is signaled, 0 to disable
+    pub queue_high_watermark_bytes: u64,
+
+    /// What happens to a message queued while the queue is full
+    pub overflow_policy: OverflowPolicy,
+
+    /// How long `OverflowPolicy::BlockWithTimeout` waits for room in the queue, in milliseconds
+    pub overflow_timeout_ms: u64,
+
+    /// Interval in milliseconds between flushes of buffered publishes
+    pub flush_interval_ms: u64,
+
+    /// How long the worker waits for a message when the queue is empty before checking for
+    /// shutdown and ended sessions, in milliseconds; queued messages wake it up right away
+    pub poll_interval_ms: u64,
+
+    /// Flush once this many messages were published since the last flush, 0 for interval only
+    pub flush_batch_size: usize,
+
+    /// Whether messages carry `Enqueued-At` and `Published-At` headers
+    pub publish_timestamps: bool,
+
+    /// Limits on the publish rate
+    pub rate_limit: RateLimitConfig,
+
+    /// Subjects published through JetStream, waiting for the stream's acknowledgement
+    pub jetstream_subjects: Vec<String>,
+
+    /// Maximum number of JetStream publishes waiting for their acknowledgement
+    pub jetstream_max_in_flight: usize,
+
+    /// Times a JetStream publish that was not acknowledged is retried
+    pub jetstream_max_ack_retries: u32,
+
+    /// Subject receiving a reference to messages larger than the server's max_payload;
+    /// oversized messages are dropped when unset
+    pub oversize_subject: Option<String>,
+
+    /// How many times a dead connection worker is respawned before entering degraded mode
+    pub max_worker_restarts: u32,
+
+    /// Client name sent to the server in the CONNECT command
+    pub client_name: String,
+
+    /// Interval in seconds between client PINGs; a connection with more than two
+    /// PINGs unanswered is considered stale and re-established
+    pub ping_interval_secs: u64,
+
+    /// Credentials sent to the server in the CONNECT command
+    pub auth: AuthConfig,
+
+    /// Whether the connection must be upgraded to TLS
+    pub tls: bool,
+
+    /// KV entry the highest published slot is checkpointed to
+    pub slot_checkpoint: SlotCheckpointConfig,
+
+    /// Object store large payloads are uploaded to, publishing a pointer in their place
+    pub payload_offload: PayloadOffloadConfig,
+
+    /// Reply subjects consumers acknowledge core NATS messages on
+    pub consumer_acks: ConsumerAcksConfig,
+
+    /// Counters updated by the connection worker
+    pub stats: Arc<PluginStats>,
+
+    /// Optional circuit breaker told about connection and publish outcomes
+    pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+
+    /// Optional lifecycle events of the connection, published whenever it is up
+    pub lifecycle_events: Option<Arc<LifecycleEvents>>,
+
+    /// Handlers for requests received on subscribed subjects
+    pub request_handlers: Vec<Arc<dyn RequestHandler>>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            timeout_secs: 10,
+            queue_capacity: 0,
+            queue_high_watermark_bytes: 0,
+            overflow_policy: OverflowPolicy::default(),
+            overflow_timeout_ms: 100,
+            flush_interval_ms: 1,
+            poll_interval_ms: 100,
+            flush_batch_size: 0,
+            publish_timestamps: false,
+            rate_limit: RateLimitConfig::default(),
+            jetstream_subjects: Vec::new(),
+            jetstream_max_in_flight: 256,
+            jetstream_max_ack_retries: 3,
+            oversize_subject: None,
+            max_worker_restarts: 3,
+            client_name: "solana-geyser-nats".to_string(),
+            ping_interval_secs: 60,
+            auth: AuthConfig::default(),
+            tls: false,
+            slot_checkpoint: SlotCheckpointConfig::default(),
+            payload_offload: PayloadOffloadConfig::default(),
+            consumer_acks: ConsumerAcksConfig::default(),
+            stats: Arc::new(PluginStats::new()),
+            circuit_breaker: None,
+            lifecycle_events: None,
+            request_handlers: Vec::new(),
+        }
+    }
+}
+
+impl ConnectionOptions {
+    /// Report a failed connection attempt or publish to the circuit breaker
+    fn record_failure(&self) {
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.record_failure();
+        }
+    }
+
+    /// Report a successful connection or publish to the circuit breaker
+    fn record_success(&self) {
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.record_success();
+        }
+    }
+
+    /// Record a connection lifecycle event, published once the connection is up
+    fn record_event(
+        &self,
+        event: &str,
+        server: Option<&str>,
+        reason: Option<&str>,
+        attempt: Option<u32>,
+    ) {
+        if let Some(lifecycle_events) = &self.lifecycle_events {
+            lifecycle_events.record(event, server, reason, attempt);
+        }
+    }
+
+    /// Stamp a message with the time it is queued; `Published-At` is set too, so the
+    /// message size counted against the queue and max_payload already includes it
+    fn stamp_enqueued_at(&self, message: &mut NatsMessage) {
+        if self.publish_timestamps {
+            let now = unix_nanos();
+            message.set_header(ENQUEUED_AT_HEADER, now.clone());
+            message.set_header(PUBLISHED_AT_HEADER, now);
+        }
+    }
+
+    /// Stamp a message with the time it is handed to the client
+    fn stamp_published_at(&self, message: &mut NatsMessage) {
+        if self.publish_timestamps {
+            message.set_header(PUBLISHED_AT_HEADER, unix_nanos());
+        }
+    }
+
+    /// Account for a message entering the queue, signaling when the high watermark is crossed
+    fn record_enqueued(&self, size: usize) {
+        let queued_bytes = self.stats.add_queued_bytes(size as u64);
+        let watermark = self.queue_high_watermark_bytes;
+        if watermark > 0 && queued_bytes >= watermark && !self.stats.set_above_high_watermark(true)
+        {
+            warn!(
+                "Publish queue holds {queued_bytes} bytes, above the high watermark of \
+                 {watermark} bytes"
+            );
+            datapoint_warn!(
+                "geyser_nats_queue",
+                ("event", "above_high_watermark", String),
+                ("queued_bytes", queued_bytes as i64, i64),
+            );
+        }
+    }
+
+    /// Account for a message leaving the queue; the signal clears once the queue has
+    /// drained below half the high watermark, so it does not flap
+    fn record_dequeued(&self, size: usize) {
+        let queued_bytes = self.stats.remove_queued_bytes(size as u64);
+        let watermark = self.queue_high_watermark_bytes;
+        if watermark > 0
+            && queued_bytes < watermark / 2
+            && self.stats.set_above_high_watermark(false)
+        {
+            info!("Publish queue drained to {queued_bytes} bytes, below the high watermark");
+            datapoint_info!(
+                "geyser_nats_queue",
+                ("event", "below_high_watermark", String),
+                ("queued_bytes", queued_bytes as i64, i64),
+            );
+        }
+    }
+}
+
+/// The connection worker thread and its restart bookkeeping
+struct WorkerState {
+    handle: Option<thread::JoinHandle<()>>,
+    restarts: u32,
+    /// Attempts to leave degraded mode since the worker last connected
+    degraded_retries: u32,
+}
+
+/// What the worker thread reports back, readable without locking the worker state
+#[derive(Default)]
+struct WorkerFlags {
+    /// The worker thread has returned
+    exited: AtomicBool,
+    /// The worker connected at least once since it was spawned
+    connected: AtomicBool,
+}
+
+/// The JetStream-backed and acknowledgement-tracking helpers of a connection session
+struct JetStreamSession<'a> {
+    ack_window: AckWindow<'a>,
+    slot_checkpoint: Option<SlotCheckpoint>,
+    payload_offloader: Option<PayloadOffloader>,
+    consumer_acks: Option<ConsumerAcks>,
+}
+
+impl<'a> JetStreamSession<'a> {
+    async fn open(client: &Client, options: &'a ConnectionOptions) -> Self {
+        let timeout = Duration::from_secs(options.timeout_secs);
+
+        let slot_checkpoint = match &options.slot_checkpoint {
+            config if config.enabled => {
+                let key = config.key(&options.client_name);
+                let open = SlotCheckpoint::open(client, config, key, timeout);
+                Self::open_optional("Slot checkpoint", open, timeout).await
+            }
+            _ => None,
+        };
+        let payload_offloader = match &options.payload_offload {
+            config if config.enabled => {
+                let open = PayloadOffloader::open(client, config, timeout);
+                Self::open_optional("Payload offload", open, timeout).await
+            }
+            _ => None,
+        };
+        let consumer_acks = match &options.consumer_acks {
+            config if config.enabled => {
+                let open = ConsumerAcks::open(client, config, options.stats.clone());
+                Self::open_optional("Consumer acks", open, timeout).await
+            }
+            _ => None,
+        };
+
+        Self {
+            ack_window: AckWindow::new(client, options),
+            slot_checkpoint,
+            payload_offloader,
+            consumer_acks,
+        }
+    }
+
+    /// Open an optional helper; the session goes on without it when the server has no
+    /// JetStream, whose API requests are not bound by the context's timeout
+    async fn open_optional<T>(
+        name: &str,
+        open: impl Future<Output = Result<T, ConnectionError>>,
+        timeout: Duration,
+    ) -> Option<T> {
+        tokio::time::timeout(timeout, open)
+            .await
+            .unwrap_or_else(|_| {
+                Err(ConnectionError::SendFailed {
+                    msg: format!("Timed out after {timeout:?}"),
+                })
+            })
+            .inspect_err(|e| warn!("{name} disabled for this session: {e}"))
+            .ok()
+    }
+}
+
+/// A JetStream publish resolving to the message, its attempt and whether it was acknowledged
+type PendingAck =
+    Pin<Box<dyn Future<Output = (NatsMessage, u32, Result<(), ConnectionError>)> + Send>>;
+
+/// JetStream publishes waiting for their acknowledgement, bounded so persistence does not
+/// cost a round-trip per message
+struct AckWindow<'a> {
+    jetstream: jetstream::Context,
+    options: &'a ConnectionOptions,
+    pending: FuturesUnordered<PendingAck>,
+}
+
+impl<'a> AckWindow<'a> {
+    fn new(client: &Client, options: &'a ConnectionOptions) -> Self {
+        let mut jetstream = jetstream::new(client.clone());
+        jetstream.set_timeout(Duration::from_secs(options.timeout_secs));
+
+        Self {
+            jetstream,
+            options,
+            pending: FuturesUnordered::new(),
+        }
+    }
+
+    /// Publish a message once there is room in the window
+    async fn publish(&mut self, message: NatsMessage) {
+        while self.pending.len() >= self.options.jetstream_max_in_flight.max(1) {
+            self.settle_next().await;
+        }
+        self.send(message, 1).await;
+    }
+
+    /// Send a message and track its acknowledgement; a failed send is retried like a
+    /// missing acknowledgement
+    async fn send(&mut self, mut message: NatsMessage, attempt: u32) {
+        self.options.stamp_published_at(&mut message);
+        let subject = message.subject.clone();
+        let payload = message.payload.clone().into();
+        let publish = if message.headers.is_empty() {
+            self.jetstream.publish(subject.clone(), payload).await
+        } else {
+            let headers = ConnectionManager::header_map(&message.headers);
+            self.jetstream
+                .publish_with_headers(subject.clone(), headers, payload)
+                .await
+        };
+
+        let pending: PendingAck = match publish {
+            Ok(ack) => Box::pin(async move {
+                let result = ack
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| ConnectionError::SendFailed {
+                        msg: format!("JetStream did not acknowledge message on {subject}: {e}"),
+                    });
+                (message, attempt, result)
+            }),
+            Err(e) => Box::pin(future::ready((
+                message,
+                attempt,
+                Err(ConnectionError::SendFailed {
+                    msg: format!("Failed to publish message to JetStream subject {subject}: {e}"),
+                }),
+            ))),
+        };
+        self.pending.push(pending);
+    }
+
+    /// Settle the acknowledgements that already arrived, without waiting
+    async fn settle_ready(&mut self) {
+        while let Some(Some((message, attempt, result))) = self.pending.next().now_or_never() {
+            self.settle(message, attempt, result).await;
+        }
+    }
+
+    /// Wait for the next acknowledgement, returning false when none is pending
+    async fn settle_next(&mut self) -> bool {
+        match self.pending.next().await {
+            Some((message, attempt, result)) => {
+                self.settle(message, attempt, result).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Wait up to `timeout` for a message to be queued, settling the acknowledgements
+    /// arriving in the meantime
+    async fn wait(&mut self, queued: &Notify, timeout: Duration) {
+        let queued = queued.notified();
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(queued, deadline);
+
+        loop {
+            tokio::select! {
+                _ = &mut queued => return,
+                _ = &mut deadline => return,
+                Some((message, attempt, result)) = self.pending.next(),
+                    if !self.pending.is_empty() =>
+                {
+                    self.settle(message, attempt, result).await;
+                }
+            }
+        }
+    }
+
+    /// Wait for every pending acknowledgement
+    async fn settle_all(&mut self) {
+        while self.settle_next().await {}
+    }
+
+    /// Give up on the pending acknowledgements of a lost connection
+    fn abandon(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        warn!(
+            "Connection lost with {} JetStream publishes unacknowledged",
+            self.pending.len()
+        );
+        for _ in 0..self.pending.len() {
+            self.options.stats.record_publish_error();
+        }
+        self.pending.clear();
+    }
+
+    /// Record an acknowledged publish, or retry it while retries are left
+    async fn settle(
+        &mut self,
+        message: NatsMessage,
+        attempt: u32,
+        result: Result<(), ConnectionError>,
+    ) {
+        let stats = &self.options.stats;
+        match result {
+            Ok(()) => {
+                stats.record_message_acked();
+                stats.record_message_published(
+                    &message.subject,
+                    message.payload.len(),
+                    message.created_at.elapsed(),
+                );
+                if let Some(slot) = message.slot {
+                    stats.record_slot_published(&message.subject, slot);
+                }
+                self.options.record_success();
+            }
+            Err(e) if attempt <= self.options.jetstream_max_ack_retries => {
+                debug!("{e}, retrying (attempt {attempt})");
+                self.send(message, attempt + 1).await;
+            }
+            // A message the stream did not acknowledge is lost, but does not make the
+            // session unusable
+            Err(e) => {
+                warn!("{e}");
+                stats.record_publish_error();
+                stats.record_error(e.to_string());
+                self.options.record_failure();
+            }
+        }
+    }
 }
 
 pub struct ConnectionManager {
     sender: Sender<NatsMessage>,
+    receiver: Receiver<NatsMessage>,
+    /// Wakes the worker up as soon as a message is queued
+    queued: Arc<Notify>,
+    host_port: String,
+    options: ConnectionOptions,
     shutdown: Arc<AtomicBool>,
-    worker_handle: Option<thread::JoinHandle<()>>,
+    worker: Mutex<WorkerState>,
+    worker_flags: Arc<WorkerFlags>,
+    degraded: AtomicBool,
+    /// When to try leaving degraded mode, in milliseconds since `created_at`
+    degraded_retry_at: AtomicU64,
+    created_at: Instant,
+    stats: Arc<PluginStats>,
 }
 
 impl ConnectionManager {
@@ -48,30 +612,272 @@ impl ConnectionManager {
         max_retries: u32,
         timeout_secs: u64,
     ) -> Result<Self, ConnectionError> {
+        Self::with_options(
+            nats_url,
+            ConnectionOptions {
+                max_retries,
+                timeout_secs,
+                ..ConnectionOptions::default()
+            },
+        )
+    }
+
+    /// Create a new connection with the specified NATS server address and options
+    pub fn with_options(
+        nats_url: &str,
+        mut options: ConnectionOptions,
+    ) -> Result<Self, ConnectionError> {
+        // Credentials embedded in the URL must never end up in logs
+        let (nats_url, url_auth) = split_url_credentials(nats_url);
+        if let Some(url_auth) = url_auth {
+            options.auth.merge(url_auth);
+        }
         info!("Creating NATS connection to: {nats_url}");
 
-        let addr = Self::resolve_nats_address(nats_url)?;
-        let (sender, receiver) = crossbeam_channel::unbounded::<NatsMessage>();
+        // Resolve once up front so a misconfigured host fails plugin loading
+        let host_port = Self::nats_host_port(&nats_url)?;
+        Self::resolve_addresses(&host_port)?;
+        let (sender, receiver) = match options.queue_capacity {
+            0 => crossbeam_channel::unbounded::<NatsMessage>(),
+            capacity => crossbeam_channel::bounded::<NatsMessage>(capacity),
+        };
+        let queued = Arc::new(Notify::new());
         let shutdown = Arc::new(AtomicBool::new(false));
-        let shutdown_clone = shutdown.clone();
 
-        // Spawn worker thread to handle NATS connection
-        let worker_handle = thread::spawn(move || {
-            Self::connection_worker(addr, receiver, shutdown_clone, max_retries, timeout_secs);
-        });
+        let worker_flags = Arc::new(WorkerFlags::default());
+        let worker_handle = Self::spawn_worker(
+            &host_port,
+            &receiver,
+            &queued,
+            &shutdown,
+            &worker_flags,
+            &options,
+        );
 
         info!("NATS connection created successfully");
 
         Ok(Self {
             sender,
+            receiver,
+            queued,
+            host_port,
+            stats: options.stats.clone(),
+            options,
             shutdown,
-            worker_handle: Some(worker_handle),
+            worker: Mutex::new(WorkerState {
+                handle: Some(worker_handle),
+                restarts: 0,
+                degraded_retries: 0,
+            }),
+            worker_flags,
+            degraded: AtomicBool::new(false),
+            degraded_retry_at: AtomicU64::new(0),
+            created_at: Instant::now(),
         })
     }
 
-    /// Resolve NATS URL to socket address
-    fn resolve_nats_address(nats_url: &str) -> Result<SocketAddr, ConnectionError> {
-        let host_port = nats_url.replace("nats://", "");
+    /// Spawn a worker thread to handle the NATS connection
+    fn spawn_worker(
+        host_port: &str,
+        receiver: &Receiver<NatsMessage>,
+        queued: &Arc<Notify>,
+        shutdown: &Arc<AtomicBool>,
+        flags: &Arc<WorkerFlags>,
+        options: &ConnectionOptions,
+    ) -> thread::JoinHandle<()> {
+        let host_port = host_port.to_string();
+        let receiver = receiver.clone();
+        let queued = queued.clone();
+        let shutdown = shutdown.clone();
+        let flags = flags.clone();
+        let options = options.clone();
+        flags.exited.store(false, Ordering::Relaxed);
+        flags.connected.store(false, Ordering::Relaxed);
+
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                Self::connection_worker(&host_port, receiver, &queued, shutdown, &flags, &options);
+            }));
+            if let Err(payload) = result {
+                let message = Self::panic_message(payload.as_ref());
+                error!("NATS connection worker thread panicked: {message}");
+                options
+                    .stats
+                    .record_error(format!("NATS connection worker panicked: {message}"));
+            }
+            flags.exited.store(true, Ordering::Release);
+        })
+    }
+
+    /// The message a panic was raised with, if it carried one
+    fn panic_message(payload: &(dyn Any + Send)) -> &str {
+        payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic payload")
+    }
+
+    /// Whether the worker has exited, or degraded mode is due for another attempt
+    fn worker_needs_attention(&self) -> bool {
+        if self.is_degraded() {
+            self.elapsed_ms() >= self.degraded_retry_at.load(Ordering::Relaxed)
+        } else {
+            self.worker_flags.exited.load(Ordering::Acquire)
+        }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.created_at.elapsed().as_millis() as u64
+    }
+
+    /// Respawn the worker if it has exited, or enter degraded mode once out of restarts
+    ///
+    /// A worker that connected earns back the full restart budget, and degraded mode
+    /// spawns a worker again after a backoff in case the server came back.
+    fn ensure_worker(&self) {
+        if self.shutdown.load(Ordering::Relaxed) || !self.worker_needs_attention() {
+            return;
+        }
+
+        let mut worker = self.worker.lock().unwrap();
+        // Another sender may have handled it while we waited for the lock
+        if !self.worker_needs_attention() {
+            return;
+        }
+
+        if let Some(handle) = worker.handle.take() {
+            if let Err(payload) = handle.join() {
+                error!(
+                    "Error joining NATS connection worker thread: {}",
+                    Self::panic_message(payload.as_ref())
+                );
+            }
+        }
+
+        if self.worker_flags.connected.swap(false, Ordering::Relaxed) {
+            worker.restarts = 0;
+            worker.degraded_retries = 0;
+        }
+
+        if self.is_degraded() {
+            self.degraded.store(false, Ordering::Relaxed);
+            self.stats.set_degraded(false);
+            info!(
+                "Leaving degraded mode, respawning the NATS connection worker (attempt {})",
+                worker.degraded_retries
+            );
+            self.stats.record_worker_restart();
+            datapoint_info!(
+                "geyser_nats_worker",
+                ("event", "degraded_retry", String),
+                ("attempt", worker.degraded_retries as i64, i64),
+            );
+            worker.handle = Some(self.respawn_worker());
+        } else if worker.restarts < self.options.max_worker_restarts {
+            worker.restarts += 1;
+            warn!(
+                "NATS connection worker exited, restarting it (restart {}/{})",
+                worker.restarts, self.options.max_worker_restarts
+            );
+            self.stats.record_worker_restart();
+            datapoint_warn!(
+                "geyser_nats_worker",
+                ("event", "restarted", String),
+                ("restarts", worker.restarts as i64, i64),
+            );
+            worker.handle = Some(self.respawn_worker());
+        } else {
+            self.enter_degraded_mode(&mut worker);
+        }
+    }
+
+    fn respawn_worker(&self) -> thread::JoinHandle<()> {
+        Self::spawn_worker(
+            &self.host_port,
+            &self.receiver,
+            &self.queued,
+            &self.shutdown,
+            &self.worker_flags,
+            &self.options,
+        )
+    }
+
+    /// Stop queueing messages for a connection nobody is draining
+    ///
+    /// The next attempt to leave degraded mode backs off exponentially, like connection
+    /// attempts do.
+    fn enter_degraded_mode(&self, worker: &mut WorkerState) {
+        let retry_in = Duration::from_secs(2_u64.pow(worker.degraded_retries.min(5)));
+        worker.degraded_retries += 1;
+        self.degraded_retry_at.store(
+            self.elapsed_ms() + retry_in.as_millis() as u64,
+            Ordering::Relaxed,
+        );
+        self.degraded.store(true, Ordering::Relaxed);
+        self.stats.set_degraded(true);
+
+        // Messages already queued will never be published
+        let mut dropped = 0;
+        while let Ok(message) = self.receiver.try_recv() {
+            self.options.record_dequeued(Self::message_size(&message));
+            self.stats.record_message_dropped();
+            dropped += 1;
+        }
+
+        error!(
+            "NATS connection worker is dead after {} restarts, entering degraded mode: \
+             dropped {dropped} queued messages, new messages will be dropped until a \
+             retry in {retry_in:?}",
+            self.options.max_worker_restarts
+        );
+        datapoint_error!(
+            "geyser_nats_worker",
+            ("event", "degraded", String),
+            ("dropped", dropped, i64),
+        );
+    }
+
+    /// Wait until every queued message has been written, up to `timeout`
+    ///
+    /// Returns whether the queue was fully drained. Gives up early when the
+    /// connection is down, as nothing would drain the queue anyway.
+    pub fn drain(&self, timeout: Duration) -> bool {
+        let started = Instant::now();
+        while !self.receiver.is_empty() {
+            if self.is_degraded() || !self.stats.is_connected() || started.elapsed() >= timeout {
+                warn!(
+                    "Stopped draining NATS queue with {} messages left",
+                    self.queue_depth()
+                );
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+
+    /// Whether the connection gave up and is dropping all messages
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Whether repeated failures opened the circuit, so work should be dropped
+    pub fn is_circuit_open(&self) -> bool {
+        self.options
+            .circuit_breaker
+            .as_ref()
+            .is_some_and(|circuit_breaker| circuit_breaker.is_open())
+    }
+
+    /// Extract the `host:port` to resolve from a NATS URL
+    fn nats_host_port(nats_url: &str) -> Result<String, ConnectionError> {
+        let nats_url_without_credentials = split_url_credentials(nats_url).0;
+        let Some((_, host_port, _)) = split_url_scheme(&nats_url_without_credentials) else {
+            return Err(ConnectionError::HostResolutionFailed {
+                msg: format!("Invalid NATS URL format: {nats_url}"),
+            });
+        };
         let parts: Vec<&str> = host_port.split(':').collect();
 
         if parts.len() != 2 {
@@ -87,137 +893,606 @@ impl ConnectionManager {
                 msg: format!("Invalid port number: {e}"),
             })?;
 
-        info!("Resolving NATS host: {host} port: {port}");
+        Ok(format!("{host}:{port}"))
+    }
+
+    /// Resolve a `host:port` to all of its A/AAAA records
+    fn resolve_addresses(host_port: &str) -> Result<Vec<SocketAddr>, ConnectionError> {
+        info!("Resolving NATS host: {host_port}");
 
-        let addr = format!("{host}:{port}")
+        let addrs: Vec<SocketAddr> = host_port
             .to_socket_addrs()
             .map_err(|e| ConnectionError::HostResolutionFailed {
-                msg: format!("Failed to resolve hostname {host}: {e}"),
+                msg: format!("Failed to resolve hostname {host_port}: {e}"),
             })?
-            .next()
-            .ok_or_else(|| ConnectionError::HostResolutionFailed {
-                msg: format!("No addresses found for hostname: {host}"),
-            })?;
+            .collect();
+        if addrs.is_empty() {
+            return Err(ConnectionError::HostResolutionFailed {
+                msg: format!("No addresses found for hostname: {host_port}"),
+            });
+        }
 
-        Ok(addr)
+        Ok(addrs)
     }
 
     /// Send a message through the NATS connection
     pub fn send_message(&self, message: NatsMessage) -> Result<(), ConnectionError> {
-        self.sender
-            .send(message)
-            .map_err(|e| ConnectionError::SendFailed {
-                msg: format!("Failed to queue message: {e}"),
-            })
+        if self.shutdown.load(Ordering::Relaxed) {
+            return Err(ConnectionError::SendFailed {
+                msg: "Failed to queue message: connection manager is shut down".to_string(),
+            });
+        }
+
+        self.ensure_worker();
+        if self.is_degraded() {
+            self.stats.record_message_dropped();
+            return Ok(());
+        }
+
+        let mut message = message;
+        self.options.stamp_enqueued_at(&mut message);
+        let size = Self::message_size(&message);
+        loop {
+            match self.sender.try_send(message) {
+                Ok(()) => {
+                    self.record_queued(size);
+                    return Ok(());
+                }
+                Err(TrySendError::Full(full)) => match self.options.overflow_policy {
+                    OverflowPolicy::DropNewest => return self.drop_newest(full),
+                    OverflowPolicy::DropOldest => {
+                        if let Ok(oldest) = self.receiver.try_recv() {
+                            self.options.record_dequeued(Self::message_size(&oldest));
+                            self.stats.record_message_dropped();
+                            debug!(
+                                "Publish queue full, dropping oldest message for {}",
+                                oldest.subject
+                            );
+                        }
+                        message = full;
+                    }
+                    OverflowPolicy::BlockWithTimeout => {
+                        let timeout = Duration::from_millis(self.options.overflow_timeout_ms);
+                        return match self.sender.send_timeout(full, timeout) {
+                            Ok(()) => {
+                                self.record_queued(size);
+                                Ok(())
+                            }
+                            Err(SendTimeoutError::Timeout(full)) => self.drop_newest(full),
+                            Err(SendTimeoutError::Disconnected(_)) => {
+                                Err(Self::disconnected_queue_error())
+                            }
+                        };
+                    }
+                },
+                Err(TrySendError::Disconnected(_)) => return Err(Self::disconnected_queue_error()),
+            }
+        }
+    }
+
+    /// Account for a queued message and wake the worker up to publish it
+    fn record_queued(&self, size: usize) {
+        self.stats.record_message_queued();
+        self.options.record_enqueued(size);
+        self.queued.notify_one();
+    }
+
+    /// Drop a message that found the queue full
+    fn drop_newest(&self, message: NatsMessage) -> Result<(), ConnectionError> {
+        self.stats.record_message_dropped();
+        debug!(
+            "Publish queue full, dropping message for {}",
+            message.subject
+        );
+        Ok(())
+    }
+
+    fn disconnected_queue_error() -> ConnectionError {
+        ConnectionError::SendFailed {
+            msg: "Failed to queue message: sending on a disconnected channel".to_string(),
+        }
+    }
+
+    /// Number of messages waiting to be published
+    pub fn queue_depth(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// Get the counters shared with the connection worker
+    pub fn stats(&self) -> Arc<PluginStats> {
+        self.stats.clone()
     }
 
     /// Worker thread that maintains the NATS connection and processes messages
     fn connection_worker(
-        addr: SocketAddr,
+        host_port: &str,
         receiver: Receiver<NatsMessage>,
+        queued: &Notify,
         shutdown: Arc<AtomicBool>,
-        max_retries: u32,
-        timeout_secs: u64,
+        flags: &WorkerFlags,
+        options: &ConnectionOptions,
     ) {
         let mut retry_count = 0;
-        let timeout = Duration::from_secs(timeout_secs);
+        let max_retries = options.max_retries;
+        // Cluster peers advertised by the last server, to fail over to
+        let mut discovered_servers = Vec::new();
+        let mut addrs = Vec::new();
+        let mut connected_before = false;
+
+        // Zero retries means retrying forever
+        while !shutdown.load(Ordering::Relaxed) && (max_retries == 0 || retry_count < max_retries) {
+            // One runtime per session: the client reconnects on its own in background tasks,
+            // which must end with the session so reconnects stay bounded by `max_retries`
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    error!("Failed to create NATS client runtime: {e}");
+                    options
+                        .stats
+                        .record_error(format!("Failed to create NATS client runtime: {e}"));
+                    break;
+                }
+            };
 
-        while !shutdown.load(Ordering::Relaxed) && retry_count < max_retries {
-            match TcpStream::connect_timeout(&addr, timeout) {
-                Ok(stream) => {
-                    info!("Connected to NATS server at {addr}");
+            // Re-resolve on every attempt so DNS-based failover takes effect, keeping the
+            // previous addresses while DNS is unavailable
+            match Self::resolve_addresses(host_port) {
+                Ok(resolved) => addrs = resolved,
+                Err(e) => warn!("{e}, trying the previously resolved addresses"),
+            }
+
+            let end_session = Arc::new(Mutex::new(None));
+            match runtime.block_on(Self::connect(
+                host_port,
+                &addrs,
+                &discovered_servers,
+                options,
+                end_session.clone(),
+            )) {
+                Ok(client) => {
+                    info!("Connected to NATS server at {host_port}");
                     retry_count = 0; // Reset retry count on successful connection
+                    flags.connected.store(true, Ordering::Relaxed);
+                    options.record_success();
+                    if connected_before {
+                        options.stats.record_reconnect();
+                    }
+                    connected_before = true;
+                    discovered_servers = client.server_info().connect_urls;
 
-                    if let Err(e) = Self::handle_connection(stream, &receiver, &shutdown) {
+                    let result = runtime.block_on(Self::handle_connection(
+                        client,
+                        &receiver,
+                        queued,
+                        &shutdown,
+                        options,
+                        &end_session,
+                    ));
+                    options.stats.set_connected(false);
+                    if let Err(e) = result {
                         error!("NATS connection error: {e}");
+                        options.stats.record_error(e.to_string());
                     }
                 }
                 Err(e) => {
-                    retry_count += 1;
-                    error!("Failed to connect to NATS (attempt {retry_count}/{max_retries}): {e}");
+                    retry_count = retry_count.saturating_add(1);
+                    options.stats.record_connection_failure();
+                    options.record_failure();
+                    if max_retries == 0 {
+                        error!("Failed to connect to NATS (attempt {retry_count}): {e}");
+                    } else {
+                        error!(
+                            "Failed to connect to NATS (attempt {retry_count}/{max_retries}): {e}"
+                        );
+                    }
+                    options.stats.record_error(e.to_string());
 
-                    if retry_count < max_retries {
-                        thread::sleep(Duration::from_secs(2_u64.pow(retry_count.min(5))));
+                    if max_retries == 0 || retry_count < max_retries {
+                        options.record_event(
+                            "reconnecting",
+                            Some(host_port),
+                            Some(&e.to_string()),
+                            Some(retry_count),
+                        );
+                        Self::backoff(retry_count, &shutdown);
+                    } else {
+                        options.record_event(
+                            "gave_up",
+                            Some(host_port),
+                            Some(&e.to_string()),
+                            Some(retry_count),
+                        );
                     }
                 }
             }
         }
 
-        if retry_count >= max_retries {
+        if max_retries > 0 && retry_count >= max_retries {
             error!("Max connection retries ({max_retries}) exceeded. Giving up.");
         }
 
         info!("NATS connection worker thread shutting down");
     }
 
+    /// Wait before the next connection attempt, with exponential backoff and jitter so
+    /// validators do not reconnect in lockstep after an outage
+    fn backoff(retry_count: u32, shutdown: &AtomicBool) {
+        let backoff = Duration::from_secs(2_u64.pow(retry_count.min(5)));
+        let delay = backoff / 2 + backoff.mul_f64(rand::random::<f64>() / 2.0);
+
+        // Sleep in short steps so shutdown is not held up by a long backoff
+        let started = Instant::now();
+        while !shutdown.load(Ordering::Relaxed) && started.elapsed() < delay {
+            thread::sleep(Duration::from_millis(100).min(delay.saturating_sub(started.elapsed())));
+        }
+    }
+
+    /// Connect a NATS client to the server or one of its discovered cluster peers,
+    /// storing in `end_session` why the session should end early
+    async fn connect(
+        host_port: &str,
+        addrs: &[SocketAddr],
+        discovered_servers: &[String],
+        options: &ConnectionOptions,
+        end_session: Arc<Mutex<Option<String>>>,
+    ) -> Result<Client, ConnectionError> {
+        let timeout = Duration::from_secs(options.timeout_secs);
+        let stats = options.stats.clone();
+        let mut connect_options = ConnectOptions::new();
+        if let Some(token) = &options.auth.token {
+            connect_options = connect_options.token(token.clone());
+        }
+        if let (Some(username), Some(password)) = (&options.auth.username, &options.auth.password) {
+            connect_options = connect_options.user_and_password(username.clone(), password.clone());
+        }
+        if let Some(path) = &options.auth.nkey_seed_file {
+            // Read on every attempt so a rotated seed is picked up on reconnect
+            let seed =
+                std::fs::read_to_string(path).map_err(|e| ConnectionError::ConnectionFailed {
+                    msg: format!("Failed to read NKey seed file {path}: {e}"),
+                })?;
+            connect_options = connect_options.nkey(seed.trim().to_string());
+        }
+        if let Some(path) = &options.auth.credentials_file {
+            connect_options = connect_options
+                .credentials_file(path.into())
+                .await
+                .map_err(|e| ConnectionError::ConnectionFailed {
+                    msg: format!("Failed to load NATS credentials file {path}: {e}"),
+                })?;
+        }
+        let connect_options = connect_options
+            .require_tls(options.tls)
+            .name(&options.client_name)
+            .connection_timeout(timeout)
+            .ping_interval(Duration::from_secs(options.ping_interval_secs))
+            .flush_interval(Duration::from_millis(options.flush_interval_ms))
+            .event_callback(move |event| {
+                let stats = stats.clone();
+                let end_session = end_session.clone();
+                async move {
+                    match event {
+                        Event::ServerError(e) => {
+                            warn!("NATS server error: {e}");
+                            stats.record_server_error();
+                            stats.record_error(format!("NATS server error: {e}"));
+                            datapoint_error!(
+                                "geyser_nats_server_error",
+                                ("error", e.to_string(), String),
+                            );
+                            if Self::is_fatal_server_error(&e) {
+                                *end_session.lock().unwrap() =
+                                    Some(format!("NATS server error: {e}"));
+                            }
+                        }
+                        Event::LameDuckMode => {
+                            // Move to another server before this one closes the connection
+                            warn!("NATS server entered lame duck mode, reconnecting");
+                            *end_session.lock().unwrap() =
+                                Some("NATS server entered lame duck mode".to_string());
+                        }
+                        Event::ClientError(e) => {
+                            warn!("NATS client error: {e}");
+                            stats.record_error(format!("NATS client error: {e}"));
+                        }
+                        event => debug!("NATS connection event: {event}"),
+                    }
+                }
+            });
+
+        // Every address of the configured host comes first, peers are tried when they are
+        // unreachable. TLS connects by host name, which the server certificate is verified
+        // against, trying each of its addresses in turn
+        let server_urls = match options.tls {
+            true => vec![format!("tls://{host_port}")],
+            false => addrs.iter().map(|addr| format!("nats://{addr}")).collect(),
+        };
+        let mut servers = server_urls
+            .iter()
+            .map(|url| {
+                url.parse::<ServerAddr>()
+                    .map_err(|e| ConnectionError::ConnectionFailed {
+                        msg: format!("Invalid NATS server address {url}: {e}"),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        servers.extend(
+            discovered_servers
+                .iter()
+                .filter_map(|url| url.parse::<ServerAddr>().ok()),
+        );
+        if servers.is_empty() {
+            return Err(ConnectionError::ConnectionFailed {
+                msg: "No NATS server addresses to connect to".to_string(),
+            });
+        }
+        let connect_options = connect_options.retain_servers_order();
+
+        // The whole handshake is bounded, so a server that accepts but never answers
+        // cannot block the worker (and therefore shutdown) forever
+        tokio::time::timeout(timeout, connect_options.connect(servers))
+            .await
+            .map_err(|_| ConnectionError::ConnectionFailed {
+                msg: format!("Timed out connecting to NATS after {timeout:?}"),
+            })?
+            .map_err(|e| ConnectionError::ConnectionFailed {
+                msg: format!("Failed to connect to NATS: {e}"),
+            })
+    }
+
+    /// Whether a server error leaves the session unusable; the server keeps the
+    /// connection open after permission violations, so those are only reported
+    fn is_fatal_server_error(error: &ServerError) -> bool {
+        match error {
+            ServerError::AuthorizationViolation => true,
+            ServerError::SlowConsumer(_) => false,
+            ServerError::Other(reason) => !reason.starts_with("permissions violation"),
+        }
+    }
+
     /// Handle a single NATS connection session
-    fn handle_connection(
-        stream: TcpStream,
+    async fn handle_connection(
+        client: Client,
         receiver: &Receiver<NatsMessage>,
+        queued: &Notify,
         shutdown: &Arc<AtomicBool>,
+        options: &ConnectionOptions,
+        end_session: &Mutex<Option<String>>,
     ) -> Result<(), ConnectionError> {
-        let mut reader =
-            BufReader::new(
-                stream
-                    .try_clone()
-                    .map_err(|e| ConnectionError::ConnectionLost {
-                        msg: format!("Failed to clone stream: {e}"),
-                    })?,
-            );
-        let mut writer = BufWriter::new(stream);
+        let timeout = Duration::from_secs(options.timeout_secs);
+        options.stats.set_connected(true);
+
+        let server_info = client.server_info();
+        let server = format!("{}:{}", server_info.host, server_info.port);
+        options.record_event("connected", Some(&server), None, None);
+        Self::publish_lifecycle_events(&client, options, timeout).await?;
 
-        // Send CONNECT command
-        Self::write_command(
-            &mut writer,
-            "CONNECT {\"verbose\":false,\"pedantic\":false,\"name\":\"solana-geyser-nats\"}",
+        // Serve requests on the request handlers' subjects for the lifetime of this session
+        for handler in &options.request_handlers {
+            for subject in handler.subjects() {
+                let subscriber = client.subscribe(subject.clone()).await.map_err(|e| {
+                    ConnectionError::ConnectionLost {
+                        msg: format!("Failed to subscribe to {subject}: {e}"),
+                    }
+                })?;
+                tokio::spawn(Self::serve_requests(
+                    client.clone(),
+                    subscriber,
+                    handler.clone(),
+                ));
+            }
+        }
+
+        let result =
+            Self::process_messages(&client, receiver, queued, shutdown, options, end_session).await;
+
+        // An event about a lost connection waits for the next one
+        match &result {
+            Ok(()) => {
+                options.record_event("disconnected", Some(&server), Some("shutdown"), None);
+                if let Err(e) = Self::publish_lifecycle_events(&client, options, timeout).await {
+                    debug!("{e}");
+                }
+            }
+            Err(e) => {
+                options.record_event("disconnected", Some(&server), Some(&e.to_string()), None)
+            }
+        }
+
+        // Write out what the client still buffers before the session ends
+        if let Err(e) = Self::flush(&client, timeout).await {
+            debug!("{e}");
+        }
+
+        result
+    }
+
+    /// Publish the pending lifecycle events, keeping the ones not published for later
+    async fn publish_lifecycle_events(
+        client: &Client,
+        options: &ConnectionOptions,
+        timeout: Duration,
+    ) -> Result<(), ConnectionError> {
+        let Some(lifecycle_events) = &options.lifecycle_events else {
+            return Ok(());
+        };
+
+        let mut events = lifecycle_events.take().into_iter();
+        while let Some(event) = events.next() {
+            let subject = event.subject.clone();
+            let size = event.payload.len();
+            if let Err(e) = Self::publish_message(
+                client,
+                subject.clone(),
+                None,
+                Vec::new(),
+                event.payload.clone(),
+                timeout,
+            )
+            .await
+            {
+                lifecycle_events.restore(std::iter::once(event).chain(events).collect());
+                options.stats.record_publish_error();
+                return Err(e);
+            }
+            options
+                .stats
+                .record_message_published(&subject, size, event.created_at.elapsed());
+        }
+
+        Ok(())
+    }
+
+    /// Publish queued messages until shutdown or the connection is lost
+    async fn process_messages(
+        client: &Client,
+        receiver: &Receiver<NatsMessage>,
+        queued: &Notify,
+        shutdown: &Arc<AtomicBool>,
+        options: &ConnectionOptions,
+        end_session: &Mutex<Option<String>>,
+    ) -> Result<(), ConnectionError> {
+        let mut session = JetStreamSession::open(client, options).await;
+        let result = Self::publish_queued(
+            client,
+            receiver,
+            queued,
+            shutdown,
+            options,
+            end_session,
+            &mut session,
         )
-        .map_err(|e| ConnectionError::ConnectionLost {
-            msg: format!("Failed to send CONNECT command: {e}"),
-        })?;
-
-        // Send initial PING
-        Self::write_command(&mut writer, "PING").map_err(|e| ConnectionError::ConnectionLost {
-            msg: format!("Failed to send initial PING: {e}"),
-        })?;
-        writer
-            .flush()
-            .map_err(|e| ConnectionError::ConnectionLost {
-                msg: format!("Failed to flush initial commands: {e}"),
-            })?;
+        .await;
 
-        // Read initial responses
-        Self::read_response(&mut reader)?;
+        // Acknowledgements never arrive over a lost connection
+        match result {
+            Ok(()) => {
+                session.ack_window.settle_all().await;
+                if let Some(slot_checkpoint) = &mut session.slot_checkpoint {
+                    slot_checkpoint.write(&options.stats).await;
+                }
+            }
+            Err(_) => session.ack_window.abandon(),
+        }
+        if let Some(consumer_acks) = &session.consumer_acks {
+            consumer_acks.abandon();
+        }
+        result
+    }
 
-        // Main message processing loop
-        let mut last_ping = std::time::Instant::now();
-        let ping_interval = Duration::from_secs(30);
+    /// Publish queued messages, JetStream ones through the acknowledgement window
+    async fn publish_queued(
+        client: &Client,
+        receiver: &Receiver<NatsMessage>,
+        queued: &Notify,
+        shutdown: &Arc<AtomicBool>,
+        options: &ConnectionOptions,
+        end_session: &Mutex<Option<String>>,
+        session: &mut JetStreamSession<'_>,
+    ) -> Result<(), ConnectionError> {
+        let stats = &options.stats;
+        let timeout = Duration::from_secs(options.timeout_secs);
+        let max_payload = client.server_info().max_payload;
+        let mut rate_limiter = RateLimiter::new(&options.rate_limit);
+        let mut unflushed = 0;
 
         while !shutdown.load(Ordering::Relaxed) {
-            // Process any queued messages
+            session.ack_window.settle_ready().await;
+            if let Some(slot_checkpoint) = &mut session.slot_checkpoint {
+                slot_checkpoint.update(stats).await;
+            }
+            if let Some(consumer_acks) = &session.consumer_acks {
+                consumer_acks.expire();
+            }
+
+            if client.connection_state() == State::Disconnected {
+                return Err(ConnectionError::ConnectionLost {
+                    msg: "Connection closed by server".to_string(),
+                });
+            }
+
+            // Reconnect rather than keep writing to a session the server rejected or is
+            // about to close
+            if let Some(reason) = end_session.lock().unwrap().take() {
+                return Err(ConnectionError::ConnectionLost { msg: reason });
+            }
+
             match receiver.try_recv() {
                 Ok(msg) => {
-                    Self::write_publish_message(&mut writer, &msg).map_err(|e| {
-                        ConnectionError::SendFailed {
-                            msg: format!("Failed to publish message: {e}"),
+                    options.record_dequeued(Self::message_size(&msg));
+
+                    let msg = match &session.payload_offloader {
+                        Some(offloader) => {
+                            Self::offload_payload(offloader, msg, max_payload, options).await
+                        }
+                        None => msg,
+                    };
+                    if max_payload > 0 && Self::message_size(&msg) > max_payload {
+                        Self::handle_oversized_message(client, msg, max_payload, options, timeout)
+                            .await?;
+                        continue;
+                    }
+
+                    // Messages wait in the queue while throttled, where the overflow policy
+                    // applies once it fills up
+                    if let Some(rate_limiter) = &mut rate_limiter {
+                        let delay = rate_limiter.acquire(Self::message_size(&msg), Instant::now());
+                        if !delay.is_zero() {
+                            stats.record_message_throttled();
+                            tokio::time::sleep(delay).await;
                         }
+                    }
+
+                    // Published once acknowledged
+                    if Self::uses_jetstream(&msg.subject, options) {
+                        session.ack_window.publish(msg).await;
+                        continue;
+                    }
+
+                    let mut msg = msg;
+                    options.stamp_published_at(&mut msg);
+                    let subject = msg.subject.clone();
+                    let size = msg.payload.len();
+                    let created_at = msg.created_at;
+                    let reply = session
+                        .consumer_acks
+                        .as_mut()
+                        .filter(|consumer_acks| consumer_acks.tracks(&subject))
+                        .map(ConsumerAcks::track);
+                    Self::publish_message(
+                        client,
+                        msg.subject,
+                        reply,
+                        msg.headers,
+                        msg.payload,
+                        timeout,
+                    )
+                    .await
+                    .inspect_err(|_| {
+                        stats.record_publish_error();
+                        options.record_failure();
                     })?;
+                    stats.record_message_published(&subject, size, created_at.elapsed());
+                    options.record_success();
+                    if let Some(slot) = msg.slot {
+                        stats.record_slot_published(&subject, slot);
+                    }
+
+                    // The client also flushes every `flush_interval_ms`
+                    unflushed += 1;
+                    if options.flush_batch_size > 0 && unflushed >= options.flush_batch_size {
+                        unflushed = 0;
+                        Self::flush(client, timeout).await?;
+                    }
                 }
                 Err(crossbeam_channel::TryRecvError::Empty) => {
-                    // No messages, check if we need to ping
-                    if last_ping.elapsed() >= ping_interval {
-                        Self::write_command(&mut writer, "PING").map_err(|e| {
-                            ConnectionError::ConnectionLost {
-                                msg: format!("Failed to send keepalive PING: {e}"),
-                            }
-                        })?;
-                        writer
-                            .flush()
-                            .map_err(|e| ConnectionError::ConnectionLost {
-                                msg: format!("Failed to flush keepalive PING: {e}"),
-                            })?;
-                        last_ping = std::time::Instant::now();
-                    }
-                    thread::sleep(Duration::from_millis(10));
+                    session
+                        .ack_window
+                        .wait(queued, Duration::from_millis(options.poll_interval_ms))
+                        .await;
                 }
                 Err(crossbeam_channel::TryRecvError::Disconnected) => {
                     info!("Message channel disconnected, closing connection");
@@ -229,54 +1504,209 @@ impl ConnectionManager {
         Ok(())
     }
 
-    /// Write a NATS publish message to a writer
-    fn write_publish_message<W: Write>(
-        writer: &mut BufWriter<W>,
-        msg: &NatsMessage,
-    ) -> Result<(), std::io::Error> {
-        // PUB subject
-        let command = format!("PUB {} {}\r\n", msg.subject, msg.payload.len());
-        writer.write_all(command.as_bytes())?;
+    /// Size of a message as counted against the server's max_payload, headers included
+    fn message_size(message: &NatsMessage) -> usize {
+        let headers_size = if message.headers.is_empty() {
+            0
+        } else {
+            // "NATS/1.0\r\n", a "name: value\r\n" line per header and a final "\r\n"
+            message
+                .headers
+                .iter()
+                .map(|(name, value)| name.len() + value.len() + 4)
+                .sum::<usize>()
+                + 12
+        };
+        message.payload.len() + headers_size
+    }
 
-        // payload
-        writer.write_all(&msg.payload)?;
-        writer.write_all(b"\r\n")?;
-        writer.flush()?;
+    /// Publish a reference to a message the server would reject, instead of the message
+    async fn handle_oversized_message(
+        client: &Client,
+        msg: NatsMessage,
+        max_payload: usize,
+        options: &ConnectionOptions,
+        timeout: Duration,
+    ) -> Result<(), ConnectionError> {
+        let size = Self::message_size(&msg);
+        options.stats.record_message_oversized();
+        warn!(
+            "Message for {} is {size} bytes, over the server's max_payload of {max_payload}",
+            msg.subject
+        );
 
-        debug!("Published NATS message: {} bytes", msg.payload.len());
+        let Some(oversize_subject) = &options.oversize_subject else {
+            return Ok(());
+        };
+        let mut reference = TransactionSerializer::serialize_oversized_message(
+            &msg.subject,
+            size,
+            max_payload,
+            msg.slot,
+            &msg.headers,
+        );
+        TransactionSerializer::set_schema_version(&mut reference);
+        let payload = reference.to_string().into_bytes();
+        let reference_size = payload.len();
+        Self::publish_message(
+            client,
+            oversize_subject.clone(),
+            None,
+            Vec::new(),
+            payload,
+            timeout,
+        )
+        .await
+        .inspect_err(|_| options.stats.record_publish_error())?;
+        options.stats.record_message_published(
+            oversize_subject,
+            reference_size,
+            msg.created_at.elapsed(),
+        );
         Ok(())
     }
 
-    /// Write a NATS command to a writer
-    fn write_command<W: Write>(
-        writer: &mut BufWriter<W>,
-        command: &str,
-    ) -> Result<(), std::io::Error> {
-        let formatted = format!("{command}\r\n");
-        writer.write_all(formatted.as_bytes())?;
-        Ok(())
+    /// Replace a message too large to publish by a pointer to its uploaded payload; the
+    /// message is kept as is when the upload fails
+    async fn offload_payload(
+        offloader: &PayloadOffloader,
+        msg: NatsMessage,
+        max_payload: usize,
+        options: &ConnectionOptions,
+    ) -> NatsMessage {
+        if !offloader.should_offload(Self::message_size(&msg), max_payload) {
+            return msg;
+        }
+
+        match offloader.offload(&msg).await {
+            Ok(pointer) => {
+                options.stats.record_message_offloaded();
+                pointer
+            }
+            Err(e) => {
+                warn!("{e}");
+                options.stats.record_error(e.to_string());
+                msg
+            }
+        }
     }
 
-    /// Read and discard a response from the NATS server
-    fn read_response(reader: &mut BufReader<TcpStream>) -> Result<(), ConnectionError> {
-        let mut line = String::new();
-        reader
-            .read_line(&mut line)
-            .map_err(|e| ConnectionError::ConnectionLost {
-                msg: format!("Failed to read NATS response: {e}"),
-            })?;
-        debug!("NATS server response: {}", line.trim());
-        Ok(())
+    /// Write out the publishes the client buffers
+    async fn flush(client: &Client, timeout: Duration) -> Result<(), ConnectionError> {
+        tokio::time::timeout(timeout, client.flush())
+            .await
+            .map_err(|_| ConnectionError::SendFailed {
+                msg: format!("Timed out flushing NATS client after {timeout:?}"),
+            })?
+            .map_err(|e| ConnectionError::SendFailed {
+                msg: format!("Failed to flush NATS client: {e}"),
+            })
+    }
+
+    /// Hand a message to the client, with its reply subject and headers if any
+    async fn publish_message(
+        client: &Client,
+        subject: String,
+        reply: Option<String>,
+        headers: Vec<(String, String)>,
+        payload: Vec<u8>,
+        timeout: Duration,
+    ) -> Result<(), ConnectionError> {
+        let publish = async {
+            match (reply, headers.is_empty()) {
+                (None, true) => client.publish(subject, payload.into()).await,
+                (None, false) => {
+                    client
+                        .publish_with_headers(subject, Self::header_map(&headers), payload.into())
+                        .await
+                }
+                (Some(reply), true) => {
+                    client
+                        .publish_with_reply(subject, reply, payload.into())
+                        .await
+                }
+                (Some(reply), false) => {
+                    client
+                        .publish_with_reply_and_headers(
+                            subject,
+                            reply,
+                            Self::header_map(&headers),
+                            payload.into(),
+                        )
+                        .await
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, publish)
+            .await
+            .map_err(|_| ConnectionError::SendFailed {
+                msg: format!("Timed out publishing message after {timeout:?}"),
+            })?
+            .map_err(|e| ConnectionError::SendFailed {
+                msg: format!("Failed to publish message: {e}"),
+            })
+    }
+
+    /// Whether a subject is published through JetStream
+    fn uses_jetstream(subject: &str, options: &ConnectionOptions) -> bool {
+        options
+            .jetstream_subjects
+            .iter()
+            .any(|pattern| subject_matches(pattern, subject))
+    }
+
+    fn header_map(headers: &[(String, String)]) -> HeaderMap {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            header_map.insert(name.as_str(), value.as_str());
+        }
+        header_map
+    }
+
+    /// Answer requests received by a subscription until the session ends
+    async fn serve_requests(
+        client: Client,
+        mut subscriber: Subscriber,
+        request_handler: Arc<dyn RequestHandler>,
+    ) {
+        while let Some(request) = subscriber.next().await {
+            let Some(reply_to) = request.reply else {
+                continue;
+            };
+            let headers: Vec<(String, String)> = request
+                .headers
+                .iter()
+                .flat_map(|headers| headers.iter())
+                .flat_map(|(name, values)| {
+                    values
+                        .iter()
+                        .map(move |value| (name.to_string(), value.clone()))
+                })
+                .collect();
+            let Some(reply) =
+                request_handler.handle_request(&request.subject, &headers, &request.payload)
+            else {
+                continue;
+            };
+            if let Err(e) = client.publish(reply_to, reply.into()).await {
+                debug!("Failed to reply to request on {}: {e}", request.subject);
+            }
+        }
     }
 
     /// Shutdown the connection manager
     pub fn shutdown(&mut self) {
         info!("Shutting down NATS connection manager");
         self.shutdown.store(true, Ordering::Relaxed);
+        self.queued.notify_one();
 
-        if let Some(handle) = self.worker_handle.take() {
-            if let Err(e) = handle.join() {
-                error!("Error joining worker thread: {e:?}");
+        if let Some(handle) = self.worker.lock().unwrap().handle.take() {
+            if let Err(payload) = handle.join() {
+                error!(
+                    "Error joining worker thread: {}",
+                    Self::panic_message(payload.as_ref())
+                );
             }
         }
     }
@@ -0,0 +1,128 @@
+use {
+    crate::{
+        config::{subject_matches, ConsumerAcksConfig},
+        connection::ConnectionError,
+        stats::PluginStats,
+    },
+    async_nats::{Client, Subscriber},
+    futures::StreamExt,
+    log::debug,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Tracks the acknowledgements consumers send to the reply subject of core NATS
+/// messages, for single-consumer setups that need delivery confirmation without JetStream.
+/// Messages are not retried, a missing acknowledgement only counts as a timeout
+pub struct ConsumerAcks {
+    inbox: String,
+    subjects: Vec<String>,
+    timeout: Duration,
+    next_id: u64,
+    pending: Arc<Mutex<HashMap<u64, Instant>>>,
+    stats: Arc<PluginStats>,
+}
+
+impl ConsumerAcks {
+    /// Subscribe to this session's acknowledgement inbox
+    pub async fn open(
+        client: &Client,
+        config: &ConsumerAcksConfig,
+        stats: Arc<PluginStats>,
+    ) -> Result<Self, ConnectionError> {
+        let inbox = client.new_inbox();
+        let subscriber = client.subscribe(format!("{inbox}.*")).await.map_err(|e| {
+            ConnectionError::ConnectionLost {
+                msg: format!("Failed to subscribe to consumer acks on {inbox}: {e}"),
+            }
+        })?;
+
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        tokio::spawn(Self::receive_acks(
+            subscriber,
+            pending.clone(),
+            stats.clone(),
+        ));
+
+        Ok(Self {
+            inbox,
+            subjects: config.subjects.clone(),
+            timeout: Duration::from_millis(config.timeout_ms),
+            next_id: 0,
+            pending,
+            stats,
+        })
+    }
+
+    /// Whether messages published to `subject` carry a reply subject
+    pub fn tracks(&self, subject: &str) -> bool {
+        self.subjects.is_empty()
+            || self
+                .subjects
+                .iter()
+                .any(|pattern| subject_matches(pattern, subject))
+    }
+
+    /// Reply subject of the next published message, tracked until it is acknowledged
+    /// or times out
+    pub fn track(&mut self) -> String {
+        self.next_id += 1;
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(self.next_id, Instant::now());
+        format!("{}.{}", self.inbox, self.next_id)
+    }
+
+    /// Count the messages left unacknowledged for longer than the timeout
+    pub fn expire(&self) {
+        let timeout = self.timeout;
+        self.pending.lock().unwrap().retain(|_, published_at| {
+            let waiting = published_at.elapsed() < timeout;
+            if !waiting {
+                self.stats.record_consumer_ack_timeout();
+            }
+            waiting
+        });
+    }
+
+    /// Count every message still waiting as timed out, as the session ends along with
+    /// its inbox
+    pub fn abandon(&self) {
+        for _ in self.pending.lock().unwrap().drain() {
+            self.stats.record_consumer_ack_timeout();
+        }
+    }
+
+    /// Number of messages waiting for their acknowledgement
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Settle the messages acknowledged on the inbox until the session ends
+    async fn receive_acks(
+        mut subscriber: Subscriber,
+        pending: Arc<Mutex<HashMap<u64, Instant>>>,
+        stats: Arc<PluginStats>,
+    ) {
+        while let Some(ack) = subscriber.next().await {
+            let Some(id) = ack
+                .subject
+                .rsplit('.')
+                .next()
+                .and_then(|id| id.parse::<u64>().ok())
+            else {
+                continue;
+            };
+
+            // Acknowledgements arriving after the timeout were already counted
+            match pending.lock().unwrap().remove(&id) {
+                Some(published_at) => stats.record_consumer_ack(published_at.elapsed()),
+                None => debug!("Ignoring late or duplicate consumer ack on {}", ack.subject),
+            }
+        }
+    }
+}
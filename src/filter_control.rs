@@ -0,0 +1,224 @@
+use {
+    crate::{
+        config::{ConfigError, ConfigurationManager, NatsPluginConfig, TransactionFilterConfig},
+        connection::RequestHandler,
+        message_signer::{MESSAGE_SIGNATURE_HEADER, MESSAGE_SIGNER_HEADER},
+        transaction_selector::{SelectorError, TransactionSelector},
+    },
+    log::{info, warn},
+    serde_derive::Deserialize,
+    serde_json::json,
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::{
+        collections::HashSet,
+        sync::{Arc, Mutex, RwLock},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum FilterControlError {
+    #[error("Unauthorized filter update: {msg}")]
+    Unauthorized { msg: String },
+
+    #[error("Invalid filter update: {msg}")]
+    InvalidUpdate { msg: String },
+}
+
+/// New address and vote settings of the top-level filter, replacing the current ones
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterUpdate {
+    /// Unix time in seconds the update was signed at. Must exceed the version of the last
+    /// applied update and the time the plugin loaded, so recorded updates cannot be
+    /// replayed, even after a restart
+    pub version: u64,
+
+    #[serde(default)]
+    pub select_all_transactions: bool,
+
+    #[serde(default)]
+    pub select_vote_transactions: bool,
+
+    #[serde(default)]
+    pub mentioned_addresses: Vec<String>,
+
+    #[serde(default)]
+    pub vote_addresses: Vec<String>,
+}
+
+/// Swaps the transaction selector at runtime on signed requests to the control subject, so
+/// adding an address does not take a validator restart
+pub struct FilterControl {
+    subject: String,
+    authorized_signers: HashSet<Pubkey>,
+    selector: RwLock<Arc<TransactionSelector>>,
+    version: Mutex<u64>,
+    max_clock_skew_secs: u64,
+}
+
+impl FilterControl {
+    /// Create the filter control starting from the configured filter, or `None` when
+    /// runtime updates are disabled
+    pub fn new(config: &NatsPluginConfig) -> Result<Option<Self>, SelectorError> {
+        if !config.filter_control.enabled {
+            return Ok(None);
+        }
+
+        let subject = config.prefixed_subject(&config.filter_control.subject);
+        info!("Accepting filter updates on {subject}");
+        Ok(Some(Self {
+            subject,
+            authorized_signers: config
+                .filter_control
+                .authorized_signers
+                .iter()
+                .filter_map(|signer| signer.parse().ok())
+                .collect(),
+            selector: RwLock::new(Arc::new(TransactionSelector::from_filter(&config.filter)?)),
+            // Updates signed before the plugin loaded may have been applied by an earlier run
+            version: Mutex::new(Self::unix_secs()),
+            max_clock_skew_secs: config.filter_control.max_clock_skew_secs,
+        }))
+    }
+
+    fn unix_secs() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    /// The selector transactions are currently matched against
+    pub fn selector(&self) -> Arc<TransactionSelector> {
+        self.selector.read().unwrap().clone()
+    }
+
+    /// Version of the last applied update, or the time the plugin loaded before the first one
+    pub fn version(&self) -> u64 {
+        *self.version.lock().unwrap()
+    }
+
+    /// Verify a filter update signed by an authorized signer and swap in its selector,
+    /// returning the applied version
+    pub fn apply_update(
+        &self,
+        headers: &[(String, String)],
+        payload: &[u8],
+    ) -> Result<u64, FilterControlError> {
+        self.verify_signature(headers, payload)?;
+
+        let update: FilterUpdate = serde_json::from_slice(payload)
+            .map_err(|e| FilterControlError::InvalidUpdate { msg: e.to_string() })?;
+        ConfigurationManager::validate_mentioned_addresses(&update.mentioned_addresses).map_err(
+            |e| FilterControlError::InvalidUpdate {
+                msg: match e {
+                    ConfigError::ValidationError { msg } => msg,
+                    e => e.to_string(),
+                },
+            },
+        )?;
+
+        // A version far from the current time was not signed for this run
+        let now = Self::unix_secs();
+        if update.version.abs_diff(now) > self.max_clock_skew_secs {
+            return Err(FilterControlError::InvalidUpdate {
+                msg: format!(
+                    "version {} is more than {}s away from the current time {now}",
+                    update.version, self.max_clock_skew_secs
+                ),
+            });
+        }
+
+        // Holding the version lock orders concurrent updates
+        let mut version = self.version.lock().unwrap();
+        if update.version <= *version {
+            return Err(FilterControlError::InvalidUpdate {
+                msg: format!(
+                    "version {} is not newer than the applied version {}",
+                    update.version, *version
+                ),
+            });
+        }
+
+        let selector = TransactionSelector::from_filter(&TransactionFilterConfig {
+            select_all_transactions: update.select_all_transactions,
+            select_vote_transactions: update.select_vote_transactions,
+            mentioned_addresses: update.mentioned_addresses,
+            vote_addresses: update.vote_addresses,
+            ..TransactionFilterConfig::default()
+        })
+        .map_err(|e| FilterControlError::InvalidUpdate { msg: e.to_string() })?;
+        *self.selector.write().unwrap() = Arc::new(selector);
+        *version = update.version;
+
+        info!("Applied filter update version {}", update.version);
+        Ok(update.version)
+    }
+
+    /// Check the payload is signed, through the headers `MessageSigner` sets, by an
+    /// authorized signer
+    fn verify_signature(
+        &self,
+        headers: &[(String, String)],
+        payload: &[u8],
+    ) -> Result<(), FilterControlError> {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+                .ok_or_else(|| FilterControlError::Unauthorized {
+                    msg: format!("missing {name} header"),
+                })
+        };
+
+        let signer: Pubkey = header(MESSAGE_SIGNER_HEADER)?.parse().map_err(|_| {
+            FilterControlError::Unauthorized {
+                msg: "invalid signer".to_string(),
+            }
+        })?;
+        if !self.authorized_signers.contains(&signer) {
+            return Err(FilterControlError::Unauthorized {
+                msg: format!("{signer} is not an authorized signer"),
+            });
+        }
+
+        let signature: Signature = header(MESSAGE_SIGNATURE_HEADER)?.parse().map_err(|_| {
+            FilterControlError::Unauthorized {
+                msg: "invalid signature".to_string(),
+            }
+        })?;
+        if !signature.verify(signer.as_ref(), payload) {
+            return Err(FilterControlError::Unauthorized {
+                msg: format!("signature does not verify against {signer}"),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl RequestHandler for FilterControl {
+    fn subjects(&self) -> Vec<String> {
+        vec![self.subject.clone()]
+    }
+
+    fn handle_request(
+        &self,
+        _subject: &str,
+        headers: &[(String, String)],
+        payload: &[u8],
+    ) -> Option<Vec<u8>> {
+        let reply = match self.apply_update(headers, payload) {
+            Ok(version) => json!({ "applied": true, "version": version }),
+            Err(e) => {
+                warn!("Rejected filter update: {e}");
+                json!({ "applied": false, "error": e.to_string() })
+            }
+        };
+
+        serde_json::to_vec(&reply).ok()
+    }
+}
@@ -0,0 +1,588 @@
+use {
+    crate::{
+        config::{AddressMatch, TransactionFilterConfig},
+        transaction_router,
+    },
+    log::error,
+    solana_sdk::{pubkey::Pubkey, transaction::SanitizedTransaction},
+    solana_transaction_status::TransactionStatusMeta,
+    std::collections::HashSet,
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum FilterExpressionError {
+    #[error("Syntax error at offset {offset}: {msg}")]
+    SyntaxError { offset: usize, msg: String },
+
+    #[error("Type error at offset {offset}: {msg}")]
+    TypeError { offset: usize, msg: String },
+}
+
+/// Transaction properties an expression can refer to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Fee,
+    ComputeUnits,
+    Slot,
+    IsVote,
+    Failed,
+    FeePayer,
+    Programs,
+    Accounts,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "fee" => Some(Self::Fee),
+            "compute_units" => Some(Self::ComputeUnits),
+            "slot" => Some(Self::Slot),
+            "is_vote" => Some(Self::IsVote),
+            "failed" => Some(Self::Failed),
+            "fee_payer" => Some(Self::FeePayer),
+            "programs" => Some(Self::Programs),
+            "accounts" => Some(Self::Accounts),
+            _ => None,
+        }
+    }
+
+    fn kind(self) -> Kind {
+        match self {
+            Self::Fee | Self::ComputeUnits | Self::Slot => Kind::Int,
+            Self::IsVote | Self::Failed => Kind::Bool,
+            Self::FeePayer => Kind::Str,
+            Self::Programs | Self::Accounts => Kind::Addresses,
+        }
+    }
+}
+
+/// Type of an expression, checked when it is parsed so evaluation cannot fail
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Int,
+    Str,
+    Addresses,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::Int => "int",
+            Self::Str => "string",
+            Self::Addresses => "address list",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Bool(bool),
+    Int(u64),
+    Str(String),
+    Field(Field),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Comparison, Box<Expr>, Box<Expr>),
+    Contains(Field, HashSet<Pubkey>),
+    Len(Field),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Int(u64),
+    Str(String),
+    Ident(String),
+    And,
+    Or,
+    Not,
+    Compare(Comparison),
+    LParen,
+    RParen,
+    Dot,
+}
+
+/// The properties of one transaction, computed only when an expression refers to them
+struct TransactionView<'a> {
+    is_vote: bool,
+    transaction: &'a SanitizedTransaction,
+    meta: &'a TransactionStatusMeta,
+    slot: u64,
+}
+
+enum Value {
+    Bool(bool),
+    Int(u64),
+    Str(String),
+}
+
+/// A boolean expression over a transaction, for conditions the flat filter settings cannot
+/// express, such as `fee > 10000 && programs.contains("<program id>") && !is_vote`
+#[derive(Debug)]
+pub struct FilterExpression {
+    expr: Expr,
+}
+
+impl FilterExpression {
+    /// Create the expression of a filter, or `None` when the filter sets none
+    pub fn new(filter: &TransactionFilterConfig) -> Option<Self> {
+        let source = filter.expression.as_deref()?;
+
+        // Configuration validation rejects invalid expressions before this is reached
+        Self::parse(source)
+            .inspect_err(|e| error!("Invalid filter expression '{source}': {e}"))
+            .ok()
+    }
+
+    /// Parse and type check an expression, which must evaluate to a bool
+    pub fn parse(source: &str) -> Result<Self, FilterExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            end: source.len(),
+        };
+        let (expr, kind) = parser.parse_or()?;
+        if let Some((_, offset)) = parser.tokens.get(parser.position) {
+            return Err(FilterExpressionError::SyntaxError {
+                offset: *offset,
+                msg: "unexpected token after the expression".to_string(),
+            });
+        }
+        if kind != Kind::Bool {
+            return Err(FilterExpressionError::TypeError {
+                offset: 0,
+                msg: format!("expression must be a bool, not {}", kind.name()),
+            });
+        }
+
+        Ok(Self { expr })
+    }
+
+    /// Whether the expression holds for a transaction of a slot
+    pub fn matches(
+        &self,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+    ) -> bool {
+        let view = TransactionView {
+            is_vote,
+            transaction,
+            meta,
+            slot,
+        };
+        matches!(evaluate(&self.expr, &view), Value::Bool(true))
+    }
+}
+
+fn evaluate(expr: &Expr, view: &TransactionView) -> Value {
+    match expr {
+        Expr::Bool(value) => Value::Bool(*value),
+        Expr::Int(value) => Value::Int(*value),
+        Expr::Str(value) => Value::Str(value.clone()),
+        Expr::Field(field) => view.field(*field),
+        Expr::Not(operand) => Value::Bool(!is_true(operand, view)),
+        Expr::And(left, right) => Value::Bool(is_true(left, view) && is_true(right, view)),
+        Expr::Or(left, right) => Value::Bool(is_true(left, view) || is_true(right, view)),
+        Expr::Compare(comparison, left, right) => {
+            let ordering = match (evaluate(left, view), evaluate(right, view)) {
+                (Value::Int(left), Value::Int(right)) => left.cmp(&right),
+                (Value::Bool(left), Value::Bool(right)) => left.cmp(&right),
+                (Value::Str(left), Value::Str(right)) => left.cmp(&right),
+                // Type checking only compares operands of the same kind
+                _ => return Value::Bool(false),
+            };
+            Value::Bool(match comparison {
+                Comparison::Eq => ordering.is_eq(),
+                Comparison::Ne => ordering.is_ne(),
+                Comparison::Lt => ordering.is_lt(),
+                Comparison::Le => ordering.is_le(),
+                Comparison::Gt => ordering.is_gt(),
+                Comparison::Ge => ordering.is_ge(),
+            })
+        }
+        Expr::Contains(field, addresses) => Value::Bool(view.contains(*field, addresses)),
+        Expr::Len(field) => Value::Int(view.len(*field)),
+    }
+}
+
+fn is_true(expr: &Expr, view: &TransactionView) -> bool {
+    matches!(evaluate(expr, view), Value::Bool(true))
+}
+
+impl TransactionView<'_> {
+    fn field(&self, field: Field) -> Value {
+        match field {
+            Field::Fee => Value::Int(self.meta.fee),
+            Field::ComputeUnits => Value::Int(self.meta.compute_units_consumed.unwrap_or_default()),
+            Field::Slot => Value::Int(self.slot),
+            Field::IsVote => Value::Bool(self.is_vote),
+            Field::Failed => Value::Bool(self.meta.status.is_err()),
+            Field::FeePayer => Value::Str(self.transaction.message().fee_payer().to_string()),
+            // Address lists are only used through their methods
+            Field::Programs | Field::Accounts => Value::Int(self.len(field)),
+        }
+    }
+
+    fn contains(&self, field: Field, addresses: &HashSet<Pubkey>) -> bool {
+        match field {
+            Field::Programs => transaction_router::invokes_program(
+                self.transaction,
+                self.meta.inner_instructions.as_deref(),
+                addresses,
+            ),
+            _ => self
+                .accounts()
+                .any(|account_key| addresses.contains(account_key)),
+        }
+    }
+
+    fn len(&self, field: Field) -> u64 {
+        let count = match field {
+            Field::Programs => self.programs().len(),
+            _ => self.accounts().count(),
+        };
+        count as u64
+    }
+
+    /// Accounts of the transaction, including the addresses its lookup tables loaded
+    fn accounts(&self) -> impl Iterator<Item = &Pubkey> {
+        transaction_router::matched_account_keys(
+            self.transaction.message(),
+            &self.meta.loaded_addresses,
+            AddressMatch::AnyMention,
+        )
+    }
+
+    /// Distinct programs the transaction invokes, including cross-program invocations
+    fn programs(&self) -> HashSet<&Pubkey> {
+        let message = self.transaction.message();
+        let account_keys = message.account_keys();
+        message
+            .program_instructions_iter()
+            .map(|(program_id, _)| program_id)
+            .chain(
+                self.meta
+                    .inner_instructions
+                    .iter()
+                    .flatten()
+                    .flat_map(|inner| &inner.instructions)
+                    .filter_map(|ix| {
+                        account_keys.get(usize::from(ix.instruction.program_id_index))
+                    }),
+            )
+            .collect()
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, FilterExpressionError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '.' => Token::Dot,
+            '&' | '|' => {
+                if chars.next_if(|(_, next)| *next == c).is_none() {
+                    return Err(FilterExpressionError::SyntaxError {
+                        offset,
+                        msg: format!("expected '{c}{c}'"),
+                    });
+                }
+                if c == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            '!' | '=' | '<' | '>' => {
+                let followed_by_eq = chars.next_if(|(_, next)| *next == '=').is_some();
+                match (c, followed_by_eq) {
+                    ('!', false) => Token::Not,
+                    ('!', true) => Token::Compare(Comparison::Ne),
+                    ('=', true) => Token::Compare(Comparison::Eq),
+                    ('<', false) => Token::Compare(Comparison::Lt),
+                    ('<', true) => Token::Compare(Comparison::Le),
+                    ('>', false) => Token::Compare(Comparison::Gt),
+                    ('>', true) => Token::Compare(Comparison::Ge),
+                    _ => {
+                        return Err(FilterExpressionError::SyntaxError {
+                            offset,
+                            msg: "expected '=='".to_string(),
+                        })
+                    }
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+                            _ => {
+                                return Err(FilterExpressionError::SyntaxError {
+                                    offset,
+                                    msg: "invalid escape in string".to_string(),
+                                })
+                            }
+                        },
+                        Some((_, c)) => value.push(c),
+                        None => {
+                            return Err(FilterExpressionError::SyntaxError {
+                                offset,
+                                msg: "unterminated string".to_string(),
+                            })
+                        }
+                    }
+                }
+                Token::Str(value)
+            }
+            c if c.is_ascii_digit() => {
+                let mut digits = c.to_string();
+                while let Some((_, digit)) = chars.next_if(|(_, next)| next.is_ascii_digit()) {
+                    digits.push(digit);
+                }
+                Token::Int(
+                    digits
+                        .parse()
+                        .map_err(|_| FilterExpressionError::SyntaxError {
+                            offset,
+                            msg: format!("integer {digits} out of range"),
+                        })?,
+                )
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some((_, next)) =
+                    chars.next_if(|(_, next)| next.is_ascii_alphanumeric() || *next == '_')
+                {
+                    name.push(next);
+                }
+                Token::Ident(name)
+            }
+            c => {
+                return Err(FilterExpressionError::SyntaxError {
+                    offset,
+                    msg: format!("unexpected character '{c}'"),
+                })
+            }
+        };
+        tokens.push((token, offset));
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, from the lowest precedence `||` to literals and fields
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |(_, offset)| *offset)
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        let matched = self.tokens.get(self.position).map(|(next, _)| next) == Some(token);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn expect(&mut self, token: &Token, description: &str) -> Result<(), FilterExpressionError> {
+        if self.next_if(token) {
+            Ok(())
+        } else {
+            Err(FilterExpressionError::SyntaxError {
+                offset: self.offset(),
+                msg: format!("expected {description}"),
+            })
+        }
+    }
+
+    fn expect_bool(&self, kind: Kind, offset: usize) -> Result<(), FilterExpressionError> {
+        if kind == Kind::Bool {
+            Ok(())
+        } else {
+            Err(FilterExpressionError::TypeError {
+                offset,
+                msg: format!("expected a bool, not {}", kind.name()),
+            })
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<(Expr, Kind), FilterExpressionError> {
+        let offset = self.offset();
+        let (mut expr, kind) = self.parse_and()?;
+        while self.next_if(&Token::Or) {
+            self.expect_bool(kind, offset)?;
+            let offset = self.offset();
+            let (right, kind) = self.parse_and()?;
+            self.expect_bool(kind, offset)?;
+            expr = Expr::Or(Box::new(expr), Box::new(right));
+        }
+        Ok((expr, kind))
+    }
+
+    fn parse_and(&mut self) -> Result<(Expr, Kind), FilterExpressionError> {
+        let offset = self.offset();
+        let (mut expr, kind) = self.parse_unary()?;
+        while self.next_if(&Token::And) {
+            self.expect_bool(kind, offset)?;
+            let offset = self.offset();
+            let (right, kind) = self.parse_unary()?;
+            self.expect_bool(kind, offset)?;
+            expr = Expr::And(Box::new(expr), Box::new(right));
+        }
+        Ok((expr, kind))
+    }
+
+    fn parse_unary(&mut self) -> Result<(Expr, Kind), FilterExpressionError> {
+        if self.next_if(&Token::Not) {
+            let offset = self.offset();
+            let (operand, kind) = self.parse_unary()?;
+            self.expect_bool(kind, offset)?;
+            return Ok((Expr::Not(Box::new(operand)), Kind::Bool));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<(Expr, Kind), FilterExpressionError> {
+        let (left, left_kind) = self.parse_primary()?;
+        let Some((Token::Compare(comparison), offset)) = self.tokens.get(self.position).cloned()
+        else {
+            return Ok((left, left_kind));
+        };
+        self.position += 1;
+
+        let (right, right_kind) = self.parse_primary()?;
+        let ordered =
+            matches!(comparison, Comparison::Eq | Comparison::Ne) || left_kind == Kind::Int;
+        if left_kind != right_kind || left_kind == Kind::Addresses || !ordered {
+            return Err(FilterExpressionError::TypeError {
+                offset,
+                msg: format!(
+                    "cannot compare {} with {} using {comparison:?}",
+                    left_kind.name(),
+                    right_kind.name()
+                ),
+            });
+        }
+        Ok((
+            Expr::Compare(comparison, Box::new(left), Box::new(right)),
+            Kind::Bool,
+        ))
+    }
+
+    fn parse_primary(&mut self) -> Result<(Expr, Kind), FilterExpressionError> {
+        let offset = self.offset();
+        let Some((token, _)) = self.tokens.get(self.position).cloned() else {
+            return Err(FilterExpressionError::SyntaxError {
+                offset,
+                msg: "unexpected end of expression".to_string(),
+            });
+        };
+        self.position += 1;
+
+        match token {
+            Token::Int(value) => Ok((Expr::Int(value), Kind::Int)),
+            Token::Str(value) => Ok((Expr::Str(value), Kind::Str)),
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                self.expect(&Token::RParen, "')'")?;
+                Ok(expr)
+            }
+            Token::Ident(name) if name == "true" || name == "false" => {
+                Ok((Expr::Bool(name == "true"), Kind::Bool))
+            }
+            Token::Ident(name) => {
+                let field =
+                    Field::parse(&name).ok_or_else(|| FilterExpressionError::TypeError {
+                        offset,
+                        msg: format!("unknown field '{name}'"),
+                    })?;
+                if self.next_if(&Token::Dot) {
+                    self.parse_method(field, offset)
+                } else {
+                    Ok((Expr::Field(field), field.kind()))
+                }
+            }
+            token => Err(FilterExpressionError::SyntaxError {
+                offset,
+                msg: format!("unexpected {token:?}"),
+            }),
+        }
+    }
+
+    /// `contains("<address>")` and `len()` of the `programs` and `accounts` lists
+    fn parse_method(
+        &mut self,
+        field: Field,
+        field_offset: usize,
+    ) -> Result<(Expr, Kind), FilterExpressionError> {
+        let offset = self.offset();
+        let Some((Token::Ident(method), _)) = self.tokens.get(self.position).cloned() else {
+            return Err(FilterExpressionError::SyntaxError {
+                offset,
+                msg: "expected a method name".to_string(),
+            });
+        };
+        self.position += 1;
+        if field.kind() != Kind::Addresses {
+            return Err(FilterExpressionError::TypeError {
+                offset: field_offset,
+                msg: format!("{} has no method {method}", field.kind().name()),
+            });
+        }
+        self.expect(&Token::LParen, "'('")?;
+
+        let expr = match method.as_str() {
+            "contains" => {
+                let offset = self.offset();
+                let address = match self.tokens.get(self.position) {
+                    Some((Token::Str(address), _)) => address.parse::<Pubkey>().ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| FilterExpressionError::TypeError {
+                    offset,
+                    msg: "contains expects a base58 address string".to_string(),
+                })?;
+                self.position += 1;
+                (Expr::Contains(field, HashSet::from([address])), Kind::Bool)
+            }
+            "len" => (Expr::Len(field), Kind::Int),
+            _ => {
+                return Err(FilterExpressionError::TypeError {
+                    offset,
+                    msg: format!("unknown method '{method}'"),
+                })
+            }
+        };
+        self.expect(&Token::RParen, "')'")?;
+        Ok(expr)
+    }
+}
@@ -1,21 +1,36 @@
 use {
     crate::{
+        circuit_breaker::CircuitBreaker,
         config::{ConfigurationManager, NatsPluginConfig},
-        connection::ConnectionManager,
+        config_watcher::ConfigWatcher,
+        connection::{ConnectionManager, ConnectionOptions, RequestHandler},
+        filter_control::FilterControl,
+        lifecycle_events::LifecycleEvents,
         processor::TransactionProcessor,
+        service::NatsService,
+        stats::{PluginStats, StatsReporter},
+        stats_publisher::StatsPublisher,
+        status_file::StatusFileWriter,
     },
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, ReplicaBlockInfoVersions,
         ReplicaTransactionInfoVersions, Result, SlotStatus,
     },
-    log::{debug, error, info},
-    std::sync::Arc,
+    log::{debug, error, info, warn},
+    std::{path::PathBuf, sync::Arc, time::Duration},
 };
 
+/// How long unloading waits for queued messages to be published
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Default)]
 pub struct GeyserPluginNats {
     processor: Option<Arc<TransactionProcessor>>,
     connection_manager: Option<Arc<ConnectionManager>>,
+    stats_reporter: Option<StatsReporter>,
+    stats_publisher: Option<StatsPublisher>,
+    status_file_writer: Option<StatusFileWriter>,
+    config_watcher: Option<ConfigWatcher>,
 }
 
 impl std::fmt::Debug for GeyserPluginNats {
@@ -23,6 +38,13 @@ impl std::fmt::Debug for GeyserPluginNats {
         f.debug_struct("GeyserPluginNats")
             .field("processor_initialized", &self.processor.is_some())
             .field("connection_initialized", &self.connection_manager.is_some())
+            .field("stats_reporter_running", &self.stats_reporter.is_some())
+            .field("stats_publisher_running", &self.stats_publisher.is_some())
+            .field(
+                "status_file_writer_running",
+                &self.status_file_writer.is_some(),
+            )
+            .field("config_watcher_running", &self.config_watcher.is_some())
             .finish()
     }
 }
@@ -56,8 +78,37 @@ impl GeyserPlugin for GeyserPluginNats {
 
         info!("Configuration loaded successfully");
 
+        let metrics_interval = Duration::from_secs(config.metrics_interval_secs);
+        let status_file = config.status_file.clone();
+        let plugin_stats = config.plugin_stats.clone();
+        let watched_config = config.config_reload.enabled.then(|| config.clone());
         let (connection_manager, processor) = Self::initialize_components(config)?;
 
+        self.stats_reporter = Some(StatsReporter::start(
+            connection_manager.stats(),
+            metrics_interval,
+        ));
+        self.status_file_writer = status_file.map(|status_file| {
+            StatusFileWriter::start(
+                PathBuf::from(status_file.path),
+                Duration::from_secs(status_file.interval_secs),
+                connection_manager.clone(),
+            )
+        });
+        self.stats_publisher = plugin_stats.enabled.then(|| {
+            StatsPublisher::start(
+                processor.clone(),
+                Duration::from_secs(plugin_stats.interval_secs),
+            )
+        });
+        self.config_watcher = watched_config.map(|config| {
+            ConfigWatcher::start(
+                PathBuf::from(config_file),
+                Duration::from_secs(config.config_reload.interval_secs),
+                config,
+                processor.clone(),
+            )
+        });
         self.connection_manager = Some(connection_manager);
         self.processor = Some(processor);
 
@@ -68,14 +119,33 @@ impl GeyserPlugin for GeyserPluginNats {
     fn on_unload(&mut self) {
         info!("Unloading plugin: {}", self.name());
 
+        // Stop publishing stats and reloading filters before the final drain
+        self.stats_publisher = None;
+        self.config_watcher = None;
+
+        // Publish what is queued, then tell consumers where the live feed stops
+        if let Some(processor) = &self.processor {
+            if let Err(e) = processor.flush_batches() {
+                warn!("Failed to publish pending transaction batches: {e}");
+            }
+            processor.drain(SHUTDOWN_DRAIN_TIMEOUT);
+            if let Err(e) = processor.publish_shutdown_notice() {
+                warn!("Failed to publish shutdown notice: {e}");
+            }
+            processor.drain(SHUTDOWN_DRAIN_TIMEOUT);
+        }
+
+        // Release the processor first so the connection manager is no longer shared
+        self.processor = None;
+        self.stats_reporter = None;
+        self.status_file_writer = None;
+
         // Clean shutdown
         let connection_manager = self.connection_manager.take();
         if let Err(e) = Self::shutdown_components(connection_manager) {
             error!("Error during shutdown: {e}");
         }
 
-        self.processor = None;
-
         info!("Plugin unloaded successfully");
     }
 
@@ -91,17 +161,36 @@ impl GeyserPlugin for GeyserPluginNats {
 
     fn update_slot_status(
         &self,
-        _slot: u64,
-        _parent: Option<u64>,
-        _status: &SlotStatus,
+        slot: u64,
+        parent: Option<u64>,
+        status: &SlotStatus,
     ) -> Result<()> {
-        // Do not process any slot status
-        Ok(())
+        // Slot status is published when a slots subject is configured, and used to know the
+        // current slot and detect dropped forks
+        let Some(processor) = self.processor.as_ref() else {
+            return Ok(());
+        };
+
+        processor.record_slot(slot);
+        processor
+            .process_slot_status(slot, parent, status)
+            .map_err(|err| {
+                error!("Failed to process slot status: {err:?}");
+                GeyserPluginError::Custom(Box::new(err))
+            })
     }
 
     fn notify_end_of_startup(&self) -> Result<()> {
         info!("NATS plugin startup complete");
-        Ok(())
+
+        let Some(processor) = self.processor.as_ref() else {
+            return Ok(());
+        };
+
+        processor.publish_startup_complete().map_err(|err| {
+            error!("Failed to publish startup complete marker: {err:?}");
+            GeyserPluginError::Custom(Box::new(err))
+        })
     }
 
     fn notify_transaction(
@@ -124,9 +213,16 @@ impl GeyserPlugin for GeyserPluginNats {
             })
     }
 
-    fn notify_block_metadata(&self, _block_info: ReplicaBlockInfoVersions) -> Result<()> {
-        // Do not process block metadata
-        Ok(())
+    fn notify_block_metadata(&self, block_info: ReplicaBlockInfoVersions) -> Result<()> {
+        // Block metadata is only used for block assembly and vote summaries
+        let Some(processor) = self.processor.as_ref() else {
+            return Ok(());
+        };
+
+        processor.process_block_metadata(block_info).map_err(|err| {
+            error!("Failed to process block metadata: {err:?}");
+            GeyserPluginError::Custom(Box::new(err))
+        })
     }
 
     fn account_data_notifications_enabled(&self) -> bool {
@@ -153,23 +249,191 @@ impl GeyserPluginNats {
         info!("Initializing NATS plugin");
         debug!("Config: {config:?}");
 
+        let mut stats = PluginStats::new();
+        if config.address_fanout.enabled {
+            // Per-address subjects are counted together, or their stats would grow without bound
+            stats = stats.with_subject_group(
+                &config.prefixed_subject(&config.address_fanout.subject_prefix),
+            );
+        }
+        let stats = Arc::new(stats);
+        let filter_control = FilterControl::new(&config)
+            .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?
+            .map(Arc::new);
+        let mut request_handlers: Vec<Arc<dyn RequestHandler>> =
+            Self::create_service(&config, stats.clone())
+                .into_iter()
+                .collect();
+        if let Some(filter_control) = &filter_control {
+            request_handlers.push(filter_control.clone());
+        }
+
         // Create connection manager
         let connection_manager = Arc::new(
-            ConnectionManager::new(&config.nats_url, config.max_retries, config.timeout_secs)
-                .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?,
+            ConnectionManager::with_options(
+                &config.nats_url,
+                ConnectionOptions {
+                    max_retries: config.max_retries,
+                    timeout_secs: config.timeout_secs,
+                    queue_capacity: config.queue_capacity,
+                    queue_high_watermark_bytes: config.queue_high_watermark_bytes,
+                    overflow_policy: config.overflow_policy,
+                    overflow_timeout_ms: config.overflow_timeout_ms,
+                    flush_interval_ms: config.flush_interval_ms,
+                    poll_interval_ms: config.poll_interval_ms,
+                    jetstream_max_in_flight: config.jetstream.max_in_flight,
+                    jetstream_max_ack_retries: config.jetstream.max_ack_retries,
+                    flush_batch_size: config.flush_batch_size,
+                    publish_timestamps: config.publish_timestamps,
+                    rate_limit: config.rate_limit.clone(),
+                    jetstream_subjects: config.jetstream.subjects.clone(),
+                    oversize_subject: config
+                        .oversize_subject
+                        .as_deref()
+                        .map(|subject| config.prefixed_subject(subject)),
+                    max_worker_restarts: config.max_worker_restarts,
+                    client_name: config.client_name.clone(),
+                    ping_interval_secs: config.ping_interval_secs,
+                    auth: config.auth.clone(),
+                    tls: config.tls,
+                    slot_checkpoint: config.slot_checkpoint.clone(),
+                    payload_offload: config.payload_offload.clone(),
+                    consumer_acks: config.consumer_acks.clone(),
+                    stats,
+                    circuit_breaker: CircuitBreaker::new(&config.circuit_breaker).map(Arc::new),
+                    lifecycle_events: Self::lifecycle_events(&config, "primary"),
+                    request_handlers,
+                },
+            )
+            .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?,
         );
 
         // Create transaction processor
-        let processor = Arc::new(TransactionProcessor::new(
-            connection_manager.clone(),
-            &config.filter,
-            config.subject.clone(),
-        ));
+        let mut processor = TransactionProcessor::from_config(connection_manager.clone(), &config)
+            .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?;
+        if let Some(filter_control) = filter_control {
+            processor = processor.with_filter_control(filter_control);
+        }
+
+        // Create secondary connection manager with its own queue and reconnect state
+        if let Some(secondary) = &config.secondary {
+            info!(
+                "Publishing to secondary NATS cluster: {}",
+                secondary.nats_url
+            );
+            let secondary_connection_manager = ConnectionManager::with_options(
+                &secondary.nats_url,
+                ConnectionOptions {
+                    max_retries: secondary.max_retries,
+                    timeout_secs: secondary.timeout_secs,
+                    queue_capacity: secondary.queue_capacity,
+                    queue_high_watermark_bytes: config.queue_high_watermark_bytes,
+                    overflow_policy: secondary.overflow_policy,
+                    overflow_timeout_ms: secondary.overflow_timeout_ms,
+                    max_worker_restarts: secondary.max_worker_restarts,
+                    client_name: config.client_name.clone(),
+                    ping_interval_secs: config.ping_interval_secs,
+                    flush_interval_ms: config.flush_interval_ms,
+                    poll_interval_ms: config.poll_interval_ms,
+                    jetstream_max_in_flight: config.jetstream.max_in_flight,
+                    jetstream_max_ack_retries: config.jetstream.max_ack_retries,
+                    flush_batch_size: config.flush_batch_size,
+                    publish_timestamps: config.publish_timestamps,
+                    rate_limit: config.rate_limit.clone(),
+                    jetstream_subjects: config.jetstream.subjects.clone(),
+                    oversize_subject: config
+                        .oversize_subject
+                        .as_deref()
+                        .map(|subject| config.prefixed_subject(subject)),
+                    auth: secondary.auth.clone(),
+                    tls: secondary.tls,
+                    payload_offload: config.payload_offload.clone(),
+                    circuit_breaker: CircuitBreaker::new(&secondary.circuit_breaker).map(Arc::new),
+                    lifecycle_events: Self::lifecycle_events(&config, "secondary"),
+                    ..ConnectionOptions::default()
+                },
+            )
+            .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?;
+            processor = processor.with_secondary_connection(Arc::new(secondary_connection_manager));
+        }
+        let processor = Arc::new(processor);
 
         info!("NATS plugin initialized successfully");
         Ok((connection_manager, processor))
     }
 
+    /// Create the lifecycle events of a cluster's connection if they are enabled
+    fn lifecycle_events(config: &NatsPluginConfig, cluster: &str) -> Option<Arc<LifecycleEvents>> {
+        config.lifecycle_events.enabled.then(|| {
+            Arc::new(LifecycleEvents::new(
+                config.prefixed_subject(&config.lifecycle_events.subject),
+                cluster,
+                &config.client_name,
+                config.network(),
+            ))
+        })
+    }
+
+    /// Create the NATS service responder if service registration is enabled
+    fn create_service(
+        config: &NatsPluginConfig,
+        stats: Arc<PluginStats>,
+    ) -> Option<Arc<dyn RequestHandler>> {
+        if !config.service.enabled {
+            return None;
+        }
+
+        let mut subjects = vec![config.transaction_subject().to_string()];
+        if config.vote_transaction_subject() != config.transaction_subject() {
+            subjects.push(config.vote_transaction_subject().to_string());
+        }
+        if let Some(slot_subject) = &config.subjects.slots {
+            subjects.push(slot_subject.clone());
+        }
+        if config.block_assembly.enabled {
+            subjects.push(config.block_subject().to_string());
+        }
+        if config.address_fanout.enabled {
+            subjects.push(format!("{}.*", config.address_fanout.subject_prefix));
+        }
+        if config.vote_summary.enabled {
+            subjects.push(config.vote_summary.subject.clone());
+        }
+        if config.commitment_updates.enabled {
+            subjects.push(config.commitment_updates.subject.clone());
+        }
+        if config.rewards.enabled {
+            subjects.push(config.rewards.subject.clone());
+        }
+        if let Some(oversize_subject) = &config.oversize_subject {
+            subjects.push(oversize_subject.clone());
+        }
+        if config.plugin_stats.enabled {
+            subjects.push(config.plugin_stats.subject.clone());
+        }
+        if config.lifecycle_events.enabled {
+            subjects.push(config.lifecycle_events.subject.clone());
+        }
+        let subjects = subjects
+            .iter()
+            .map(|subject| config.prefixed_subject(subject))
+            .collect();
+
+        let service = NatsService::new(
+            &config.service.name,
+            &config.service.description,
+            subjects,
+            stats,
+        );
+        info!(
+            "Registering NATS service {} with id {}",
+            config.service.name,
+            service.id()
+        );
+
+        Some(Arc::new(service))
+    }
+
     /// Shutdown all plugin components gracefully
     fn shutdown_components(connection_manager: Option<Arc<ConnectionManager>>) -> Result<()> {
         info!("Shutting down plugin");
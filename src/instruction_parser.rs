@@ -0,0 +1,111 @@
+use {
+    serde_json::{json, Value},
+    solana_sdk::{
+        compute_budget, instruction::CompiledInstruction, message::AccountKeys, pubkey::Pubkey,
+        transaction::SanitizedTransaction,
+    },
+    solana_transaction_status::{parse_instruction, TransactionStatusMeta},
+};
+
+/// Programs decoded through the RPC's own instruction parsers
+const PARSED_PROGRAMS: [&str; 4] = ["system", "spl-token", "spl-memo", "stake"];
+
+/// Decodes the instructions of the System, SPL Token, Memo, Stake and Compute Budget
+/// programs into the `program` and `parsed: { type, info }` fields of the RPC's
+/// jsonParsed encoding
+pub struct InstructionParser;
+
+impl InstructionParser {
+    /// Decode an instruction, or `None` when its program is not decoded or its data is
+    /// malformed
+    pub fn parse(
+        program_id: &Pubkey,
+        instruction: &CompiledInstruction,
+        account_keys: &AccountKeys,
+    ) -> Option<(String, Value)> {
+        if compute_budget::check_id(program_id) {
+            return Some((
+                "compute-budget".to_string(),
+                Self::parse_compute_budget(&instruction.data)?,
+            ));
+        }
+
+        let parsed = parse_instruction::parse(program_id, instruction, account_keys, None).ok()?;
+        PARSED_PROGRAMS
+            .contains(&parsed.program.as_str())
+            .then_some((parsed.program, parsed.parsed))
+    }
+
+    /// Add the decoded form of every decodable instruction, inner instructions included,
+    /// to a serialized transaction
+    pub fn attach(
+        transaction_value: &mut Value,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+    ) {
+        let account_keys = transaction.message().account_keys();
+
+        let instructions = transaction.message().instructions();
+        if let Some(values) = transaction_value
+            .pointer_mut("/transaction/message/instructions")
+            .and_then(Value::as_array_mut)
+        {
+            for (value, instruction) in values.iter_mut().zip(instructions) {
+                Self::attach_to(value, instruction, &account_keys);
+            }
+        }
+
+        let inner_instructions = meta.inner_instructions.as_deref().unwrap_or_default();
+        if let Some(values) = transaction_value
+            .pointer_mut("/meta/innerInstructions")
+            .and_then(Value::as_array_mut)
+        {
+            for (value, inner) in values.iter_mut().zip(inner_instructions) {
+                let Some(values) = value["instructions"].as_array_mut() else {
+                    continue;
+                };
+                for (value, inner_instruction) in values.iter_mut().zip(&inner.instructions) {
+                    Self::attach_to(value, &inner_instruction.instruction, &account_keys);
+                }
+            }
+        }
+    }
+
+    /// Add the `program` and `parsed` fields to a serialized instruction
+    fn attach_to(value: &mut Value, instruction: &CompiledInstruction, account_keys: &AccountKeys) {
+        let Some(program_id) = account_keys.get(instruction.program_id_index as usize) else {
+            return;
+        };
+        let (Some((program, parsed)), Some(object)) = (
+            Self::parse(program_id, instruction, account_keys),
+            value.as_object_mut(),
+        ) else {
+            return;
+        };
+
+        object.insert("program".to_string(), json!(program));
+        object.insert("parsed".to_string(), parsed);
+    }
+
+    /// Decode a Compute Budget instruction, a discriminator followed by a little endian
+    /// integer
+    fn parse_compute_budget(data: &[u8]) -> Option<Value> {
+        let (&discriminator, value) = data.split_first()?;
+        let u32_value = || Some(u32::from_le_bytes(value.try_into().ok()?));
+
+        let parsed = match discriminator {
+            1 => json!({ "type": "requestHeapFrame", "info": { "bytes": u32_value()? } }),
+            2 => json!({ "type": "setComputeUnitLimit", "info": { "units": u32_value()? } }),
+            3 => json!({
+                "type": "setComputeUnitPrice",
+                "info": { "microLamports": u64::from_le_bytes(value.try_into().ok()?) },
+            }),
+            4 => json!({
+                "type": "setLoadedAccountsDataSizeLimit",
+                "info": { "bytes": u32_value()? },
+            }),
+            _ => return None,
+        };
+        Some(parsed)
+    }
+}
@@ -1,13 +1,82 @@
+pub mod address_rate_limiter;
+pub mod block_assembler;
+pub mod circuit_breaker;
+pub mod commitment_tracker;
+pub mod compression;
 pub mod config;
+pub mod config_watcher;
 pub mod connection;
+pub mod consumer_acks;
+pub mod filter_control;
+pub mod filter_expression;
 pub mod geyser_plugin_nats;
+pub mod instruction_parser;
+pub mod lifecycle_events;
+pub mod load_shedder;
+pub mod log_limiter;
+pub mod message_signer;
+pub mod payload_fields;
+pub mod payload_offload;
 pub mod processor;
+pub mod rate_limiter;
 pub mod serializer;
+pub mod service;
+pub mod signature_deduplicator;
+pub mod slot_checkpoint;
+pub mod slot_tracker;
+pub mod stats;
+pub mod stats_publisher;
+pub mod status_file;
+pub mod transaction_batcher;
+pub mod transaction_router;
 pub mod transaction_selector;
+pub mod transaction_writer;
+pub mod vote_summarizer;
 
-pub use config::{ConfigurationManager, NatsPluginConfig, TransactionFilterConfig};
-pub use connection::{ConnectionManager, NatsMessage};
+pub use address_rate_limiter::AddressRateLimiter;
+pub use block_assembler::BlockAssembler;
+pub use circuit_breaker::CircuitBreaker;
+pub use commitment_tracker::{Commitment, CommitmentTracker};
+pub use compression::PayloadCompressor;
+pub use config::{
+    AddressFanoutConfig, AddressMatch, AddressRateLimitConfig, AuthConfig, BatchingConfig,
+    BlockAssemblyConfig, CircuitBreakerConfig, CommitmentUpdatesConfig, CompressionAlgorithm,
+    CompressionConfig, ConfigReloadConfig, ConfigurationManager, ConsumerAcksConfig,
+    DeduplicationConfig, FilterControlConfig, JetStreamConfig, LifecycleEventsConfig,
+    LoadSheddingConfig, LogLimitsConfig, NatsPluginConfig, OverflowPolicy, PayloadFieldsConfig,
+    PayloadFormat, PayloadOffloadConfig, PluginStatsConfig, RateLimitConfig, RewardsConfig,
+    RouteConfig, SecondaryClusterConfig, ServiceConfig, SlotCheckpointConfig, SlotDropConfig,
+    SubjectsConfig, TransactionFilterConfig, TransactionSchema, TransactionVersion,
+    VoteSummaryConfig,
+};
+pub use config_watcher::ConfigWatcher;
+pub use connection::{ConnectionManager, ConnectionOptions, NatsMessage, RequestHandler};
+pub use consumer_acks::ConsumerAcks;
+pub use filter_control::{FilterControl, FilterControlError, FilterUpdate};
+pub use filter_expression::{FilterExpression, FilterExpressionError};
 pub use geyser_plugin_nats::{GeyserPluginNats, _create_plugin};
+pub use instruction_parser::InstructionParser;
+pub use lifecycle_events::LifecycleEvents;
+pub use load_shedder::{LoadShedder, SheddingLevel};
+pub use log_limiter::LogLimiter;
+pub use message_signer::MessageSigner;
+pub use payload_fields::PayloadShaper;
+pub use payload_offload::PayloadOffloader;
 pub use processor::{ProcessingError, TransactionProcessor};
+pub use rate_limiter::RateLimiter;
 pub use serializer::{SerializationError, TransactionSerializer};
-pub use transaction_selector::TransactionSelector;
+pub use service::NatsService;
+pub use signature_deduplicator::SignatureDeduplicator;
+pub use slot_checkpoint::SlotCheckpoint;
+pub use slot_tracker::{DroppedSlot, SlotDropReason, SlotTracker};
+pub use stats::{FilterStats, PluginStats, StatsReporter};
+pub use stats_publisher::StatsPublisher;
+pub use status_file::StatusFileWriter;
+pub use transaction_batcher::TransactionBatcher;
+pub use transaction_router::{
+    LogFilter, MessageShapeFilter, PublishWindow, TransactionFilter, TransactionRoute,
+    TransactionRouter,
+};
+pub use transaction_selector::{SelectorError, TransactionSelector};
+pub use transaction_writer::TransactionWriter;
+pub use vote_summarizer::{VoteSummarizer, VoteSummary};
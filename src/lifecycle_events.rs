@@ -0,0 +1,87 @@
+use {
+    crate::{
+        connection::{NatsMessage, NETWORK_HEADER},
+        serializer::TransactionSerializer,
+    },
+    serde_json::json,
+    std::{collections::VecDeque, sync::Mutex},
+};
+
+/// Events kept while they cannot be published, the oldest being dropped first
+const MAX_PENDING_EVENTS: usize = 100;
+
+/// Connection lifecycle events waiting to be published to the events subject. They are kept
+/// across sessions and worker restarts, so events raised while disconnected go out once a
+/// connection is back
+pub struct LifecycleEvents {
+    subject: String,
+    cluster: String,
+    client_name: String,
+    network: Option<String>,
+    pending: Mutex<VecDeque<NatsMessage>>,
+}
+
+impl LifecycleEvents {
+    pub fn new(subject: String, cluster: &str, client_name: &str, network: Option<&str>) -> Self {
+        Self {
+            subject,
+            cluster: cluster.to_string(),
+            client_name: client_name.to_string(),
+            network: network.map(str::to_string),
+            pending: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Record an event, timestamped now
+    pub fn record(
+        &self,
+        event: &str,
+        server: Option<&str>,
+        reason: Option<&str>,
+        attempt: Option<u32>,
+    ) {
+        let mut value = TransactionSerializer::serialize_lifecycle_event(
+            event,
+            &self.cluster,
+            &self.client_name,
+            server,
+            reason,
+            attempt,
+        );
+        if let (Some(network), Some(object)) = (&self.network, value.as_object_mut()) {
+            object.insert("network".to_string(), json!(network));
+        }
+        TransactionSerializer::set_schema_version(&mut value);
+
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() >= MAX_PENDING_EVENTS {
+            pending.pop_front();
+        }
+        let mut message = NatsMessage::new(self.subject.clone(), value.to_string().into_bytes());
+        if let Some(network) = &self.network {
+            message.set_header(NETWORK_HEADER, network.clone());
+        }
+        pending.push_back(message);
+    }
+
+    /// Take the pending events, oldest first
+    pub fn take(&self) -> Vec<NatsMessage> {
+        self.pending.lock().unwrap().drain(..).collect()
+    }
+
+    /// Put back events that could not be published, ahead of the ones recorded since
+    pub fn restore(&self, events: Vec<NatsMessage>) {
+        let mut pending = self.pending.lock().unwrap();
+        for event in events.into_iter().rev() {
+            pending.push_front(event);
+        }
+        while pending.len() > MAX_PENDING_EVENTS {
+            pending.pop_front();
+        }
+    }
+
+    /// Number of events waiting to be published
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
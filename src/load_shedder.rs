@@ -0,0 +1,114 @@
+use {
+    crate::config::LoadSheddingConfig,
+    log::{info, warn},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashSet,
+        str::FromStr,
+        sync::atomic::{AtomicU64, AtomicU8, Ordering},
+    },
+};
+
+/// How aggressively transactions are being shed
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum SheddingLevel {
+    /// Every selected transaction is published
+    None = 0,
+    /// Vote transactions are dropped
+    Votes = 1,
+    /// Vote transactions are dropped and non-priority transactions are sampled
+    Sampling = 2,
+}
+
+impl SheddingLevel {
+    fn from_u8(level: u8) -> Self {
+        match level {
+            0 => Self::None,
+            1 => Self::Votes,
+            _ => Self::Sampling,
+        }
+    }
+}
+
+/// Decides which transactions to drop as the publish queue backs up
+pub struct LoadShedder {
+    vote_threshold: usize,
+    sampling_threshold: usize,
+    sample_rate: u64,
+    priority_addresses: HashSet<Pubkey>,
+    level: AtomicU8,
+    sampled: AtomicU64,
+}
+
+impl LoadShedder {
+    /// Create a load shedder from validated configuration
+    pub fn new(config: &LoadSheddingConfig) -> Self {
+        let priority_addresses = config
+            .priority_addresses
+            .iter()
+            .filter_map(|address| Pubkey::from_str(address).ok())
+            .collect();
+
+        Self {
+            vote_threshold: config.vote_threshold,
+            sampling_threshold: config.sampling_threshold,
+            sample_rate: config.sample_rate.max(1),
+            priority_addresses,
+            level: AtomicU8::new(SheddingLevel::None as u8),
+            sampled: AtomicU64::new(0),
+        }
+    }
+
+    /// The shedding level applied to the most recent transaction
+    pub fn level(&self) -> SheddingLevel {
+        SheddingLevel::from_u8(self.level.load(Ordering::Relaxed))
+    }
+
+    /// Update the shedding level for the current queue depth, logging transitions
+    fn update_level(&self, queue_depth: usize) -> SheddingLevel {
+        let level = if queue_depth >= self.sampling_threshold {
+            SheddingLevel::Sampling
+        } else if queue_depth >= self.vote_threshold {
+            SheddingLevel::Votes
+        } else {
+            SheddingLevel::None
+        };
+
+        let previous = SheddingLevel::from_u8(self.level.swap(level as u8, Ordering::Relaxed));
+        if previous < level {
+            warn!("Publish queue depth {queue_depth}, shedding load: {previous:?} -> {level:?}");
+        } else if previous > level {
+            info!("Publish queue depth {queue_depth}, restoring load: {previous:?} -> {level:?}");
+        }
+
+        level
+    }
+
+    /// Whether a selected transaction should be dropped at the given queue depth
+    pub fn should_shed<'a>(
+        &self,
+        queue_depth: usize,
+        is_vote: bool,
+        account_keys: impl Iterator<Item = &'a Pubkey>,
+    ) -> bool {
+        match self.update_level(queue_depth) {
+            SheddingLevel::None => false,
+            SheddingLevel::Votes => is_vote,
+            SheddingLevel::Sampling => {
+                if is_vote {
+                    return true;
+                }
+
+                let mut account_keys = account_keys;
+                if account_keys.any(|key| self.priority_addresses.contains(key)) {
+                    return false;
+                }
+
+                !self
+                    .sampled
+                    .fetch_add(1, Ordering::Relaxed)
+                    .is_multiple_of(self.sample_rate)
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use {crate::config::LogLimitsConfig, serde_json::Value};
+
+/// Marker of the last line kept from a log message shortened to `max_line_bytes`
+const TRUNCATED_LINE_SUFFIX: &str = "...";
+
+/// Caps the program logs of transaction payloads, so programs emitting huge logs do not
+/// produce multi-hundred-KB messages. Payloads losing logs are marked `truncated: true`
+pub struct LogLimiter {
+    max_lines: usize,
+    max_line_bytes: usize,
+    max_payload_bytes: usize,
+}
+
+impl LogLimiter {
+    /// Create a log limiter, or `None` when logs are not limited
+    pub fn new(config: &LogLimitsConfig) -> Option<Self> {
+        if config.max_lines == 0 && config.max_line_bytes == 0 && config.max_payload_bytes == 0 {
+            return None;
+        }
+
+        Some(Self {
+            max_lines: config.max_lines,
+            max_line_bytes: config.max_line_bytes,
+            max_payload_bytes: config.max_payload_bytes,
+        })
+    }
+
+    /// Keep at most `max_lines` log messages of at most `max_line_bytes` each
+    pub fn limit_lines(&self, value: &mut Value) {
+        let Some(logs) = Self::log_messages(value) else {
+            return;
+        };
+
+        let mut truncated = false;
+        if self.max_lines > 0 && logs.len() > self.max_lines {
+            logs.truncate(self.max_lines);
+            truncated = true;
+        }
+        if self.max_line_bytes > 0 {
+            for log in logs.iter_mut() {
+                let Some(line) = log.as_str().filter(|line| line.len() > self.max_line_bytes)
+                else {
+                    continue;
+                };
+                let mut end = self.max_line_bytes;
+                while !line.is_char_boundary(end) {
+                    end -= 1;
+                }
+                *log = Value::String(format!("{}{TRUNCATED_LINE_SUFFIX}", &line[..end]));
+                truncated = true;
+            }
+        }
+
+        if truncated {
+            Self::mark_truncated(value);
+        }
+    }
+
+    /// Whether encoded payloads are limited to `max_payload_bytes`
+    pub fn limits_payload(&self) -> bool {
+        self.max_payload_bytes > 0
+    }
+
+    /// Whether an encoded payload is larger than `max_payload_bytes`
+    pub fn is_oversized(&self, payload_len: usize) -> bool {
+        self.max_payload_bytes > 0 && payload_len > self.max_payload_bytes
+    }
+
+    /// Shrink the logs of a payload whose encoding is `payload_len` bytes, dropping
+    /// `meta.parsedLogs`, then log messages from the end, until it should fit. Returns
+    /// whether anything was dropped
+    pub fn shed(&self, value: &mut Value, payload_len: usize) -> bool {
+        let mut excess = payload_len.saturating_sub(self.max_payload_bytes);
+        let mut shed = false;
+
+        if let Some(parsed_logs) = value
+            .get_mut("meta")
+            .and_then(Value::as_object_mut)
+            .and_then(|meta| meta.remove("parsedLogs"))
+        {
+            excess = excess.saturating_sub(parsed_logs.to_string().len());
+            shed = true;
+        }
+
+        if let Some(logs) = Self::log_messages(value) {
+            while excess > 0 {
+                let Some(log) = logs.pop() else {
+                    break;
+                };
+                // The line, its quotes and the separating comma
+                excess = excess.saturating_sub(log.to_string().len() + 1);
+                shed = true;
+            }
+        }
+
+        if shed {
+            Self::mark_truncated(value);
+        }
+        shed
+    }
+
+    /// Drop every log message of a payload still too large after `shed`, which already
+    /// marked it truncated
+    pub fn shed_all(&self, value: &mut Value) {
+        if let Some(logs) = Self::log_messages(value) {
+            logs.clear();
+        }
+    }
+
+    fn log_messages(value: &mut Value) -> Option<&mut Vec<Value>> {
+        value
+            .pointer_mut("/meta/logMessages")
+            .and_then(Value::as_array_mut)
+    }
+
+    fn mark_truncated(value: &mut Value) {
+        if let Some(object) = value.as_object_mut() {
+            object.insert("truncated".to_string(), Value::Bool(true));
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use {
+    crate::connection::NatsMessage,
+    log::{error, info},
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+    },
+};
+
+/// Header holding the base58 ed25519 signature of a message payload
+pub const MESSAGE_SIGNATURE_HEADER: &str = "Solana-Message-Signature";
+
+/// Header holding the base58 public key a message was signed with
+pub const MESSAGE_SIGNER_HEADER: &str = "Solana-Message-Signer";
+
+/// Signs published payloads with a keypair of the validator operator, so consumers that do
+/// not trust the NATS deployment can verify where a message came from
+pub struct MessageSigner {
+    keypair: Keypair,
+}
+
+impl MessageSigner {
+    /// Load the signing keypair, or `None` when messages are not signed
+    pub fn new(keypair_path: Option<&str>) -> Option<Self> {
+        let keypair_path = keypair_path?;
+        match read_keypair_file(keypair_path) {
+            Ok(keypair) => {
+                info!("Signing published messages with {}", keypair.pubkey());
+                Some(Self { keypair })
+            }
+            Err(e) => {
+                error!("Failed to read signing keypair {keypair_path}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Public key consumers verify signatures against
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    /// Add a detached signature of the payload, and the key it verifies against
+    pub fn sign(&self, message: &mut NatsMessage) {
+        let signature = self.keypair.sign_message(&message.payload);
+        message.set_header(MESSAGE_SIGNATURE_HEADER, signature.to_string());
+        message.set_header(MESSAGE_SIGNER_HEADER, self.pubkey().to_string());
+    }
+}
@@ -0,0 +1,81 @@
+use {crate::config::PayloadFieldsConfig, serde_json::Value};
+
+/// A field of a transaction payload, at the top level or nested one level below it, as in
+/// `meta.logMessages`
+type FieldPath = (String, Option<String>);
+
+/// Shrinks transaction payloads to the fields operators asked for, e.g. dropping
+/// `meta.logMessages`, which often dominates message size
+pub struct PayloadShaper {
+    include: Vec<FieldPath>,
+    exclude: Vec<FieldPath>,
+}
+
+impl PayloadShaper {
+    /// Create a payload shaper, or `None` when every field is published
+    pub fn new(config: &PayloadFieldsConfig) -> Option<Self> {
+        if config.include.is_empty() && config.exclude.is_empty() {
+            return None;
+        }
+
+        let parse = |paths: &[String]| -> Vec<FieldPath> {
+            paths
+                .iter()
+                .map(|path| match path.split_once('.') {
+                    Some((field, nested)) => (field.to_string(), Some(nested.to_string())),
+                    None => (path.clone(), None),
+                })
+                .collect()
+        };
+        Some(Self {
+            include: parse(&config.include),
+            exclude: parse(&config.exclude),
+        })
+    }
+
+    /// Keep the included fields of a payload, if any are listed, then remove the excluded ones
+    pub fn apply(&self, value: &mut Value) {
+        let Some(object) = value.as_object_mut() else {
+            return;
+        };
+
+        if !self.include.is_empty() {
+            object.retain(|field, field_value| {
+                let mut nested_fields = Vec::new();
+                for (included, nested) in &self.include {
+                    if included != field {
+                        continue;
+                    }
+                    match nested {
+                        Some(nested) => nested_fields.push(nested.as_str()),
+                        // The whole field is included
+                        None => return true,
+                    }
+                }
+                if nested_fields.is_empty() {
+                    return false;
+                }
+
+                if let Some(nested_object) = field_value.as_object_mut() {
+                    nested_object.retain(|nested, _| nested_fields.contains(&nested.as_str()));
+                }
+                true
+            });
+        }
+
+        for (excluded, nested) in &self.exclude {
+            match nested {
+                Some(nested) => {
+                    if let Some(nested_object) =
+                        object.get_mut(excluded).and_then(Value::as_object_mut)
+                    {
+                        nested_object.remove(nested);
+                    }
+                }
+                None => {
+                    object.remove(excluded);
+                }
+            }
+        }
+    }
+}
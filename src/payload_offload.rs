@@ -0,0 +1,123 @@
+use {
+    crate::{
+        config::PayloadOffloadConfig,
+        connection::{ConnectionError, NatsMessage},
+        serializer::TransactionSerializer,
+    },
+    async_nats::{
+        jetstream::{self, object_store},
+        Client,
+    },
+    std::time::Duration,
+};
+
+/// Uploads payloads too large to publish to a NATS object store, to be replaced by a
+/// pointer on their subject
+pub struct PayloadOffloader {
+    store: object_store::ObjectStore,
+    bucket: String,
+    min_bytes: usize,
+    timeout: Duration,
+}
+
+impl PayloadOffloader {
+    /// Open the offload bucket, creating it when missing
+    pub async fn open(
+        client: &Client,
+        config: &PayloadOffloadConfig,
+        timeout: Duration,
+    ) -> Result<Self, ConnectionError> {
+        let mut jetstream = jetstream::new(client.clone());
+        jetstream.set_timeout(timeout);
+
+        let store = match jetstream.get_object_store(&config.bucket).await {
+            Ok(store) => store,
+            Err(_) => jetstream
+                .create_object_store(object_store::Config {
+                    bucket: config.bucket.clone(),
+                    description: Some(
+                        "Payloads offloaded by Solana Geyser NATS plugins".to_string(),
+                    ),
+                    max_age: Duration::from_secs(config.max_age_secs),
+                    ..object_store::Config::default()
+                })
+                .await
+                .map_err(|e| ConnectionError::SendFailed {
+                    msg: format!("Failed to create object store {}: {e}", config.bucket),
+                })?,
+        };
+
+        Ok(Self {
+            store,
+            bucket: config.bucket.clone(),
+            min_bytes: config.min_bytes,
+            timeout,
+        })
+    }
+
+    /// Whether a message of `size` bytes is offloaded on a server accepting `max_payload` bytes
+    pub fn should_offload(&self, size: usize, max_payload: usize) -> bool {
+        let limit = match (self.min_bytes, max_payload) {
+            (0, max_payload) => max_payload,
+            (min_bytes, 0) => min_bytes,
+            (min_bytes, max_payload) => min_bytes.min(max_payload),
+        };
+        limit > 0 && size > limit
+    }
+
+    /// Upload the payload of a message, returning the pointer to publish in its place
+    pub async fn offload(&self, message: &NatsMessage) -> Result<NatsMessage, ConnectionError> {
+        let object = match message.slot {
+            Some(slot) => format!("{}/{slot}/{:016x}", message.subject, rand::random::<u64>()),
+            None => format!("{}/{:016x}", message.subject, rand::random::<u64>()),
+        };
+
+        let info = tokio::time::timeout(
+            self.timeout,
+            self.store
+                .put(object.as_str(), &mut message.payload.as_slice()),
+        )
+        .await
+        .map_err(|_| ConnectionError::SendFailed {
+            msg: format!(
+                "Timed out uploading {object} to object store {}",
+                self.bucket
+            ),
+        })?
+        .map_err(|e| ConnectionError::SendFailed {
+            msg: format!(
+                "Failed to upload {object} to object store {}: {e}",
+                self.bucket
+            ),
+        })?;
+
+        // The pointer itself is plain JSON, the encoding applies to the stored object
+        let content_encoding = message
+            .headers
+            .iter()
+            .find(|(name, _)| name == "Content-Encoding")
+            .map(|(_, value)| value.as_str());
+        let mut pointer = TransactionSerializer::serialize_offloaded_payload(
+            &self.bucket,
+            &info.name,
+            info.digest.as_deref(),
+            message.payload.len(),
+            message.slot,
+            content_encoding,
+        );
+        TransactionSerializer::set_schema_version(&mut pointer);
+
+        Ok(NatsMessage {
+            subject: message.subject.clone(),
+            payload: pointer.to_string().into_bytes(),
+            headers: message
+                .headers
+                .iter()
+                .filter(|(name, _)| name != "Content-Encoding")
+                .cloned()
+                .collect(),
+            slot: message.slot,
+            created_at: message.created_at,
+        })
+    }
+}
@@ -1,19 +1,53 @@
 use {
     crate::{
-        config::TransactionFilterConfig,
-        connection::{ConnectionManager, NatsMessage},
+        address_rate_limiter::AddressRateLimiter,
+        block_assembler::{AssembledSlot, BlockAssembler},
+        commitment_tracker::{Commitment, CommitmentTracker},
+        compression::PayloadCompressor,
+        config::{
+            subject_matches, AddressFanoutConfig, AddressMatch, MessageTtlConfig, NatsPluginConfig,
+            PayloadFormat, TransactionFilterConfig, TransactionSchema,
+        },
+        connection::{ConnectionManager, NatsMessage, NETWORK_HEADER, PAYLOAD_DIGEST_HEADER},
+        filter_control::FilterControl,
+        instruction_parser::InstructionParser,
+        load_shedder::LoadShedder,
+        log_limiter::LogLimiter,
+        message_signer::MessageSigner,
+        payload_fields::PayloadShaper,
         serializer::{SerializationError, TransactionSerializer},
-        transaction_selector::TransactionSelector,
+        signature_deduplicator::SignatureDeduplicator,
+        slot_tracker::SlotTracker,
+        stats::PluginStats,
+        transaction_batcher::TransactionBatcher,
+        transaction_router::{self, TransactionFilter, TransactionRouter},
+        transaction_selector::{SelectorError, TransactionSelector},
+        transaction_writer::TransactionWriter,
+        vote_summarizer::VoteSummarizer,
     },
     agave_geyser_plugin_interface::geyser_plugin_interface::{
-        ReplicaTransactionInfo, ReplicaTransactionInfoV2, ReplicaTransactionInfoVersions,
+        ReplicaBlockInfoVersions, ReplicaTransactionInfoVersions, SlotStatus,
+    },
+    chrono::Utc,
+    log::{debug, info, warn},
+    serde_json::{self, json, Value},
+    solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::SanitizedTransaction},
+    solana_transaction_status::{Reward, TransactionStatusMeta},
+    std::{
+        collections::HashSet,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, RwLock,
+        },
+        time::{Duration, Instant},
     },
-    log::{debug, info},
-    serde_json,
-    std::sync::Arc,
     thiserror::Error,
 };
 
+/// Minimum interval between warnings about messages the secondary cluster lost
+const SECONDARY_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
 #[derive(Error, Debug)]
 pub enum ProcessingError {
     #[error("Connection error: {0}")]
@@ -27,12 +61,67 @@ pub enum ProcessingError {
 
     #[error("Transaction processor not initialized: {msg}")]
     NotInitialized { msg: String },
+
+    #[error("Invalid transaction selector: {0}")]
+    Selector(#[from] SelectorError),
+}
+
+/// The parts of a transaction notification the processor uses, common to every version
+struct TransactionInfo<'a> {
+    signature: &'a Signature,
+    is_vote: bool,
+    transaction: &'a SanitizedTransaction,
+    transaction_status_meta: &'a TransactionStatusMeta,
+    /// Index of the transaction within its block, which V1 notifications do not carry
+    index: Option<usize>,
 }
 
 pub struct TransactionProcessor {
     connection_manager: Arc<ConnectionManager>,
-    transaction_selector: TransactionSelector,
-    subject: String,
+    secondary_connection_manager: Option<Arc<ConnectionManager>>,
+    /// Messages the secondary cluster dropped or failed to publish as of the last warning
+    secondary_reported_losses: AtomicU64,
+    /// Unix time in milliseconds of the last warning about secondary cluster losses
+    secondary_warned_at: AtomicU64,
+    filters: RwLock<Arc<TransactionFilters>>,
+    filter_control: Option<Arc<FilterControl>>,
+    slot_subject: Option<String>,
+    block_assembler: Option<BlockAssembler>,
+    block_subject: String,
+    vote_summarizer: Option<VoteSummarizer>,
+    vote_summary_subject: String,
+    reward_selector: Option<TransactionSelector>,
+    reward_types: HashSet<String>,
+    rewards_subject: String,
+    address_fanout: AddressFanoutConfig,
+    message_ttls: Vec<MessageTtlConfig>,
+    control_subject_prefix: String,
+    load_shedder: Option<LoadShedder>,
+    slot_tracker: Option<SlotTracker>,
+    signature_deduplicator: Option<SignatureDeduplicator>,
+    address_rate_limiter: Option<AddressRateLimiter>,
+    commitment_tracker: Option<CommitmentTracker>,
+    commitment_subject: String,
+    plugin_stats_subject: String,
+    compute_breakdown: bool,
+    parsed_logs: bool,
+    decode_votes: bool,
+    parsed_instructions: bool,
+    metadata_headers: bool,
+    message_ids: bool,
+    payload_digest: bool,
+    message_signer: Option<MessageSigner>,
+    format: PayloadFormat,
+    transaction_schema: TransactionSchema,
+    payload_shaper: Option<PayloadShaper>,
+    log_limiter: Option<LogLimiter>,
+    transaction_batcher: Option<TransactionBatcher>,
+    compressor: Option<PayloadCompressor>,
+    subject_prefix: String,
+    network: Option<String>,
+    sequence: AtomicU64,
+    highest_slot: AtomicU64,
+    stats: Arc<PluginStats>,
 }
 
 impl TransactionProcessor {
@@ -41,33 +130,168 @@ impl TransactionProcessor {
         connection_manager: Arc<ConnectionManager>,
         filter_config: &TransactionFilterConfig,
         subject: String,
-    ) -> Self {
-        let transaction_selector = Self::create_transaction_selector(filter_config);
+    ) -> Result<Self, ProcessingError> {
+        let config = NatsPluginConfig {
+            subject,
+            filter: filter_config.clone(),
+            ..NatsPluginConfig::default()
+        };
 
-        info!("Transaction processor created with subject: {subject}");
-        debug!("Filter configuration: {filter_config:?}");
+        Self::from_config(connection_manager, &config)
+    }
 
-        Self {
-            connection_manager,
-            transaction_selector,
-            subject,
+    /// Create a new transaction processor from the full plugin configuration
+    pub fn from_config(
+        connection_manager: Arc<ConnectionManager>,
+        config: &NatsPluginConfig,
+    ) -> Result<Self, ProcessingError> {
+        info!(
+            "Transaction processor created with subject: {}, vote subject: {}",
+            config.transaction_subject(),
+            config.vote_transaction_subject()
+        );
+
+        let block_assembler = config.block_assembly.enabled.then(|| {
+            info!(
+                "Block assembly enabled with subject: {}",
+                config.block_subject()
+            );
+            BlockAssembler::new(config.block_assembly.max_buffered_slots)
+        });
+
+        let vote_summarizer = config.vote_summary.enabled.then(|| {
+            info!(
+                "Vote summary enabled with subject: {}",
+                config.vote_summary.subject
+            );
+            VoteSummarizer::new(config.vote_summary.max_buffered_slots)
+        });
+
+        let reward_selector = config
+            .rewards
+            .enabled
+            .then(|| {
+                info!(
+                    "Reward events enabled with subject: {}",
+                    config.rewards.subject
+                );
+                if config.rewards.addresses.is_empty() {
+                    TransactionSelector::new(&["*".to_string()])
+                } else {
+                    TransactionSelector::new(&config.rewards.addresses)
+                }
+            })
+            .transpose()?;
+
+        let load_shedder = config.load_shedding.enabled.then(|| {
+            info!(
+                "Load shedding enabled: votes dropped at queue depth {}, sampling 1/{} at {}",
+                config.load_shedding.vote_threshold,
+                config.load_shedding.sample_rate,
+                config.load_shedding.sampling_threshold
+            );
+            LoadShedder::new(&config.load_shedding)
+        });
+
+        let slot_tracker = config.slot_drop.enabled.then(|| {
+            info!(
+                "Slot drop notifications enabled, tracking up to {} unrooted slots",
+                config.slot_drop.max_tracked_slots
+            );
+            SlotTracker::new(config.slot_drop.max_tracked_slots)
+        });
+
+        let signature_deduplicator = config.deduplication.enabled.then(|| {
+            info!(
+                "Deduplication enabled, remembering up to {} published transactions",
+                config.deduplication.max_signatures
+            );
+            SignatureDeduplicator::new(config.deduplication.max_signatures)
+        });
+
+        let address_rate_limiter = AddressRateLimiter::new(&config.address_rate_limit);
+        if address_rate_limiter.is_some() {
+            info!(
+                "Address rate limiting enabled at {} messages/sec per address",
+                config.address_rate_limit.messages_per_sec
+            );
         }
+
+        let commitment_tracker = config.commitment_updates.enabled.then(|| {
+            info!(
+                "Commitment updates enabled with subject: {}",
+                config.commitment_updates.subject
+            );
+            CommitmentTracker::new(config.commitment_updates.max_tracked_slots)
+        });
+
+        let stats = connection_manager.stats();
+
+        Ok(Self {
+            connection_manager,
+            secondary_connection_manager: None,
+            secondary_reported_losses: AtomicU64::new(0),
+            secondary_warned_at: AtomicU64::new(0),
+            filters: RwLock::new(Arc::new(TransactionFilters::new(config)?)),
+            filter_control: None,
+            slot_subject: config.subjects.slots.clone(),
+            block_assembler,
+            block_subject: config.block_subject().to_string(),
+            vote_summarizer,
+            vote_summary_subject: config.vote_summary.subject.clone(),
+            reward_selector,
+            reward_types: config
+                .rewards
+                .reward_types
+                .iter()
+                .map(|reward_type| reward_type.to_lowercase())
+                .collect(),
+            rewards_subject: config.rewards.subject.clone(),
+            address_fanout: config.address_fanout.clone(),
+            message_ttls: config.message_ttls.clone(),
+            control_subject_prefix: config.control_subject_prefix.clone(),
+            load_shedder,
+            slot_tracker,
+            signature_deduplicator,
+            address_rate_limiter,
+            commitment_tracker,
+            commitment_subject: config.commitment_updates.subject.clone(),
+            plugin_stats_subject: config.plugin_stats.subject.clone(),
+            compute_breakdown: config.compute_breakdown,
+            parsed_logs: config.parsed_logs,
+            decode_votes: config.decode_votes,
+            parsed_instructions: config.parsed_instructions,
+            metadata_headers: config.metadata_headers,
+            message_ids: config.message_ids,
+            payload_digest: config.payload_digest,
+            message_signer: MessageSigner::new(config.signing_keypair.as_deref()),
+            format: config.format,
+            transaction_schema: config.transaction_schema,
+            payload_shaper: PayloadShaper::new(&config.payload_fields),
+            log_limiter: LogLimiter::new(&config.log_limits),
+            transaction_batcher: TransactionBatcher::new(&config.batching),
+            compressor: PayloadCompressor::new(&config.compression),
+            subject_prefix: config.subject_prefix.clone(),
+            network: config.network().map(str::to_string),
+            sequence: AtomicU64::new(0),
+            highest_slot: AtomicU64::new(0),
+            stats,
+        })
     }
 
-    /// Create transaction selector from filter configuration
-    fn create_transaction_selector(filter_config: &TransactionFilterConfig) -> TransactionSelector {
-        if filter_config.select_all_transactions {
-            TransactionSelector::new(&["*".to_string()])
-        } else if filter_config.select_vote_transactions
-            && filter_config.mentioned_addresses.is_empty()
-        {
-            TransactionSelector::new(&["all_votes".to_string()])
-        } else if !filter_config.mentioned_addresses.is_empty() {
-            TransactionSelector::new(&filter_config.mentioned_addresses)
-        } else {
-            // Default: select all non-vote transactions
-            TransactionSelector::new(&["*".to_string()])
-        }
+    /// Also publish every message to a secondary cluster
+    pub fn with_secondary_connection(
+        mut self,
+        secondary_connection_manager: Arc<ConnectionManager>,
+    ) -> Self {
+        self.secondary_connection_manager = Some(secondary_connection_manager);
+        self
+    }
+
+    /// Match transactions against the selector filter updates swap in at runtime
+    pub fn with_filter_control(mut self, filter_control: Arc<FilterControl>) -> Self {
+        self.filter_control = Some(filter_control);
+        self
     }
 
     /// Process a transaction
@@ -76,167 +300,1161 @@ impl TransactionProcessor {
         transaction_info: ReplicaTransactionInfoVersions,
         slot: u64,
     ) -> Result<(), ProcessingError> {
-        match transaction_info {
-            ReplicaTransactionInfoVersions::V0_0_2(transaction_info) => {
-                self.process_transaction_v2(transaction_info, slot)
-            }
-            ReplicaTransactionInfoVersions::V0_0_1(transaction_info) => {
-                self.process_transaction_v1(transaction_info, slot)
-            }
-        }
+        self.record_slot(slot);
+
+        let transaction_info = match transaction_info {
+            ReplicaTransactionInfoVersions::V0_0_2(transaction_info) => TransactionInfo {
+                signature: transaction_info.signature,
+                is_vote: transaction_info.is_vote,
+                transaction: transaction_info.transaction,
+                transaction_status_meta: transaction_info.transaction_status_meta,
+                index: Some(transaction_info.index),
+            },
+            ReplicaTransactionInfoVersions::V0_0_1(transaction_info) => TransactionInfo {
+                signature: transaction_info.signature,
+                is_vote: transaction_info.is_vote,
+                transaction: transaction_info.transaction,
+                transaction_status_meta: transaction_info.transaction_status_meta,
+                index: None,
+            },
+        };
+        self.process_transaction_info(&transaction_info, slot)
     }
 
-    /// Process a V2 transaction
-    fn process_transaction_v2(
+    /// Filter a transaction of any notification version and publish it when selected
+    fn process_transaction_info(
         &self,
-        transaction_info: &ReplicaTransactionInfoV2,
+        transaction_info: &TransactionInfo,
         slot: u64,
     ) -> Result<(), ProcessingError> {
         debug!(
-            "Processing transaction V2: signature={}, is_vote={}, slot={}",
+            "Processing transaction: signature={}, is_vote={}, slot={}",
             transaction_info.signature, transaction_info.is_vote, slot
         );
 
-        // Apply transaction filtering
-        if !self.should_process_transaction(
+        let filters = self.filters();
+
+        // Apply transaction filtering, or the routing rules when configured
+        let subjects = filters.select_transaction(
+            &self.transaction_selector(),
+            transaction_info.is_vote,
+            transaction_info.transaction,
+            transaction_info.transaction_status_meta,
+            transaction_info.signature,
+            slot,
+            &self.stats,
+        );
+        if subjects.is_empty() {
+            self.stats.record_transaction_filtered();
+            return Ok(());
+        }
+
+        // Only votes the filter selects are summarized
+        if transaction_info.is_vote
+            && self.summarize_vote(
+                slot,
+                transaction_info.transaction.message().fee_payer(),
+                transaction_info.transaction_status_meta.fee,
+            )
+        {
+            return Ok(());
+        }
+        if self.is_address_rate_limited(
+            &filters,
+            transaction_info.is_vote,
+            transaction_info.transaction,
+            transaction_info.transaction_status_meta,
+        ) {
+            debug!(
+                "Transaction over the address rate limit: {}",
+                transaction_info.signature
+            );
+            self.stats.record_transaction_rate_limited();
+            return Ok(());
+        }
+        self.stats.record_transaction_selected();
+
+        if self.should_shed_transaction(
             transaction_info.is_vote,
-            transaction_info.transaction.message().account_keys().iter(),
+            transaction_router::matched_account_keys(
+                transaction_info.transaction.message(),
+                &transaction_info.transaction_status_meta.loaded_addresses,
+                AddressMatch::AnyMention,
+            ),
         ) {
-            debug!("Transaction filtered out: {}", transaction_info.signature);
+            debug!(
+                "Transaction shed under load: {}",
+                transaction_info.signature
+            );
+            self.stats.record_transaction_shed();
             return Ok(());
         }
 
+        if self.all_circuits_open() {
+            debug!(
+                "Transaction dropped while every circuit is open: {}",
+                transaction_info.signature
+            );
+            self.record_message_dropped();
+            return Ok(());
+        }
+
+        if self.is_duplicate(transaction_info.signature, slot) {
+            return Ok(());
+        }
+
+        let notifications = self.address_notifications(
+            transaction_info.transaction,
+            transaction_info.signature,
+            transaction_info.is_vote,
+            transaction_info.transaction_status_meta,
+            slot,
+        )?;
+
         info!(
             "Processing non-vote transaction: {}",
             transaction_info.signature
         );
 
         // Serialize and send transaction
-        self.serialize_and_send_v2(transaction_info, slot)
+        self.serialize_and_send(transaction_info, slot, &subjects, notifications)
     }
 
-    /// Process a V1 transaction
-    fn process_transaction_v1(
+    /// Serialize and send a transaction, then the notifications about it
+    fn serialize_and_send(
         &self,
-        transaction_info: &ReplicaTransactionInfo,
+        transaction_info: &TransactionInfo,
         slot: u64,
+        subjects: &[&str],
+        notifications: Vec<NatsMessage>,
     ) -> Result<(), ProcessingError> {
-        debug!(
-            "Processing transaction V1: signature={}, is_vote={}, slot={}",
-            transaction_info.signature, transaction_info.is_vote, slot
+        let vote_event = self.vote_event(
+            transaction_info.is_vote,
+            transaction_info.transaction,
+            transaction_info.transaction_status_meta,
+            slot,
         );
+        if vote_event.is_none() && self.writes_transactions_directly() {
+            let payload = TransactionWriter::new(
+                transaction_info.transaction,
+                transaction_info.transaction_status_meta,
+                slot,
+                transaction_info.index,
+                transaction_info.is_vote,
+            )
+            .with_sequence(self.next_sequence())
+            .with_network(self.network.as_deref())
+            .encode(self.format)?;
+            self.track_transaction(slot, transaction_info.signature);
+            self.send_transaction(
+                payload,
+                slot,
+                transaction_info.signature,
+                transaction_info.is_vote,
+                subjects,
+            )?;
+            return self.publish_notifications(notifications, transaction_info.signature);
+        }
+
+        // Serialize transaction, or only its vote
+        let mut transaction_value = match vote_event {
+            Some(vote_event) => vote_event,
+            None if self.format == PayloadFormat::Raw => {
+                TransactionSerializer::serialize_raw_transaction(
+                    transaction_info.signature,
+                    transaction_info.transaction,
+                    transaction_info.transaction_status_meta,
+                    slot,
+                )?
+            }
+            None if self.transaction_schema == TransactionSchema::Rpc => {
+                TransactionSerializer::serialize_rpc_transaction(
+                    transaction_info.transaction,
+                    transaction_info.transaction_status_meta,
+                    slot,
+                    self.parsed_instructions,
+                )?
+            }
+            None => TransactionSerializer::serialize_transaction(
+                transaction_info.transaction,
+                transaction_info.transaction_status_meta,
+                slot,
+                transaction_info.index,
+                transaction_info.is_vote,
+            )?,
+        };
+        self.attach_ordering_key(&mut transaction_value, slot, transaction_info.index);
+        self.attach_compute_breakdown(
+            &mut transaction_value,
+            transaction_info.transaction_status_meta,
+        );
+        self.attach_parsed_logs(
+            &mut transaction_value,
+            transaction_info.transaction_status_meta,
+        );
+        self.attach_parsed_instructions(
+            &mut transaction_value,
+            transaction_info.transaction,
+            transaction_info.transaction_status_meta,
+        );
+        if let Some(log_limiter) = &self.log_limiter {
+            log_limiter.limit_lines(&mut transaction_value);
+        }
+        if let Some(payload_shaper) = &self.payload_shaper {
+            payload_shaper.apply(&mut transaction_value);
+        }
+
+        self.track_transaction(slot, transaction_info.signature);
+
+        if let Some(block_assembler) = &self.block_assembler {
+            // Notifications wait for the block, which is discarded if it never arrives
+            block_assembler.add_transaction(
+                slot,
+                transaction_info.index,
+                transaction_value,
+                notifications,
+            );
+            debug!(
+                "Buffered transaction {} for block assembly",
+                transaction_info.signature
+            );
+            return Ok(());
+        }
 
-        // Apply transaction filtering
-        if !self.should_process_transaction(
+        // Convert Value to JSON bytes
+        let payload = self.encode_transaction(transaction_value)?;
+        self.send_transaction(
+            payload,
+            slot,
+            transaction_info.signature,
             transaction_info.is_vote,
-            transaction_info.transaction.message().account_keys().iter(),
-        ) {
-            debug!("Transaction filtered out: {}", transaction_info.signature);
+            subjects,
+        )?;
+        self.publish_notifications(notifications, transaction_info.signature)
+    }
+
+    /// Create the NATS message of a transaction payload for each of its subjects and queue them
+    fn send_transaction(
+        &self,
+        payload: Vec<u8>,
+        slot: u64,
+        signature: &Signature,
+        is_vote: bool,
+        subjects: &[&str],
+    ) -> Result<(), ProcessingError> {
+        // Every subject but the last gets a copy of the payload
+        for (subject, payload) in subjects
+            .iter()
+            .zip(std::iter::repeat_n(payload, subjects.len()))
+        {
+            let mut message = NatsMessage::new(subject.to_string(), payload).with_slot(slot);
+            for (name, value) in self.transaction_headers(slot, signature, is_vote) {
+                message = message.with_header(name, &value);
+            }
+
+            self.publish_transaction(message)?;
+        }
+
+        info!("Successfully queued transaction {signature} for NATS publish");
+        Ok(())
+    }
+
+    /// Whether transactions can be written straight to payload bytes, which needs the
+    /// plugin schema and no option rewriting the serialized transaction
+    fn writes_transactions_directly(&self) -> bool {
+        self.format != PayloadFormat::Raw
+            && self.transaction_schema == TransactionSchema::Plugin
+            && !self.compute_breakdown
+            && !self.parsed_logs
+            && !self.parsed_instructions
+            && self.log_limiter.is_none()
+            && self.payload_shaper.is_none()
+            && self.block_assembler.is_none()
+    }
+
+    /// Process block metadata, publishing the assembled block when block assembly is enabled
+    pub fn process_block_metadata(
+        &self,
+        block_info: ReplicaBlockInfoVersions,
+    ) -> Result<(), ProcessingError> {
+        let slot = Self::block_slot(&block_info);
+
+        // Every transaction of the slot was notified before its block metadata
+        if let Some(transaction_batcher) = &self.transaction_batcher {
+            for batch in transaction_batcher.take_through(slot) {
+                self.publish(batch)?;
+            }
+        }
+
+        if let Some(vote_summarizer) = &self.vote_summarizer {
+            let summary = vote_summarizer.take_summary(slot);
+            let summary_value = TransactionSerializer::serialize_vote_summary(slot, &summary);
+
+            let payload = self.encode(summary_value, "vote summary")?;
+
+            self.publish(
+                NatsMessage::new(self.vote_summary_subject.clone(), payload).with_slot(slot),
+            )?;
+
+            debug!(
+                "Successfully queued vote summary for slot {slot} with {} votes",
+                summary.vote_count
+            );
+        }
+
+        self.publish_rewards(&block_info, slot)?;
+
+        let Some(block_assembler) = &self.block_assembler else {
             return Ok(());
+        };
+
+        // The block time of buffered transactions is known now
+        let AssembledSlot {
+            mut transactions,
+            notifications,
+        } = block_assembler.take_slot(slot);
+        let transaction_count = transactions.len();
+        let block_time = Self::block_time(&block_info);
+        for transaction in &mut transactions {
+            if let Some(object) = transaction.as_object_mut() {
+                object.insert("blockTime".to_string(), json!(block_time));
+            }
         }
 
-        info!(
-            "Processing non-vote transaction: {}",
-            transaction_info.signature
-        );
+        let block_value = TransactionSerializer::serialize_block(&block_info, transactions)?;
 
-        // Serialize and send transaction
-        self.serialize_and_send_v1(transaction_info, slot)
+        let payload = self.encode(block_value, "block")?;
+
+        let message = NatsMessage::new(self.block_subject.clone(), payload).with_slot(slot);
+
+        self.publish(message)?;
+
+        info!("Successfully queued block for slot {slot} with {transaction_count} transactions");
+
+        for notification in notifications {
+            self.publish(notification)?;
+        }
+        Ok(())
     }
 
-    /// Serialize and send V2 transaction
-    fn serialize_and_send_v2(
+    /// Publish the selected rewards of a block, if reward events are enabled
+    fn publish_rewards(
         &self,
-        transaction_info: &ReplicaTransactionInfoV2,
+        block_info: &ReplicaBlockInfoVersions,
         slot: u64,
     ) -> Result<(), ProcessingError> {
-        // Serialize transaction
-        let transaction_value =
-            TransactionSerializer::serialize_transaction_v2(transaction_info, slot)?;
+        let Some(reward_selector) = &self.reward_selector else {
+            return Ok(());
+        };
 
-        // Convert Value to JSON bytes
-        let payload = serde_json::to_vec(&transaction_value).map_err(|e| {
-            SerializationError::SerializationFailed {
-                msg: format!("Failed to convert transaction Value to JSON bytes: {e}"),
+        let rewards: Vec<Reward> = Self::block_rewards(block_info)
+            .iter()
+            .filter(|reward| {
+                self.reward_types.is_empty()
+                    || reward.reward_type.is_some_and(|reward_type| {
+                        self.reward_types.contains(&reward_type.to_string())
+                    })
+            })
+            .filter(|reward| {
+                let pubkey = Pubkey::from_str(&reward.pubkey).ok();
+                reward_selector.is_transaction_selected(false, Box::new(pubkey.iter()))
+            })
+            .cloned()
+            .collect();
+
+        if rewards.is_empty() {
+            return Ok(());
+        }
+
+        let rewards_value = TransactionSerializer::serialize_block_rewards(slot, &rewards);
+        let payload = self.encode(rewards_value, "rewards")?;
+
+        self.publish(NatsMessage::new(self.rewards_subject.clone(), payload).with_slot(slot))?;
+
+        debug!("Queued {} rewards for slot {slot}", rewards.len());
+        Ok(())
+    }
+
+    /// Build a slim notification for the subject of every (writable) account the
+    /// transaction mentions
+    fn address_notifications(
+        &self,
+        transaction: &SanitizedTransaction,
+        signature: &Signature,
+        is_vote: bool,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+    ) -> Result<Vec<NatsMessage>, ProcessingError> {
+        if !self.address_fanout.enabled {
+            return Ok(Vec::new());
+        }
+
+        let message = transaction.message();
+        let mut notifications = Vec::new();
+        let mut notified = HashSet::new();
+        for (index, address) in message.account_keys().iter().enumerate() {
+            let writable = message.is_writable(index);
+            if (self.address_fanout.writable_only && !writable) || !notified.insert(address) {
+                continue;
             }
-        })?;
 
-        // Create and send NATS message
-        let message = NatsMessage {
-            subject: self.subject.clone(),
-            payload,
+            let notification = TransactionSerializer::serialize_address_notification(
+                signature, slot, is_vote, writable, meta,
+            );
+            let payload = self.encode(notification, "address notification")?;
+
+            notifications.push(
+                NatsMessage::new(
+                    format!("{}.{address}", self.address_fanout.subject_prefix),
+                    payload,
+                )
+                .with_slot(slot),
+            );
+        }
+        Ok(notifications)
+    }
+
+    /// Publish the notifications about a transaction that was queued
+    fn publish_notifications(
+        &self,
+        notifications: Vec<NatsMessage>,
+        signature: &Signature,
+    ) -> Result<(), ProcessingError> {
+        if notifications.is_empty() {
+            return Ok(());
+        }
+
+        let count = notifications.len();
+        for notification in notifications {
+            self.publish(notification)?;
+        }
+
+        debug!("Queued {count} address notifications for transaction {signature}");
+        Ok(())
+    }
+
+    /// Count a vote in its slot summary, returning false when vote summaries are disabled
+    fn summarize_vote(&self, slot: u64, voter: &Pubkey, fee: u64) -> bool {
+        let Some(vote_summarizer) = &self.vote_summarizer else {
+            return false;
         };
 
-        self.connection_manager.send_message(message)?;
+        vote_summarizer.add_vote(slot, *voter, fee);
+        true
+    }
+
+    /// Add a totally ordered `orderingKey` and the plugin-wide `sequence` number
+    fn attach_ordering_key(&self, transaction_value: &mut Value, slot: u64, index: Option<usize>) {
+        let sequence = self.next_sequence();
+
+        if let Some(object) = transaction_value.as_object_mut() {
+            object.insert(
+                "orderingKey".to_string(),
+                json!(TransactionSerializer::ordering_key(slot, index)),
+            );
+            object.insert("sequence".to_string(), json!(sequence));
+        }
+    }
+
+    /// Take the next plugin-wide transaction sequence number
+    fn next_sequence(&self) -> u64 {
+        self.sequence.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Decode a vote transaction into a compact vote event when enabled
+    fn vote_event(
+        &self,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+    ) -> Option<Value> {
+        if !self.decode_votes || !is_vote {
+            return None;
+        }
+
+        let vote_event = TransactionSerializer::serialize_vote_event(transaction, meta, slot);
+        if vote_event.is_none() {
+            debug!(
+                "Failed to decode vote transaction {}, publishing it in full",
+                transaction.signature()
+            );
+        }
+        vote_event
+    }
+
+    /// Add the per-invocation compute breakdown to the serialized metadata when enabled
+    fn attach_compute_breakdown(
+        &self,
+        transaction_value: &mut Value,
+        meta: &TransactionStatusMeta,
+    ) {
+        if !self.compute_breakdown {
+            return;
+        }
+
+        if let Some(meta_value) = transaction_value
+            .get_mut("meta")
+            .and_then(Value::as_object_mut)
+        {
+            let log_messages = meta.log_messages.as_deref().unwrap_or_default();
+            meta_value.insert(
+                "computeBreakdown".to_string(),
+                TransactionSerializer::serialize_compute_breakdown(log_messages),
+            );
+        }
+    }
+
+    /// Add the tree of program invocations parsed from program logs when enabled
+    fn attach_parsed_logs(&self, transaction_value: &mut Value, meta: &TransactionStatusMeta) {
+        if !self.parsed_logs {
+            return;
+        }
+
+        if let Some(meta_value) = transaction_value
+            .get_mut("meta")
+            .and_then(Value::as_object_mut)
+        {
+            let log_messages = meta.log_messages.as_deref().unwrap_or_default();
+            meta_value.insert(
+                "parsedLogs".to_string(),
+                TransactionSerializer::serialize_parsed_logs(log_messages),
+            );
+        }
+    }
+
+    /// Add the decoded form of native and SPL program instructions when enabled; vote
+    /// events have no instructions to decode, and the RPC schema is already jsonParsed
+    fn attach_parsed_instructions(
+        &self,
+        transaction_value: &mut Value,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+    ) {
+        if self.parsed_instructions && self.transaction_schema == TransactionSchema::Plugin {
+            InstructionParser::attach(transaction_value, transaction, meta);
+        }
+    }
+
+    /// Remember the highest slot the validator has notified the plugin about
+    pub fn record_slot(&self, slot: u64) {
+        self.highest_slot.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    /// Publish a slot status update, if a slots subject is configured, and track dropped slots
+    pub fn process_slot_status(
+        &self,
+        slot: u64,
+        parent: Option<u64>,
+        status: &SlotStatus,
+    ) -> Result<(), ProcessingError> {
+        // Replay has moved past earlier slots; a late transaction only starts another batch
+        if let Some(transaction_batcher) = &self.transaction_batcher {
+            for batch in transaction_batcher.take_through(slot.saturating_sub(1)) {
+                self.publish(batch)?;
+            }
+        }
+
+        if let Some(slot_subject) = &self.slot_subject {
+            let update = TransactionSerializer::serialize_slot_status(slot, parent, status);
+            let payload = self.encode(update, "slot status")?;
+
+            self.publish(NatsMessage::new(slot_subject.clone(), payload).with_slot(slot))?;
+        }
+
+        self.publish_commitment_update(slot, status)?;
+
+        let Some(slot_tracker) = &self.slot_tracker else {
+            return Ok(());
+        };
+
+        if let Some(parent) = parent {
+            slot_tracker.record_parent(slot, parent);
+        }
+
+        let dropped_slots = match status {
+            SlotStatus::Dead(_) => slot_tracker.slot_dead(slot).into_iter().collect(),
+            SlotStatus::Rooted => slot_tracker.slot_rooted(slot),
+            _ => Vec::new(),
+        };
+
+        for dropped in dropped_slots {
+            // Buffered data of a dropped slot must never be published
+            if let Some(block_assembler) = &self.block_assembler {
+                block_assembler.take_slot(dropped.slot);
+            }
+            if let Some(vote_summarizer) = &self.vote_summarizer {
+                vote_summarizer.take_summary(dropped.slot);
+            }
+
+            let notice = TransactionSerializer::serialize_slot_dropped(&dropped);
+            let payload = self.encode(notice, "slot dropped")?;
+
+            self.publish(
+                NatsMessage::new(
+                    format!("{}.slot_dropped", self.control_subject_prefix),
+                    payload,
+                )
+                .with_slot(dropped.slot),
+            )?;
+
+            info!(
+                "Queued slot dropped notice for {} slot {} with {} transactions",
+                dropped.reason.as_str(),
+                dropped.slot,
+                dropped.signatures.len()
+            );
+        }
 
-        info!(
-            "Successfully queued transaction {} for NATS publish",
-            transaction_info.signature
-        );
         Ok(())
     }
 
-    /// Serialize and send V1 transaction
-    fn serialize_and_send_v1(
+    /// Remember a published or buffered transaction for slot drop and commitment tracking
+    fn track_transaction(&self, slot: u64, signature: &Signature) {
+        if let Some(slot_tracker) = &self.slot_tracker {
+            slot_tracker.record_transaction(slot, signature.to_string());
+        }
+        if let Some(commitment_tracker) = &self.commitment_tracker {
+            commitment_tracker.record_transaction(slot, signature.to_string());
+        }
+    }
+
+    /// Publish the signatures of a slot reaching `confirmed` or `rooted`
+    fn publish_commitment_update(
         &self,
-        transaction_info: &ReplicaTransactionInfo,
         slot: u64,
+        status: &SlotStatus,
     ) -> Result<(), ProcessingError> {
-        // Serialize transaction
-        let transaction_value =
-            TransactionSerializer::serialize_transaction_v1(transaction_info, slot)?;
+        let Some(commitment_tracker) = &self.commitment_tracker else {
+            return Ok(());
+        };
 
-        // Convert Value to JSON bytes
-        let payload = serde_json::to_vec(&transaction_value).map_err(|e| {
-            SerializationError::SerializationFailed {
-                msg: format!("Failed to convert transaction Value to JSON bytes: {e}"),
+        let commitment = match status {
+            SlotStatus::Confirmed => Commitment::Confirmed,
+            SlotStatus::Rooted => Commitment::Rooted,
+            SlotStatus::Dead(_) => {
+                commitment_tracker.forget(slot);
+                return Ok(());
             }
-        })?;
+            _ => return Ok(()),
+        };
 
-        // Create and send NATS message
-        let message = NatsMessage {
-            subject: self.subject.clone(),
-            payload,
+        let Some(signatures) = commitment_tracker.upgrade(slot, commitment) else {
+            return Ok(());
         };
+        if signatures.is_empty() {
+            return Ok(());
+        }
 
-        self.connection_manager.send_message(message)?;
+        let update =
+            TransactionSerializer::serialize_commitment_update(slot, commitment, &signatures);
+        let payload = self.encode(update, "commitment update")?;
+
+        self.publish(NatsMessage::new(self.commitment_subject.clone(), payload).with_slot(slot))?;
+
+        debug!(
+            "Queued {} commitment update for slot {slot} with {} signatures",
+            commitment.as_str(),
+            signatures.len()
+        );
+        Ok(())
+    }
+
+    /// The highest slot seen so far, if any
+    pub fn highest_slot(&self) -> Option<u64> {
+        match self.highest_slot.load(Ordering::Relaxed) {
+            0 => None,
+            slot => Some(slot),
+        }
+    }
+
+    /// Announce that startup is complete and live streaming begins
+    pub fn publish_startup_complete(&self) -> Result<(), ProcessingError> {
+        let marker = TransactionSerializer::serialize_startup_complete(self.highest_slot());
+
+        let payload = self.encode(marker, "startup marker")?;
+
+        self.publish(NatsMessage::new(
+            format!("{}.startup_complete", self.control_subject_prefix),
+            payload,
+        ))?;
 
         info!(
-            "Successfully queued transaction {} for NATS publish",
-            transaction_info.signature
+            "Queued startup complete marker at slot {:?}",
+            self.highest_slot()
+        );
+        Ok(())
+    }
+
+    /// Announce the shutdown and the highest published slot of every stream
+    pub fn publish_shutdown_notice(&self) -> Result<(), ProcessingError> {
+        let last_published_slots = self.stats.last_published_slots();
+        let notice = TransactionSerializer::serialize_shutdown_notice(&last_published_slots);
+
+        let payload = self.encode(notice, "shutdown notice")?;
+
+        self.publish(NatsMessage::new(
+            format!("{}.shutdown", self.control_subject_prefix),
+            payload,
+        ))?;
+
+        info!("Queued shutdown notice, last published slots: {last_published_slots:?}");
+        Ok(())
+    }
+
+    /// Publish a snapshot of the plugin statistics
+    pub fn publish_plugin_stats(&self) -> Result<(), ProcessingError> {
+        let mut snapshot = TransactionSerializer::serialize_plugin_stats(
+            &self.stats,
+            self.connection_manager.queue_depth(),
         );
+        if let Some(secondary) = &self.secondary_connection_manager {
+            snapshot["secondary"] = TransactionSerializer::serialize_cluster_stats(
+                &secondary.stats(),
+                secondary.queue_depth(),
+            );
+        }
+        let payload = self.encode(snapshot, "plugin stats")?;
+
+        self.publish(NatsMessage::new(self.plugin_stats_subject.clone(), payload))?;
+
+        debug!("Queued plugin stats");
         Ok(())
     }
 
-    /// Determine if a transaction should be processed based on filtering rules
-    fn should_process_transaction<'a>(
+    /// Wait for the queues of every configured cluster to drain, up to `timeout` each
+    pub fn drain(&self, timeout: Duration) {
+        if let Some(secondary) = &self.secondary_connection_manager {
+            secondary.drain(timeout);
+        }
+        self.connection_manager.drain(timeout);
+    }
+
+    /// Headers of a transaction message, empty unless enabled
+    pub fn transaction_headers(
         &self,
+        slot: u64,
+        signature: &Signature,
         is_vote: bool,
-        account_keys: impl Iterator<Item = &'a solana_sdk::pubkey::Pubkey>,
-    ) -> bool {
-        // Check if transaction should be processed at all
-        if is_vote {
-            debug!("Vote transaction detected");
-        } else {
-            debug!("Non-vote transaction detected");
+    ) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+
+        if self.metadata_headers {
+            headers.extend([
+                ("Content-Type", self.format.content_type().to_string()),
+                ("Solana-Slot", slot.to_string()),
+                ("Solana-Signature", signature.to_string()),
+                ("Solana-Is-Vote", is_vote.to_string()),
+            ]);
+        }
+
+        // A transaction replayed after a restart gets the same id, so a stream's
+        // duplicate window drops it
+        if self.message_ids {
+            headers.push(("Nats-Msg-Id", format!("{signature}:{slot}")));
+        }
+
+        headers
+    }
+
+    /// Convert a payload to bytes in the configured format, naming the network it was
+    /// published on and its schema version
+    fn encode(&self, mut value: Value, description: &str) -> Result<Vec<u8>, SerializationError> {
+        if let (Some(network), Some(object)) = (&self.network, value.as_object_mut()) {
+            object.insert("network".to_string(), json!(network));
+        }
+        TransactionSerializer::set_schema_version(&mut value);
+
+        TransactionSerializer::encode(&value, self.format, description)
+    }
+
+    /// Convert a transaction payload to bytes, shedding its logs while it is larger than
+    /// the configured payload limit
+    fn encode_transaction(
+        &self,
+        mut transaction_value: Value,
+    ) -> Result<Vec<u8>, SerializationError> {
+        let Some(log_limiter) = self
+            .log_limiter
+            .as_ref()
+            .filter(|log_limiter| log_limiter.limits_payload())
+        else {
+            return self.encode(transaction_value, "transaction");
+        };
+
+        let payload = self.encode(transaction_value.clone(), "transaction")?;
+        if !log_limiter.is_oversized(payload.len())
+            || !log_limiter.shed(&mut transaction_value, payload.len())
+        {
+            return Ok(payload);
         }
 
-        // Apply transaction selector rules
-        let selected = self
-            .transaction_selector
-            .is_transaction_selected(is_vote, Box::new(account_keys));
+        let payload = self.encode(transaction_value.clone(), "transaction")?;
+        if !log_limiter.is_oversized(payload.len()) {
+            return Ok(payload);
+        }
 
-        debug!("Transaction selector result: {selected}");
-        selected
+        log_limiter.shed_all(&mut transaction_value);
+        self.encode(transaction_value, "transaction")
+    }
+
+    /// Queue a transaction message, or add it to the batch of its subject when batching
+    fn publish_transaction(&self, message: NatsMessage) -> Result<(), ProcessingError> {
+        let Some(transaction_batcher) = &self.transaction_batcher else {
+            return self.publish(message);
+        };
+
+        for batch in transaction_batcher.add(message) {
+            self.publish(batch)?;
+        }
+        Ok(())
+    }
+
+    /// Publish the transactions still waiting in batches, before the plugin shuts down
+    pub fn flush_batches(&self) -> Result<(), ProcessingError> {
+        if let Some(transaction_batcher) = &self.transaction_batcher {
+            for batch in transaction_batcher.take_all() {
+                self.publish(batch)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue a message on every configured cluster
+    fn publish(&self, mut message: NatsMessage) -> Result<(), ProcessingError> {
+        // Drop work while every circuit is open rather than queue it for unreachable clusters
+        if self.all_circuits_open() {
+            self.record_message_dropped();
+            return Ok(());
+        }
+
+        // Subject based settings such as TTLs and JetStream match the prefixed subject
+        message.subject.insert_str(0, &self.subject_prefix);
+        if let Some(network) = &self.network {
+            message.set_header(NETWORK_HEADER, network.clone());
+        }
+        let mut message = match &self.compressor {
+            Some(compressor) => compressor.compress(message)?,
+            None => message,
+        };
+        // The digest covers the bytes consumers receive, compressed or not
+        if self.payload_digest {
+            message.set_header(PAYLOAD_DIGEST_HEADER, message.payload_digest());
+        }
+        if let Some(message_signer) = &self.message_signer {
+            message_signer.sign(&mut message);
+        }
+        let message = match self.message_ttl(&message.subject) {
+            Some(ttl_secs) => message.with_header("Nats-TTL", &format!("{ttl_secs}s")),
+            None => message,
+        };
+
+        // The secondary cluster must never block or fail publishing to the primary
+        if let Some(secondary) = &self.secondary_connection_manager {
+            if secondary.is_circuit_open() {
+                secondary.stats().record_message_dropped();
+            } else if let Err(e) = secondary.send_message(message.clone()) {
+                let stats = secondary.stats();
+                stats.record_publish_error();
+                stats.record_error(format!("Failed to queue message: {e}"));
+            }
+            self.warn_secondary_losses(secondary);
+        }
+
+        if self.connection_manager.is_circuit_open() {
+            self.stats.record_message_dropped();
+            return Ok(());
+        }
+        self.connection_manager.send_message(message)?;
+        Ok(())
+    }
+
+    /// Warn, at most once per `SECONDARY_WARNING_INTERVAL`, about the messages the secondary
+    /// cluster dropped or failed to publish since the last warning
+    fn warn_secondary_losses(&self, secondary: &ConnectionManager) {
+        let stats = secondary.stats();
+        let (dropped, errors) = (stats.messages_dropped(), stats.publish_errors());
+        let reported = self.secondary_reported_losses.load(Ordering::Relaxed);
+        if dropped + errors <= reported {
+            return;
+        }
+
+        let now = Utc::now().timestamp_millis() as u64;
+        let warned_at = self.secondary_warned_at.load(Ordering::Relaxed);
+        if now.saturating_sub(warned_at) < SECONDARY_WARNING_INTERVAL.as_millis() as u64
+            || self
+                .secondary_warned_at
+                .compare_exchange(warned_at, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        self.secondary_reported_losses
+            .store(dropped + errors, Ordering::Relaxed);
+
+        warn!(
+            "Secondary NATS cluster lost {} messages since the last warning ({dropped} dropped \
+             and {errors} publish errors in total), last error: {}",
+            dropped + errors - reported,
+            stats.last_error().as_deref().unwrap_or("none")
+        );
+    }
+
+    /// Whether the circuit of every configured cluster is open, so work would only be dropped
+    fn all_circuits_open(&self) -> bool {
+        self.connection_manager.is_circuit_open()
+            && self
+                .secondary_connection_manager
+                .as_ref()
+                .is_none_or(|secondary| secondary.is_circuit_open())
+    }
+
+    /// Count a message dropped before it was queued on any cluster
+    fn record_message_dropped(&self) {
+        self.stats.record_message_dropped();
+        if let Some(secondary) = &self.secondary_connection_manager {
+            secondary.stats().record_message_dropped();
+        }
+    }
+
+    /// The TTL of the first configured entry whose subject matches
+    pub fn message_ttl(&self, subject: &str) -> Option<u64> {
+        self.message_ttls
+            .iter()
+            .find(|message_ttl| subject_matches(&message_ttl.subject, subject))
+            .map(|message_ttl| message_ttl.ttl_secs)
+    }
+
+    /// Get the slot of a block metadata notification
+    fn block_slot(block_info: &ReplicaBlockInfoVersions) -> u64 {
+        match block_info {
+            ReplicaBlockInfoVersions::V0_0_1(info) => info.slot,
+            ReplicaBlockInfoVersions::V0_0_2(info) => info.slot,
+            ReplicaBlockInfoVersions::V0_0_3(info) => info.slot,
+            ReplicaBlockInfoVersions::V0_0_4(info) => info.slot,
+        }
+    }
+
+    /// Get the block time of a block metadata notification
+    fn block_time(block_info: &ReplicaBlockInfoVersions) -> Option<i64> {
+        match block_info {
+            ReplicaBlockInfoVersions::V0_0_1(info) => info.block_time,
+            ReplicaBlockInfoVersions::V0_0_2(info) => info.block_time,
+            ReplicaBlockInfoVersions::V0_0_3(info) => info.block_time,
+            ReplicaBlockInfoVersions::V0_0_4(info) => info.block_time,
+        }
+    }
+
+    /// Get the rewards of a block metadata notification
+    fn block_rewards<'a>(block_info: &ReplicaBlockInfoVersions<'a>) -> &'a [Reward] {
+        match block_info {
+            ReplicaBlockInfoVersions::V0_0_1(info) => info.rewards,
+            ReplicaBlockInfoVersions::V0_0_2(info) => info.rewards,
+            ReplicaBlockInfoVersions::V0_0_3(info) => info.rewards,
+            ReplicaBlockInfoVersions::V0_0_4(info) => &info.rewards.rewards,
+        }
+    }
+
+    /// Determine if a transaction was already published for its slot, counting it when it was
+    fn is_duplicate(&self, signature: &Signature, slot: u64) -> bool {
+        let Some(signature_deduplicator) = &self.signature_deduplicator else {
+            return false;
+        };
+
+        if !signature_deduplicator.is_duplicate(signature, slot) {
+            return false;
+        }
+        debug!("Transaction already published for slot {slot}: {signature}");
+        self.stats.record_transaction_deduplicated();
+        true
+    }
+
+    /// Determine if all the selected addresses a transaction matched are over their rate limit
+    fn is_address_rate_limited(
+        &self,
+        filters: &TransactionFilters,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        transaction_status_meta: &TransactionStatusMeta,
+    ) -> bool {
+        let Some(address_rate_limiter) = &self.address_rate_limiter else {
+            return false;
+        };
+
+        let matched = self.transaction_selector().matched_addresses(
+            is_vote,
+            transaction_router::matched_account_keys(
+                transaction.message(),
+                &transaction_status_meta.loaded_addresses,
+                filters.filter.address_match(),
+            ),
+        );
+        !address_rate_limiter.allow(&matched, Instant::now())
+    }
+
+    /// Determine if a selected transaction should be dropped because the publish queue is backed up
+    fn should_shed_transaction<'a>(
+        &self,
+        is_vote: bool,
+        account_keys: impl Iterator<Item = &'a Pubkey>,
+    ) -> bool {
+        let Some(load_shedder) = &self.load_shedder else {
+            return false;
+        };
+
+        load_shedder.should_shed(self.connection_manager.queue_depth(), is_vote, account_keys)
     }
 
     /// Check if the processor is configured to handle any transactions
     pub fn is_enabled(&self) -> bool {
-        self.transaction_selector.is_enabled()
+        self.transaction_selector().is_enabled()
+    }
+
+    /// Get the transaction selector, as last updated at runtime when filter control is
+    /// enabled
+    pub fn transaction_selector(&self) -> Arc<TransactionSelector> {
+        match &self.filter_control {
+            Some(filter_control) => filter_control.selector(),
+            None => self.filters().filter.selector().clone(),
+        }
+    }
+
+    /// Get the transaction router, if routes are configured
+    pub fn transaction_router(&self) -> Option<Arc<TransactionRouter>> {
+        self.filters().transaction_router.clone()
+    }
+
+    /// The filter, routing and transaction subject settings currently applied
+    fn filters(&self) -> Arc<TransactionFilters> {
+        self.filters.read().unwrap().clone()
+    }
+
+    /// Apply the filter, routes and transaction subjects of a reloaded configuration to
+    /// the transactions processed from now on
+    pub fn reload_filters(&self, config: &NatsPluginConfig) -> Result<(), ProcessingError> {
+        info!(
+            "Reloading filters with subject: {}, vote subject: {}, {} routes",
+            config.transaction_subject(),
+            config.vote_transaction_subject(),
+            config.routes.len()
+        );
+        *self.filters.write().unwrap() = Arc::new(TransactionFilters::new(config)?);
+        Ok(())
+    }
+
+    /// Get a reference to the vote summarizer, if vote summaries are enabled
+    pub fn vote_summarizer(&self) -> Option<&VoteSummarizer> {
+        self.vote_summarizer.as_ref()
+    }
+
+    /// Get a reference to the signature deduplicator, if deduplication is enabled
+    pub fn signature_deduplicator(&self) -> Option<&SignatureDeduplicator> {
+        self.signature_deduplicator.as_ref()
+    }
+
+    /// Get a reference to the address rate limiter, if an address rate limit is set
+    pub fn address_rate_limiter(&self) -> Option<&AddressRateLimiter> {
+        self.address_rate_limiter.as_ref()
+    }
+
+    /// Get a reference to the load shedder, if load shedding is enabled
+    pub fn load_shedder(&self) -> Option<&LoadShedder> {
+        self.load_shedder.as_ref()
+    }
+
+    /// Get a reference to the transaction batcher, if batching is enabled
+    pub fn transaction_batcher(&self) -> Option<&TransactionBatcher> {
+        self.transaction_batcher.as_ref()
+    }
+
+    /// Get a reference to the block assembler, if block assembly is enabled
+    pub fn block_assembler(&self) -> Option<&BlockAssembler> {
+        self.block_assembler.as_ref()
     }
 
-    /// Get a reference to the transaction selector
-    pub fn transaction_selector(&self) -> &TransactionSelector {
-        &self.transaction_selector
+    /// Get a reference to the slot tracker, if slot drop notifications are enabled
+    pub fn slot_tracker(&self) -> Option<&SlotTracker> {
+        self.slot_tracker.as_ref()
+    }
+
+    /// Get a reference to the commitment tracker, if commitment updates are enabled
+    pub fn commitment_tracker(&self) -> Option<&CommitmentTracker> {
+        self.commitment_tracker.as_ref()
+    }
+}
+
+/// The filter, routing and transaction subject settings, swapped as a whole when the
+/// configuration is reloaded
+struct TransactionFilters {
+    filter: TransactionFilter,
+    transaction_router: Option<Arc<TransactionRouter>>,
+    transaction_subject: String,
+    vote_transaction_subject: String,
+}
+
+impl TransactionFilters {
+    fn new(config: &NatsPluginConfig) -> Result<Self, SelectorError> {
+        debug!("Filter configuration: {:?}", config.filter);
+
+        Ok(Self {
+            filter: TransactionFilter::new(&config.filter)?,
+            transaction_router: TransactionRouter::new(&config.routes)?.map(Arc::new),
+            transaction_subject: config.transaction_subject().to_string(),
+            vote_transaction_subject: config.vote_transaction_subject().to_string(),
+        })
+    }
+
+    /// The subject a transaction is published to
+    fn transaction_subject(&self, is_vote: bool) -> &str {
+        if is_vote {
+            &self.vote_transaction_subject
+        } else {
+            &self.transaction_subject
+        }
+    }
+
+    /// Subjects a transaction is published to: its transaction or vote subject when the
+    /// filter selects it, or the subjects of the routes selecting it; empty when filtered out.
+    /// Counts in `stats` how every configured rule the transaction reaches decides
+    #[allow(clippy::too_many_arguments)]
+    fn select_transaction(
+        &self,
+        transaction_selector: &TransactionSelector,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        signature: &Signature,
+        slot: u64,
+        stats: &PluginStats,
+    ) -> Vec<&str> {
+        let Some(transaction_router) = &self.transaction_router else {
+            if !self.filter.matches_with_selector(
+                transaction_selector,
+                is_vote,
+                transaction,
+                meta,
+                signature,
+                slot,
+                Some(stats),
+            ) {
+                return Vec::new();
+            }
+            return vec![self.transaction_subject(is_vote)];
+        };
+
+        // Excluded programs of the top-level filter apply to every route
+        if self
+            .filter
+            .invokes_excluded_program(transaction, Some(stats))
+        {
+            debug!("Transaction invokes an excluded program: {signature}");
+            return Vec::new();
+        }
+        let subjects =
+            transaction_router.subjects_with_stats(is_vote, transaction, meta, slot, stats);
+        if subjects.is_empty() {
+            debug!("Transaction matches no route: {signature}");
+        }
+        subjects
     }
 }
@@ -0,0 +1,85 @@
+use {
+    crate::config::RateLimitConfig,
+    std::time::{Duration, Instant},
+};
+
+/// Token bucket refilled at a constant rate, holding at most one second of tokens
+pub(crate) struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled_at = now;
+    }
+
+    /// Take `amount` tokens, returning how long to wait until the bucket is out of debt
+    fn take(&mut self, amount: f64, now: Instant) -> Duration {
+        self.refill(now);
+
+        // Going into debt lets a message larger than the burst through after a wait
+        self.tokens -= amount;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// Take one token if one is left, never going into debt
+    pub(crate) fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Limits the rate of published messages and bytes
+pub struct RateLimiter {
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter, or `None` when no limit is configured
+    pub fn new(config: &RateLimitConfig) -> Option<Self> {
+        let now = Instant::now();
+        let messages =
+            (config.messages_per_sec > 0).then(|| TokenBucket::new(config.messages_per_sec, now));
+        let bytes = (config.bytes_per_sec > 0).then(|| TokenBucket::new(config.bytes_per_sec, now));
+
+        (messages.is_some() || bytes.is_some()).then_some(Self { messages, bytes })
+    }
+
+    /// Account for a message of `size` bytes published at `now`, returning how long
+    /// to wait before publishing it
+    pub fn acquire(&mut self, size: usize, now: Instant) -> Duration {
+        let message_delay = self
+            .messages
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.take(1.0, now));
+        let byte_delay = self
+            .bytes
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.take(size as f64, now));
+
+        message_delay.max(byte_delay)
+    }
+}
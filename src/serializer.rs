@@ -1,11 +1,22 @@
 use {
+    crate::{
+        commitment_tracker::Commitment, config::PayloadFormat, slot_tracker::DroppedSlot,
+        stats::PluginStats, vote_summarizer::VoteSummary,
+    },
     agave_geyser_plugin_interface::geyser_plugin_interface::{
-        ReplicaTransactionInfo, ReplicaTransactionInfoV2,
+        ReplicaBlockInfoVersions, ReplicaTransactionInfo, ReplicaTransactionInfoV2, SlotStatus,
     },
     base64::{engine::general_purpose, Engine as _},
+    chrono::{SecondsFormat, Utc},
     log::{debug, info},
     serde_json::{json, Value},
-    solana_transaction_status::TransactionStatusMeta,
+    solana_sdk::{compute_budget, signature::Signature, transaction::SanitizedTransaction},
+    solana_transaction_status::{
+        ConfirmedTransactionWithStatusMeta, InnerInstructions, Reward, TransactionStatusMeta,
+        TransactionWithStatusMeta, UiTransactionEncoding, VersionedTransactionWithStatusMeta,
+    },
+    solana_vote::vote_parser,
+    std::collections::BTreeMap,
     thiserror::Error,
 };
 
@@ -21,25 +32,74 @@ pub enum SerializationError {
     InvalidFormat { msg: String },
 }
 
+/// Number of low bits of an ordering key holding the transaction index within its slot
+pub const ORDERING_KEY_INDEX_BITS: u32 = 20;
+
+/// Version of the payload schema, published as `schemaVersion` in every message.
+///
+/// Adding a field, message type or subject keeps the version, so consumers must ignore
+/// fields they do not know. Removing or renaming a field, or changing its type or meaning,
+/// bumps it, so consumers can reject or adapt payloads of a version they do not support
+pub const SCHEMA_VERSION: u32 = 2;
+
 pub struct TransactionSerializer;
 
 impl TransactionSerializer {
+    /// Compute `slot << 20 | index`, ordering transactions across slots and within a block
+    pub fn ordering_key(slot: u64, index: Option<usize>) -> u64 {
+        let index_mask = (1u64 << ORDERING_KEY_INDEX_BITS) - 1;
+        let index = index.map_or(0, |index| index as u64 & index_mask);
+        (slot << ORDERING_KEY_INDEX_BITS) | index
+    }
+
     /// Serialize a V2 transaction to NATS message format
     pub fn serialize_transaction_v2(
         transaction_info: &ReplicaTransactionInfoV2,
         slot: u64,
     ) -> Result<Value, SerializationError> {
-        info!("Serializing V2 transaction for slot {slot}");
+        Self::serialize_transaction(
+            transaction_info.transaction,
+            transaction_info.transaction_status_meta,
+            slot,
+            Some(transaction_info.index),
+            transaction_info.is_vote,
+        )
+    }
+
+    /// Serialize a V1 transaction to NATS message format
+    pub fn serialize_transaction_v1(
+        transaction_info: &ReplicaTransactionInfo,
+        slot: u64,
+    ) -> Result<Value, SerializationError> {
+        Self::serialize_transaction(
+            transaction_info.transaction,
+            transaction_info.transaction_status_meta,
+            slot,
+            None,
+            transaction_info.is_vote,
+        )
+    }
+
+    /// Serialize a transaction to NATS message format. The index within the block is
+    /// `None` for V1 notifications, which do not carry it
+    pub fn serialize_transaction(
+        transaction: &SanitizedTransaction,
+        transaction_status_meta: &TransactionStatusMeta,
+        slot: u64,
+        index: Option<usize>,
+        is_vote: bool,
+    ) -> Result<Value, SerializationError> {
+        info!("Serializing transaction for slot {slot}");
 
         // Convert SanitizedTransaction back to VersionedTransaction
         // This gives us the proper version detection and message structure
-        let versioned_tx = transaction_info.transaction.to_versioned_transaction();
+        let versioned_tx = transaction.to_versioned_transaction();
 
-        let (version, message_json) = Self::serialize_versioned_transaction(&versioned_tx)?;
+        let (version, message_json) =
+            Self::serialize_versioned_transaction(&versioned_tx, transaction)?;
 
         // Serialize signatures
-        let signatures: Vec<String> = transaction_info
-            .transaction
+        let signatures: Vec<String> = transaction
             .signatures()
             .iter()
             .map(|sig| sig.to_string())
@@ -51,59 +111,445 @@ impl TransactionSerializer {
             "message": message_json
         });
 
-        // Build final message
+        // Build final message; the block time is only known once the block metadata arrives
         let result = json!({
             "transaction": transaction_obj,
             "version": version,
             "slot": slot,
-            "meta": Self::serialize_transaction_meta(Some(transaction_info.transaction_status_meta)),
+            "feePayer": transaction.message().fee_payer().to_string(),
+            "index": index,
+            "isVote": is_vote,
+            "blockTime": null,
+            "meta": Self::serialize_transaction_meta(
+                transaction,
+                Some(transaction_status_meta),
+            ),
         });
 
-        debug!("Successfully serialized V2 transaction");
+        debug!("Successfully serialized transaction");
         Ok(result)
     }
 
-    /// Serialize a V1 transaction to NATS message format  
-    pub fn serialize_transaction_v1(
-        transaction_info: &ReplicaTransactionInfo,
+    /// Serialize a transaction as the base64 of its wire format, in an envelope naming
+    /// its slot, signature and error
+    pub fn serialize_raw_transaction(
+        signature: &Signature,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
         slot: u64,
     ) -> Result<Value, SerializationError> {
-        info!("Serializing V1 transaction for slot {slot}");
+        let wire_transaction = bincode::serialize(&transaction.to_versioned_transaction())
+            .map_err(|e| SerializationError::SerializationFailed {
+                msg: format!("Failed to serialize transaction {signature} to wire format: {e}"),
+            })?;
 
-        // Convert SanitizedTransaction back to VersionedTransaction
-        let versioned_tx = transaction_info.transaction.to_versioned_transaction();
+        Ok(json!({
+            "slot": slot,
+            "signature": signature.to_string(),
+            "err": meta.status.is_err().then(|| format!("{:?}", meta.status)),
+            "transaction": [general_purpose::STANDARD.encode(wire_transaction), "base64"],
+        }))
+    }
 
-        let (version, message_json) = Self::serialize_versioned_transaction(&versioned_tx)?;
+    /// Serialize a transaction through the RPC's own encoder, exactly like the result of
+    /// `getTransaction` with the json, or with `parsed` the jsonParsed, encoding
+    pub fn serialize_rpc_transaction(
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+        parsed: bool,
+    ) -> Result<Value, SerializationError> {
+        let encoding = if parsed {
+            UiTransactionEncoding::JsonParsed
+        } else {
+            UiTransactionEncoding::Json
+        };
+        let confirmed = ConfirmedTransactionWithStatusMeta {
+            slot,
+            tx_with_meta: TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+                transaction: transaction.to_versioned_transaction(),
+                meta: meta.clone(),
+            }),
+            // The block time is only known once the block metadata arrives
+            block_time: None,
+        };
 
-        // Serialize signatures
-        let signatures: Vec<String> = transaction_info
-            .transaction
-            .signatures()
+        let encoded =
+            confirmed
+                .encode(encoding, Some(0))
+                .map_err(|e| SerializationError::InvalidFormat {
+                    msg: format!("Failed to encode transaction for RPC: {e}"),
+                })?;
+        serde_json::to_value(encoded).map_err(|e| SerializationError::SerializationFailed {
+            msg: format!("Failed to convert RPC transaction to JSON: {e}"),
+        })
+    }
+
+    /// Serialize block metadata together with the block's transactions
+    pub fn serialize_block(
+        block_info: &ReplicaBlockInfoVersions,
+        transactions: Vec<Value>,
+    ) -> Result<Value, SerializationError> {
+        let result = match block_info {
+            ReplicaBlockInfoVersions::V0_0_1(info) => json!({
+                "slot": info.slot,
+                "blockhash": info.blockhash,
+                "blockTime": info.block_time,
+                "blockHeight": info.block_height,
+                "rewards": Self::serialize_rewards(info.rewards),
+                "transactions": transactions,
+            }),
+            ReplicaBlockInfoVersions::V0_0_2(info) => json!({
+                "slot": info.slot,
+                "parentSlot": info.parent_slot,
+                "blockhash": info.blockhash,
+                "parentBlockhash": info.parent_blockhash,
+                "blockTime": info.block_time,
+                "blockHeight": info.block_height,
+                "executedTransactionCount": info.executed_transaction_count,
+                "rewards": Self::serialize_rewards(info.rewards),
+                "transactions": transactions,
+            }),
+            ReplicaBlockInfoVersions::V0_0_3(info) => json!({
+                "slot": info.slot,
+                "parentSlot": info.parent_slot,
+                "blockhash": info.blockhash,
+                "parentBlockhash": info.parent_blockhash,
+                "blockTime": info.block_time,
+                "blockHeight": info.block_height,
+                "executedTransactionCount": info.executed_transaction_count,
+                "rewards": Self::serialize_rewards(info.rewards),
+                "transactions": transactions,
+            }),
+            ReplicaBlockInfoVersions::V0_0_4(info) => json!({
+                "slot": info.slot,
+                "parentSlot": info.parent_slot,
+                "blockhash": info.blockhash,
+                "parentBlockhash": info.parent_blockhash,
+                "blockTime": info.block_time,
+                "blockHeight": info.block_height,
+                "executedTransactionCount": info.executed_transaction_count,
+                "rewards": Self::serialize_rewards(&info.rewards.rewards),
+                "transactions": transactions,
+            }),
+        };
+
+        debug!("Successfully serialized block");
+        Ok(result)
+    }
+
+    /// Serialize the slim notification published to the subject of a mentioned account
+    pub fn serialize_address_notification(
+        signature: &Signature,
+        slot: u64,
+        is_vote: bool,
+        writable: bool,
+        meta: &TransactionStatusMeta,
+    ) -> Value {
+        json!({
+            "signature": signature.to_string(),
+            "slot": slot,
+            "isVote": is_vote,
+            "writable": writable,
+            "err": meta.status.is_err().then(|| format!("{:?}", meta.status)),
+        })
+    }
+
+    /// Serialize the vote a vote transaction casts, or `None` if it cannot be decoded
+    pub fn serialize_vote_event(
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+    ) -> Option<Value> {
+        let (vote_account, vote, switch_proof_hash, signature) =
+            vote_parser::parse_sanitized_vote_transaction(transaction)?;
+
+        Some(json!({
+            "event": "vote_event",
+            "signature": signature.to_string(),
+            "slot": slot,
+            "voteAccount": vote_account.to_string(),
+            "votedSlots": vote.slots(),
+            "hash": vote.hash().to_string(),
+            "timestamp": vote.timestamp(),
+            "switchProofHash": switch_proof_hash.map(|hash| hash.to_string()),
+            "fee": meta.fee,
+            "err": meta.status.is_err().then(|| format!("{:?}", meta.status)),
+        }))
+    }
+
+    /// Serialize the reward events of a block
+    pub fn serialize_block_rewards(slot: u64, rewards: &[Reward]) -> Value {
+        json!({
+            "slot": slot,
+            "rewards": Self::serialize_rewards(rewards),
+        })
+    }
+
+    /// Serialize the vote activity of a slot
+    pub fn serialize_vote_summary(slot: u64, summary: &VoteSummary) -> Value {
+        let mut voters: Vec<String> = summary
+            .voters
             .iter()
-            .map(|sig| sig.to_string())
+            .map(|voter| voter.to_string())
             .collect();
+        voters.sort();
 
-        // Build transaction object
-        let transaction_obj = json!({
+        json!({
+            "slot": slot,
+            "voteCount": summary.vote_count,
+            "uniqueVoters": voters.len(),
+            "totalFeeLamports": summary.total_fee_lamports,
+            "voters": voters,
+        })
+    }
+
+    /// Serialize a slot status update
+    pub fn serialize_slot_status(slot: u64, parent: Option<u64>, status: &SlotStatus) -> Value {
+        json!({
+            "slot": slot,
+            "parent": parent,
+            "status": status.as_str(),
+            "err": match status {
+                SlotStatus::Dead(err) => Some(err),
+                _ => None,
+            },
+        })
+    }
+
+    /// Serialize the commitment upgrade of the transactions published for a slot
+    pub fn serialize_commitment_update(
+        slot: u64,
+        commitment: Commitment,
+        signatures: &[String],
+    ) -> Value {
+        json!({
+            "slot": slot,
+            "commitment": commitment.as_str(),
             "signatures": signatures,
-            "message": message_json
-        });
+        })
+    }
 
-        // Build final message
-        let result = json!({
-            "transaction": transaction_obj,
-            "version": version,
+    /// Serialize the control message invalidating the transactions of a dropped slot
+    pub fn serialize_slot_dropped(dropped: &DroppedSlot) -> Value {
+        json!({
+            "event": "slot_dropped",
+            "slot": dropped.slot,
+            "parent": dropped.parent,
+            "reason": dropped.reason.as_str(),
+            "rootedSlot": dropped.rooted_slot,
+            "signatures": dropped.signatures,
+        })
+    }
+
+    /// Serialize the reference published in place of a message exceeding the server's max_payload
+    pub fn serialize_oversized_message(
+        subject: &str,
+        size: usize,
+        max_payload: usize,
+        slot: Option<u64>,
+        headers: &[(String, String)],
+    ) -> Value {
+        json!({
+            "event": "oversized_message",
+            "subject": subject,
+            "size": size,
+            "maxPayload": max_payload,
             "slot": slot,
-            "meta": Self::serialize_transaction_meta(Some(transaction_info.transaction_status_meta)),
-        });
+            "headers": headers.iter().cloned().collect::<BTreeMap<_, _>>(),
+        })
+    }
 
-        debug!("Successfully serialized V1 transaction");
-        Ok(result)
+    /// Serialize the pointer published in place of a message whose payload was uploaded to
+    /// an object store
+    pub fn serialize_offloaded_payload(
+        bucket: &str,
+        object: &str,
+        digest: Option<&str>,
+        size: usize,
+        slot: Option<u64>,
+        content_encoding: Option<&str>,
+    ) -> Value {
+        json!({
+            "event": "offloaded_payload",
+            "bucket": bucket,
+            "object": object,
+            "digest": digest,
+            "size": size,
+            "slot": slot,
+            "contentEncoding": content_encoding,
+        })
+    }
+
+    /// Mark a serialized message with the payload schema version
+    pub fn set_schema_version(value: &mut Value) {
+        if let Some(object) = value.as_object_mut() {
+            object.insert("schemaVersion".to_string(), json!(SCHEMA_VERSION));
+        }
+    }
+
+    /// Encode a serialized value as the bytes of a payload in the given format
+    pub fn encode(
+        value: &Value,
+        format: PayloadFormat,
+        description: &str,
+    ) -> Result<Vec<u8>, SerializationError> {
+        match format {
+            PayloadFormat::Json | PayloadFormat::Raw => {
+                let mut payload = Vec::new();
+                Self::write_canonical_json(value, &mut payload).map_err(|e| {
+                    SerializationError::SerializationFailed {
+                        msg: format!("Failed to convert {description} Value to JSON bytes: {e}"),
+                    }
+                })?;
+                Ok(payload)
+            }
+            PayloadFormat::Msgpack => rmp_serde::to_vec_named(value).map_err(|e| {
+                SerializationError::SerializationFailed {
+                    msg: format!("Failed to convert {description} Value to MessagePack bytes: {e}"),
+                }
+            }),
+            PayloadFormat::Cbor => {
+                let mut payload = Vec::new();
+                ciborium::into_writer(value, &mut payload).map_err(|e| {
+                    SerializationError::SerializationFailed {
+                        msg: format!("Failed to convert {description} Value to CBOR bytes: {e}"),
+                    }
+                })?;
+                Ok(payload)
+            }
+        }
+    }
+
+    /// Write a value as canonical JSON, so equal payloads have equal bytes whatever the
+    /// plugin version or serde_json features: object keys sorted by their bytes, no
+    /// whitespace, and floats holding an integer written as integers
+    pub fn write_canonical_json(value: &Value, out: &mut Vec<u8>) -> Result<(), serde_json::Error> {
+        match value {
+            Value::Object(object) => {
+                let mut fields: Vec<_> = object.iter().collect();
+                fields.sort_unstable_by_key(|(key, _)| *key);
+                out.push(b'{');
+                for (position, (key, field)) in fields.into_iter().enumerate() {
+                    if position > 0 {
+                        out.push(b',');
+                    }
+                    serde_json::to_writer(&mut *out, key)?;
+                    out.push(b':');
+                    Self::write_canonical_json(field, out)?;
+                }
+                out.push(b'}');
+            }
+            Value::Array(values) => {
+                out.push(b'[');
+                for (position, element) in values.iter().enumerate() {
+                    if position > 0 {
+                        out.push(b',');
+                    }
+                    Self::write_canonical_json(element, out)?;
+                }
+                out.push(b']');
+            }
+            Value::Number(number) => match number.as_f64() {
+                // Integral floats below 2^53 are exact as integers, with -0 written as 0
+                Some(float)
+                    if !number.is_i64()
+                        && !number.is_u64()
+                        && float.fract() == 0.0
+                        && float.abs() < 9_007_199_254_740_992.0 =>
+                {
+                    out.extend_from_slice((float as i64).to_string().as_bytes());
+                }
+                _ => serde_json::to_writer(&mut *out, number)?,
+            },
+            _ => serde_json::to_writer(&mut *out, value)?,
+        }
+        Ok(())
+    }
+
+    /// Serialize an event in the lifecycle of a NATS connection
+    pub fn serialize_lifecycle_event(
+        event: &str,
+        cluster: &str,
+        client_name: &str,
+        server: Option<&str>,
+        reason: Option<&str>,
+        attempt: Option<u32>,
+    ) -> Value {
+        json!({
+            "event": event,
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "cluster": cluster,
+            "clientName": client_name,
+            "server": server,
+            "reason": reason,
+            "attempt": attempt,
+        })
+    }
+
+    /// Serialize the control message marking the end of validator startup
+    pub fn serialize_startup_complete(slot: Option<u64>) -> Value {
+        json!({
+            "event": "startup_complete",
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "slot": slot,
+        })
+    }
+
+    /// Serialize the control message announcing the plugin shut down and where each stream stopped
+    pub fn serialize_shutdown_notice(last_published_slots: &BTreeMap<String, u64>) -> Value {
+        json!({
+            "event": "shutdown",
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "lastPublishedSlot": last_published_slots.values().max(),
+            "lastPublishedSlots": last_published_slots,
+        })
+    }
+
+    /// Serialize the periodic statistics of the plugin
+    pub fn serialize_plugin_stats(stats: &PluginStats, queue_depth: usize) -> Value {
+        let (filters, routes) = stats.filter_stats_json();
+        json!({
+            "event": "plugin_stats",
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "queued": stats.messages_queued(),
+            "published": stats.messages_published(),
+            "dropped": stats.messages_dropped(),
+            "reconnects": stats.reconnects(),
+            "lastSlot": stats.last_published_slot(),
+            "queueDepth": queue_depth,
+            "queuedBytes": stats.queued_bytes(),
+            "connected": stats.is_connected(),
+            "filters": filters,
+            "routes": routes,
+        })
+    }
+
+    /// Serialize the counters of a cluster connection other than the primary
+    pub fn serialize_cluster_stats(stats: &PluginStats, queue_depth: usize) -> Value {
+        json!({
+            "queued": stats.messages_queued(),
+            "published": stats.messages_published(),
+            "dropped": stats.messages_dropped(),
+            "publishErrors": stats.publish_errors(),
+            "lastError": stats.last_error(),
+            "reconnects": stats.reconnects(),
+            "queueDepth": queue_depth,
+            "queuedBytes": stats.queued_bytes(),
+            "connected": stats.is_connected(),
+            "degraded": stats.is_degraded(),
+        })
+    }
+
+    /// Serialize block or transaction rewards in RPC-compatible shape
+    fn serialize_rewards(rewards: &[Reward]) -> Value {
+        json!(rewards)
     }
 
     /// Serialize a VersionedTransaction to get proper version and message structure
     fn serialize_versioned_transaction(
         versioned_tx: &solana_sdk::transaction::VersionedTransaction,
+        transaction: &SanitizedTransaction,
     ) -> Result<(Value, Value), SerializationError> {
         debug!("Processing versioned transaction");
 
@@ -111,12 +557,21 @@ impl TransactionSerializer {
         // The to_versioned_transaction() method preserves the original version info
         let version = json!(0);
 
-        // Create V0 message structure with addressTableLookups
-        let account_keys: Vec<String> = versioned_tx
+        // Create V0 message structure with addressTableLookups, flagging account keys like
+        // the RPC's jsonParsed encoding
+        let message = transaction.message();
+        let account_keys: Vec<Value> = versioned_tx
             .message
             .static_account_keys()
             .iter()
-            .map(|key| key.to_string())
+            .enumerate()
+            .map(|(index, key)| {
+                json!({
+                    "pubkey": key.to_string(),
+                    "signer": message.is_signer(index),
+                    "writable": message.is_writable(index),
+                })
+            })
             .collect();
 
         let instructions: Vec<Value> = versioned_tx
@@ -138,22 +593,65 @@ impl TransactionSerializer {
             "numReadonlyUnsignedAccounts": versioned_tx.message.header().num_readonly_unsigned_accounts
         });
 
-        // Create V0 message format with addressTableLookups (this is the key improvement)
+        // Legacy messages have no lookups, an empty list keeps the V0 format
+        let address_table_lookups: Vec<Value> = versioned_tx
+            .message
+            .address_table_lookups()
+            .unwrap_or_default()
+            .iter()
+            .map(|lookup| {
+                json!({
+                    "accountKey": lookup.account_key.to_string(),
+                    "writableIndexes": lookup.writable_indexes,
+                    "readonlyIndexes": lookup.readonly_indexes,
+                })
+            })
+            .collect();
+
         let message_json = json!({
             "accountKeys": account_keys,
             "header": header,
             "instructions": instructions,
             "recentBlockhash": versioned_tx.message.recent_blockhash().to_string(),
-            "addressTableLookups": [] // Empty array for V0 format compatibility
+            "addressTableLookups": address_table_lookups,
         });
 
         Ok((version, message_json))
     }
 
-    /// Serialize transaction metadata
-    fn serialize_transaction_meta(meta: Option<&TransactionStatusMeta>) -> Value {
+    /// Compute unit limit and price, in micro-lamports per compute unit, set by a
+    /// transaction's Compute Budget instructions, `None` when it does not set them
+    pub(crate) fn requested_compute_budget(
+        transaction: &SanitizedTransaction,
+    ) -> (Option<u32>, Option<u64>) {
+        let mut compute_unit_limit = None;
+        let mut compute_unit_price = None;
+        for (program_id, instruction) in transaction.message().program_instructions_iter() {
+            if !compute_budget::check_id(program_id) {
+                continue;
+            }
+            match instruction.data.split_first() {
+                Some((2, value)) => {
+                    compute_unit_limit = value.try_into().ok().map(u32::from_le_bytes);
+                }
+                Some((3, value)) => {
+                    compute_unit_price = value.try_into().ok().map(u64::from_le_bytes);
+                }
+                _ => {}
+            }
+        }
+        (compute_unit_limit, compute_unit_price)
+    }
+
+    /// Serialize transaction metadata, with the compute budget the transaction requested
+    fn serialize_transaction_meta(
+        transaction: &SanitizedTransaction,
+        meta: Option<&TransactionStatusMeta>,
+    ) -> Value {
         match meta {
             Some(meta) => {
+                let (compute_unit_limit, compute_unit_price) =
+                    Self::requested_compute_budget(transaction);
                 json!({
                     "err": meta.status.is_err().then(|| format!("{:?}", meta.status)),
                     "fee": meta.fee,
@@ -161,9 +659,222 @@ impl TransactionSerializer {
                     "postBalances": meta.post_balances,
                     "logMessages": meta.log_messages.as_ref().unwrap_or(&vec![]),
                     "computeUnitsConsumed": meta.compute_units_consumed,
+                    "computeUnitLimit": compute_unit_limit,
+                    "computeUnitPrice": compute_unit_price,
+                    "loadedAddresses": {
+                        "writable": meta
+                            .loaded_addresses
+                            .writable
+                            .iter()
+                            .map(|key| key.to_string())
+                            .collect::<Vec<_>>(),
+                        "readonly": meta
+                            .loaded_addresses
+                            .readonly
+                            .iter()
+                            .map(|key| key.to_string())
+                            .collect::<Vec<_>>(),
+                    },
+                    "innerInstructions": meta
+                        .inner_instructions
+                        .as_deref()
+                        .map(Self::serialize_inner_instructions),
+                    "rewards": meta.rewards.as_deref().map(Self::serialize_rewards),
+                    "returnData": meta.return_data.as_ref().map(|return_data| {
+                        json!({
+                            "programId": return_data.program_id.to_string(),
+                            "data": [general_purpose::STANDARD.encode(&return_data.data), "base64"],
+                        })
+                    }),
                 })
             }
             None => json!(null),
         }
     }
+
+    /// Serialize the instructions invoked by each top-level instruction in the RPC shape,
+    /// with the data base64 encoded like the transaction's own instructions
+    fn serialize_inner_instructions(inner_instructions: &[InnerInstructions]) -> Value {
+        inner_instructions
+            .iter()
+            .map(|inner| {
+                json!({
+                    "index": inner.index,
+                    "instructions": inner
+                        .instructions
+                        .iter()
+                        .map(|ix| {
+                            json!({
+                                "programIdIndex": ix.instruction.program_id_index,
+                                "accounts": ix.instruction.accounts,
+                                "data": general_purpose::STANDARD.encode(&ix.instruction.data),
+                                "stackHeight": ix.stack_height,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect()
+    }
+
+    /// Parse per-invocation compute usage from "Program <id> consumed X of Y compute units" logs
+    pub fn serialize_compute_breakdown(log_messages: &[String]) -> Value {
+        let tree = ProgramInvocation::parse(log_messages);
+        let mut invocations = Vec::new();
+        for invocation in &tree {
+            invocation.flatten(&mut invocations);
+        }
+
+        invocations
+            .iter()
+            .map(|invocation| {
+                json!({
+                    "programId": invocation.program_id,
+                    "depth": invocation.depth,
+                    "computeUnitsConsumed": invocation.compute_units_consumed,
+                    "computeUnitsLimit": invocation.compute_units_limit,
+                })
+            })
+            .collect()
+    }
+
+    /// Parse program logs into a tree of invocations, each with its program id, depth,
+    /// outcome, compute usage, `Program log:` messages, `Program data:` entries, return data
+    /// and the invocations it made. Invocations left open by truncated logs have a null
+    /// `success`
+    pub fn serialize_parsed_logs(log_messages: &[String]) -> Value {
+        ProgramInvocation::parse(log_messages)
+            .iter()
+            .map(ProgramInvocation::to_json)
+            .collect()
+    }
+}
+
+/// A program invocation parsed from the runtime's program logs
+#[derive(Default)]
+struct ProgramInvocation<'a> {
+    program_id: &'a str,
+    depth: Option<u64>,
+    success: Option<bool>,
+    error: Option<&'a str>,
+    compute_units_consumed: Option<u64>,
+    compute_units_limit: Option<u64>,
+    logs: Vec<&'a str>,
+    data: Vec<&'a str>,
+    return_data: Option<&'a str>,
+    invocations: Vec<ProgramInvocation<'a>>,
+}
+
+impl<'a> ProgramInvocation<'a> {
+    /// Parse the `Program <id> invoke [n]`, `consumed X of Y compute units` and
+    /// `success` or `failed` events of program logs into the top-level invocations
+    fn parse(log_messages: &'a [String]) -> Vec<Self> {
+        let mut invocations = Vec::new();
+        let mut stack: Vec<Self> = Vec::new();
+
+        for log in log_messages {
+            if let Some(message) = log.strip_prefix("Program log: ") {
+                if let Some(invocation) = stack.last_mut() {
+                    invocation.logs.push(message);
+                }
+                continue;
+            }
+            if let Some(data) = log.strip_prefix("Program data: ") {
+                if let Some(invocation) = stack.last_mut() {
+                    invocation.data.extend(data.split_whitespace());
+                }
+                continue;
+            }
+            if let Some(return_data) = log.strip_prefix("Program return: ") {
+                if let (Some(invocation), Some((_, data))) =
+                    (stack.last_mut(), return_data.split_once(' '))
+                {
+                    invocation.return_data = Some(data);
+                }
+                continue;
+            }
+
+            let Some((program_id, event)) = log
+                .strip_prefix("Program ")
+                .and_then(|rest| rest.split_once(' '))
+            else {
+                // Other runtime messages, such as "Log truncated", go to the current invocation
+                if let Some(invocation) = stack.last_mut() {
+                    invocation.logs.push(log);
+                }
+                continue;
+            };
+            let is_current = stack
+                .last()
+                .is_some_and(|invocation| invocation.program_id == program_id);
+
+            if let Some(depth) = event
+                .strip_prefix("invoke [")
+                .and_then(|depth| depth.strip_suffix(']'))
+            {
+                stack.push(Self {
+                    program_id,
+                    depth: depth.parse().ok(),
+                    ..Self::default()
+                });
+            } else if let Some(units) = event
+                .strip_prefix("consumed ")
+                .and_then(|units| units.strip_suffix(" compute units"))
+            {
+                if let (Some(invocation), Some((consumed, limit))) = (
+                    stack.last_mut().filter(|_| is_current),
+                    units.split_once(" of "),
+                ) {
+                    invocation.compute_units_consumed = consumed.parse().ok();
+                    invocation.compute_units_limit = limit.parse().ok();
+                }
+            } else if is_current && (event == "success" || event.starts_with("failed")) {
+                if let Some(mut invocation) = stack.pop() {
+                    invocation.success = Some(event == "success");
+                    invocation.error = event.strip_prefix("failed: ");
+                    Self::close(&mut stack, &mut invocations, invocation);
+                }
+            }
+        }
+
+        while let Some(invocation) = stack.pop() {
+            Self::close(&mut stack, &mut invocations, invocation);
+        }
+        invocations
+    }
+
+    /// Add a finished invocation to its caller, or to the top-level invocations
+    fn close(stack: &mut [Self], invocations: &mut Vec<Self>, invocation: Self) {
+        match stack.last_mut() {
+            Some(caller) => caller.invocations.push(invocation),
+            None => invocations.push(invocation),
+        }
+    }
+
+    /// Collect this invocation and the ones it made, in the order they were invoked
+    fn flatten<'b>(&'b self, invocations: &mut Vec<&'b Self>) {
+        invocations.push(self);
+        for invocation in &self.invocations {
+            invocation.flatten(invocations);
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "programId": self.program_id,
+            "depth": self.depth,
+            "success": self.success,
+            "error": self.error,
+            "computeUnitsConsumed": self.compute_units_consumed,
+            "computeUnitsLimit": self.compute_units_limit,
+            "logs": self.logs,
+            "data": self.data,
+            "returnData": self.return_data,
+            "invocations": self
+                .invocations
+                .iter()
+                .map(Self::to_json)
+                .collect::<Vec<_>>(),
+        })
+    }
 }
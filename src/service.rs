@@ -0,0 +1,147 @@
+use {
+    crate::{connection::RequestHandler, stats::PluginStats},
+    chrono::{SecondsFormat, Utc},
+    log::debug,
+    serde_json::{json, Value},
+    std::{
+        process,
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+const PING_RESPONSE_TYPE: &str = "io.nats.micro.v1.ping_response";
+const INFO_RESPONSE_TYPE: &str = "io.nats.micro.v1.info_response";
+const STATS_RESPONSE_TYPE: &str = "io.nats.micro.v1.stats_response";
+
+/// Answers the standard NATS Services `$SRV` discovery requests for the plugin
+pub struct NatsService {
+    name: String,
+    id: String,
+    description: String,
+    subjects: Vec<String>,
+    started: String,
+    stats: Arc<PluginStats>,
+}
+
+impl NatsService {
+    /// Create a service reporting the given published subjects and counters
+    pub fn new(
+        name: &str,
+        description: &str,
+        subjects: Vec<String>,
+        stats: Arc<PluginStats>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            id: Self::generate_id(),
+            description: description.to_string(),
+            subjects,
+            started: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            stats,
+        }
+    }
+
+    /// The unique id of this service instance
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Generate an instance id unique enough to tell plugin instances apart
+    fn generate_id() -> String {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+        format!("{nanos:x}{:x}", process::id()).to_uppercase()
+    }
+
+    fn ping_response(&self) -> Value {
+        json!({
+            "type": PING_RESPONSE_TYPE,
+            "name": self.name,
+            "id": self.id,
+            "version": env!("CARGO_PKG_VERSION"),
+            "metadata": {},
+        })
+    }
+
+    /// The single endpoint the plugin reports, covering every subject it publishes to
+    fn endpoint(&self) -> Value {
+        json!({
+            "name": "publish",
+            "subject": self.subjects.join(","),
+            "metadata": {},
+        })
+    }
+
+    fn info_response(&self) -> Value {
+        json!({
+            "type": INFO_RESPONSE_TYPE,
+            "name": self.name,
+            "id": self.id,
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": self.description,
+            "metadata": {
+                "subjects": self.subjects.join(","),
+            },
+            "endpoints": [self.endpoint()],
+        })
+    }
+
+    fn stats_response(&self) -> Value {
+        // Service timings are reported in nanoseconds
+        let latency = self.stats.publish_latency();
+        let mut endpoint = self.endpoint();
+        endpoint["num_requests"] = self.stats.messages_published().into();
+        endpoint["num_errors"] = self.stats.publish_errors().into();
+        endpoint["processing_time"] = latency.total_micros().saturating_mul(1_000).into();
+        endpoint["average_processing_time"] = latency.mean_micros().saturating_mul(1_000).into();
+        endpoint["last_error"] = self.stats.last_error().unwrap_or_default().into();
+        endpoint["data"] = self.stats.to_json();
+
+        json!({
+            "type": STATS_RESPONSE_TYPE,
+            "name": self.name,
+            "id": self.id,
+            "version": env!("CARGO_PKG_VERSION"),
+            "metadata": {},
+            "started": self.started,
+            "endpoints": [endpoint],
+        })
+    }
+}
+
+impl RequestHandler for NatsService {
+    fn subjects(&self) -> Vec<String> {
+        ["PING", "INFO", "STATS"]
+            .iter()
+            .flat_map(|verb| {
+                [
+                    format!("$SRV.{verb}"),
+                    format!("$SRV.{verb}.{}", self.name),
+                    format!("$SRV.{verb}.{}.{}", self.name, self.id),
+                ]
+            })
+            .collect()
+    }
+
+    fn handle_request(
+        &self,
+        subject: &str,
+        _headers: &[(String, String)],
+        _payload: &[u8],
+    ) -> Option<Vec<u8>> {
+        debug!("Service request on {subject}");
+
+        let verb = subject.strip_prefix("$SRV.")?.split('.').next()?;
+        let response = match verb {
+            "PING" => self.ping_response(),
+            "INFO" => self.info_response(),
+            "STATS" => self.stats_response(),
+            _ => return None,
+        };
+
+        serde_json::to_vec(&response).ok()
+    }
+}
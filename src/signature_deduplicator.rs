@@ -0,0 +1,67 @@
+use {
+    solana_sdk::signature::Signature,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::Mutex,
+    },
+};
+
+#[derive(Default)]
+struct RecentTransactions {
+    /// Last use of every remembered transaction
+    last_used: HashMap<(Signature, u64), u64>,
+    /// Transactions in order of use; entries older than their `last_used` are stale
+    order: VecDeque<((Signature, u64), u64)>,
+    uses: u64,
+}
+
+/// Remembers the most recently published `(signature, slot)` pairs to skip the duplicates
+/// validator replay and fork processing notify again
+pub struct SignatureDeduplicator {
+    recent: Mutex<RecentTransactions>,
+    max_signatures: usize,
+}
+
+impl SignatureDeduplicator {
+    /// Create a deduplicator remembering at most `max_signatures` transactions
+    pub fn new(max_signatures: usize) -> Self {
+        Self {
+            recent: Mutex::new(RecentTransactions::default()),
+            max_signatures: max_signatures.max(1),
+        }
+    }
+
+    /// Record a transaction about to be published, returning whether it already was. The
+    /// same signature in another slot is not a duplicate, as forks publish it again
+    pub fn is_duplicate(&self, signature: &Signature, slot: u64) -> bool {
+        let mut recent = self.recent.lock().unwrap();
+        let recent = &mut *recent;
+        recent.uses += 1;
+        let key = (*signature, slot);
+        let duplicate = recent.last_used.insert(key, recent.uses).is_some();
+        recent.order.push_back((key, recent.uses));
+
+        while recent.last_used.len() > self.max_signatures {
+            let Some((evicted, used)) = recent.order.pop_front() else {
+                break;
+            };
+            if recent.last_used.get(&evicted) == Some(&used) {
+                recent.last_used.remove(&evicted);
+            }
+        }
+        // Duplicates leave stale entries behind; drop them before they outgrow the cache
+        if recent.order.len() > self.max_signatures * 2 {
+            let last_used = &recent.last_used;
+            recent
+                .order
+                .retain(|(key, used)| last_used.get(key) == Some(used));
+        }
+
+        duplicate
+    }
+
+    /// Number of transactions remembered
+    pub fn tracked_signatures(&self) -> usize {
+        self.recent.lock().unwrap().last_used.len()
+    }
+}
@@ -0,0 +1,95 @@
+use {
+    crate::{config::SlotCheckpointConfig, connection::ConnectionError, stats::PluginStats},
+    async_nats::{
+        jetstream::{self, kv},
+        Client,
+    },
+    log::{info, warn},
+    solana_metrics::datapoint_info,
+    std::time::{Duration, Instant},
+};
+
+/// Keeps the highest published slot in a NATS KV entry, so consumers and the plugin
+/// itself can tell which slots a restart may have skipped
+pub struct SlotCheckpoint {
+    store: kv::Store,
+    key: String,
+    interval: Duration,
+    written_slot: u64,
+    written_at: Instant,
+}
+
+impl SlotCheckpoint {
+    /// Open the checkpoint's bucket, creating it when missing, and report the slot it
+    /// held before this session
+    pub async fn open(
+        client: &Client,
+        config: &SlotCheckpointConfig,
+        key: String,
+        timeout: Duration,
+    ) -> Result<Self, ConnectionError> {
+        let mut jetstream = jetstream::new(client.clone());
+        jetstream.set_timeout(timeout);
+
+        let store = match jetstream.get_key_value(config.bucket.as_str()).await {
+            Ok(store) => store,
+            Err(_) => jetstream
+                .create_key_value(kv::Config {
+                    bucket: config.bucket.clone(),
+                    description: "Last slot published by Solana Geyser NATS plugins".to_string(),
+                    history: 1,
+                    ..kv::Config::default()
+                })
+                .await
+                .map_err(|e| ConnectionError::SendFailed {
+                    msg: format!("Failed to create KV bucket {}: {e}", config.bucket),
+                })?,
+        };
+
+        let previous_slot = store
+            .get(key.as_str())
+            .await
+            .map_err(|e| ConnectionError::SendFailed {
+                msg: format!("Failed to read slot checkpoint {key}: {e}"),
+            })?
+            .and_then(|value| std::str::from_utf8(&value).ok()?.parse::<u64>().ok());
+        if let Some(previous_slot) = previous_slot {
+            info!("Slot checkpoint {key} holds slot {previous_slot}");
+            datapoint_info!(
+                "geyser_nats_slot_checkpoint",
+                ("event", "opened", String),
+                ("previous_slot", previous_slot as i64, i64),
+            );
+        }
+
+        Ok(Self {
+            store,
+            key,
+            interval: Duration::from_millis(config.interval_ms),
+            written_slot: 0,
+            written_at: Instant::now(),
+        })
+    }
+
+    /// Write the highest published slot once the interval elapsed since the last write
+    pub async fn update(&mut self, stats: &PluginStats) {
+        if self.written_at.elapsed() >= self.interval {
+            self.write(stats).await;
+        }
+    }
+
+    /// Write the highest published slot if it moved past the checkpoint; a failed write
+    /// is logged and retried on the next update
+    pub async fn write(&mut self, stats: &PluginStats) {
+        self.written_at = Instant::now();
+        let slot = stats.last_published_slot();
+        if slot <= self.written_slot {
+            return;
+        }
+
+        match self.store.put(&self.key, slot.to_string().into()).await {
+            Ok(_) => self.written_slot = slot,
+            Err(e) => warn!("Failed to checkpoint slot {slot} to {}: {e}", self.key),
+        }
+    }
+}
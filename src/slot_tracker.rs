@@ -0,0 +1,147 @@
+use {
+    log::{debug, warn},
+    std::{
+        collections::{BTreeMap, HashSet},
+        sync::Mutex,
+    },
+};
+
+/// Why a slot's published transactions are no longer valid
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SlotDropReason {
+    /// The validator marked the slot dead
+    Dead,
+    /// A competing fork was rooted
+    Abandoned,
+}
+
+impl SlotDropReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dead => "dead",
+            Self::Abandoned => "abandoned",
+        }
+    }
+}
+
+/// An unrooted slot that will never be finalized
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DroppedSlot {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub reason: SlotDropReason,
+    /// The rooted slot that made the slot unreachable, for abandoned slots
+    pub rooted_slot: Option<u64>,
+    /// Signatures of the transactions published for the slot
+    pub signatures: Vec<String>,
+}
+
+#[derive(Default)]
+struct TrackedSlot {
+    parent: Option<u64>,
+    signatures: Vec<String>,
+}
+
+/// Tracks unrooted slots and the transactions published for them to detect dropped forks
+pub struct SlotTracker {
+    slots: Mutex<BTreeMap<u64, TrackedSlot>>,
+    max_tracked_slots: usize,
+}
+
+impl SlotTracker {
+    /// Create a slot tracker remembering at most `max_tracked_slots` unrooted slots
+    pub fn new(max_tracked_slots: usize) -> Self {
+        Self {
+            slots: Mutex::new(BTreeMap::new()),
+            max_tracked_slots: max_tracked_slots.max(1),
+        }
+    }
+
+    /// Record the parent of a slot
+    pub fn record_parent(&self, slot: u64, parent: u64) {
+        let mut slots = self.slots.lock().unwrap();
+        slots.entry(slot).or_default().parent = Some(parent);
+        Self::evict(&mut slots, self.max_tracked_slots);
+    }
+
+    /// Record a transaction published for a slot
+    pub fn record_transaction(&self, slot: u64, signature: String) {
+        let mut slots = self.slots.lock().unwrap();
+        slots.entry(slot).or_default().signatures.push(signature);
+        Self::evict(&mut slots, self.max_tracked_slots);
+    }
+
+    /// Forget a dead slot, returning what was published for it
+    pub fn slot_dead(&self, slot: u64) -> Option<DroppedSlot> {
+        let tracked = self.slots.lock().unwrap().remove(&slot)?;
+        Some(DroppedSlot {
+            slot,
+            parent: tracked.parent,
+            reason: SlotDropReason::Dead,
+            rooted_slot: None,
+            signatures: tracked.signatures,
+        })
+    }
+
+    /// Root a slot, returning the tracked slots left on competing forks
+    ///
+    /// A slot below the root is abandoned once the root's ancestry is known past it
+    /// without including it. Slots whose fate cannot be determined yet stay tracked.
+    pub fn slot_rooted(&self, rooted_slot: u64) -> Vec<DroppedSlot> {
+        let mut slots = self.slots.lock().unwrap();
+
+        // Walk the known ancestry of the rooted slot
+        let mut ancestors = HashSet::from([rooted_slot]);
+        let mut lowest_known = rooted_slot;
+        let mut current = rooted_slot;
+        while let Some(parent) = slots.get(&current).and_then(|tracked| tracked.parent) {
+            ancestors.insert(parent);
+            lowest_known = parent;
+            current = parent;
+        }
+
+        let candidates: Vec<u64> = slots.range(..=rooted_slot).map(|(slot, _)| *slot).collect();
+        let mut dropped = Vec::new();
+        for slot in candidates {
+            if ancestors.contains(&slot) {
+                // Rooted slots can no longer be dropped
+                slots.remove(&slot);
+            } else if slot > lowest_known {
+                if let Some(tracked) = slots.remove(&slot) {
+                    dropped.push(DroppedSlot {
+                        slot,
+                        parent: tracked.parent,
+                        reason: SlotDropReason::Abandoned,
+                        rooted_slot: Some(rooted_slot),
+                        signatures: tracked.signatures,
+                    });
+                }
+            }
+        }
+
+        debug!(
+            "Rooted slot {rooted_slot}: {} slots dropped, {} still tracked",
+            dropped.len(),
+            slots.len()
+        );
+        dropped
+    }
+
+    /// Number of slots currently tracked
+    pub fn tracked_slots(&self) -> usize {
+        self.slots.lock().unwrap().len()
+    }
+
+    fn evict(slots: &mut BTreeMap<u64, TrackedSlot>, max_tracked_slots: usize) {
+        while slots.len() > max_tracked_slots {
+            if let Some((evicted_slot, tracked)) = slots.pop_first() {
+                if !tracked.signatures.is_empty() {
+                    warn!(
+                        "No longer tracking slot {evicted_slot} with {} published transactions: fork status unknown",
+                        tracked.signatures.len()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,693 @@
+use {
+    log::{error, info},
+    serde_json::{json, Value},
+    solana_metrics::datapoint_info,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Number of power-of-two latency buckets, covering 1µs up to ~35 minutes
+const LATENCY_BUCKETS: usize = 32;
+
+/// Histogram of latencies with power-of-two microsecond buckets
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS],
+    count: u64,
+    total_micros: u64,
+    max_micros: u64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+        self.buckets[bucket.min(LATENCY_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.total_micros = self.total_micros.saturating_add(micros);
+        self.max_micros = self.max_micros.max(micros);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn max_micros(&self) -> u64 {
+        self.max_micros
+    }
+
+    /// Sum of every recorded latency in microseconds
+    pub fn total_micros(&self) -> u64 {
+        self.total_micros
+    }
+
+    /// Mean of the recorded latencies in microseconds
+    pub fn mean_micros(&self) -> u64 {
+        self.total_micros
+            .checked_div(self.count)
+            .unwrap_or_default()
+    }
+
+    /// Upper bound in microseconds of the bucket containing the given percentile (0-100)
+    pub fn percentile_micros(&self, percentile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let target = ((percentile / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                let upper_bound = if bucket == 0 { 0 } else { (1u64 << bucket) - 1 };
+                return upper_bound.min(self.max_micros);
+            }
+        }
+
+        self.max_micros
+    }
+
+    /// The p50, p90 and p99 percentiles and maximum, in microseconds
+    pub fn to_json(&self) -> Value {
+        json!({
+            "p50": self.percentile_micros(50.0),
+            "p90": self.percentile_micros(90.0),
+            "p99": self.percentile_micros(99.0),
+            "max": self.max_micros(),
+        })
+    }
+}
+
+/// Publishing statistics for a single subject
+#[derive(Debug, Clone, Default)]
+pub struct SubjectStats {
+    pub messages: u64,
+    pub bytes: u64,
+    pub latency: LatencyHistogram,
+    pub message_rate: f64,
+    pub byte_rate: f64,
+    /// Highest slot of the messages published to the subject
+    pub last_slot: Option<u64>,
+    last_report_messages: u64,
+    last_report_bytes: u64,
+}
+
+impl SubjectStats {
+    /// Update the per-second rates from the counters accumulated since the last report
+    fn update_rates(&mut self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.message_rate = (self.messages - self.last_report_messages) as f64 / secs;
+            self.byte_rate = (self.bytes - self.last_report_bytes) as f64 / secs;
+        }
+        self.last_report_messages = self.messages;
+        self.last_report_bytes = self.bytes;
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "messages": self.messages,
+            "bytes": self.bytes,
+            "messageRate": self.message_rate,
+            "byteRate": self.byte_rate,
+            "lastSlot": self.last_slot,
+            "latencyMicros": self.latency.to_json(),
+        })
+    }
+}
+
+/// How many transactions a filter rule or route let through and filtered out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterStats {
+    pub matched: u64,
+    pub filtered: u64,
+}
+
+impl FilterStats {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "matched": self.matched,
+            "filtered": self.filtered,
+        })
+    }
+}
+
+/// Counters describing the plugin's publishing activity
+#[derive(Debug, Default)]
+pub struct PluginStats {
+    transactions_selected: AtomicU64,
+    transactions_filtered: AtomicU64,
+    transactions_shed: AtomicU64,
+    transactions_deduplicated: AtomicU64,
+    transactions_rate_limited: AtomicU64,
+    messages_queued: AtomicU64,
+    messages_dropped: AtomicU64,
+    messages_oversized: AtomicU64,
+    messages_offloaded: AtomicU64,
+    messages_throttled: AtomicU64,
+    messages_published: AtomicU64,
+    publish_latency: Mutex<LatencyHistogram>,
+    messages_acked: AtomicU64,
+    consumer_acks: AtomicU64,
+    consumer_ack_timeouts: AtomicU64,
+    consumer_ack_latency: Mutex<LatencyHistogram>,
+    bytes_published: AtomicU64,
+    publish_errors: AtomicU64,
+    connection_failures: AtomicU64,
+    reconnects: AtomicU64,
+    server_errors: AtomicU64,
+    worker_restarts: AtomicU64,
+    queued_bytes: AtomicU64,
+    above_high_watermark: AtomicBool,
+    degraded: AtomicBool,
+    connected: AtomicBool,
+    last_published_slot: AtomicU64,
+    last_error: Mutex<Option<String>>,
+    subjects: Mutex<HashMap<String, SubjectStats>>,
+    /// Prefixes (with their trailing dot) whose subjects share one stats entry, and its name
+    subject_groups: Vec<(String, String)>,
+    filter_rules: Mutex<BTreeMap<String, FilterStats>>,
+    routes: Mutex<BTreeMap<String, FilterStats>>,
+}
+
+impl PluginStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count every subject under `prefix` as the single subject `<prefix>.*`, for fan-out
+    /// subjects too numerous to track one by one
+    pub fn with_subject_group(mut self, prefix: &str) -> Self {
+        self.subject_groups
+            .push((format!("{prefix}."), format!("{prefix}.*")));
+        self
+    }
+
+    /// The subject a message is counted under
+    fn stats_subject<'a>(&'a self, subject: &'a str) -> &'a str {
+        self.subject_groups
+            .iter()
+            .find(|(prefix, _)| subject.starts_with(prefix.as_str()))
+            .map_or(subject, |(_, group)| group.as_str())
+    }
+
+    pub fn record_transaction_selected(&self) {
+        self.transactions_selected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_transaction_filtered(&self) {
+        self.transactions_filtered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_transaction_shed(&self) {
+        self.transactions_shed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_transaction_deduplicated(&self) {
+        self.transactions_deduplicated
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_transaction_rate_limited(&self) {
+        self.transactions_rate_limited
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a transaction a rule of the top-level filter, such as `addresses` or
+    /// `programs`, was evaluated for
+    pub fn record_filter_rule(&self, rule: &str, matched: bool) {
+        Self::record_filter_result(&self.filter_rules, rule, matched);
+    }
+
+    /// Count a transaction a routing rule was evaluated for
+    pub fn record_route(&self, route: &str, matched: bool) {
+        Self::record_filter_result(&self.routes, route, matched);
+    }
+
+    fn record_filter_result(
+        results: &Mutex<BTreeMap<String, FilterStats>>,
+        name: &str,
+        matched: bool,
+    ) {
+        let mut results = results.lock().unwrap();
+        // Look up before inserting, so counting an existing rule does not allocate
+        let filter_stats = match results.get_mut(name) {
+            Some(filter_stats) => filter_stats,
+            None => results.entry(name.to_string()).or_default(),
+        };
+        if matched {
+            filter_stats.matched += 1;
+        } else {
+            filter_stats.filtered += 1;
+        }
+    }
+
+    pub fn record_message_queued(&self) {
+        self.messages_queued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_message_dropped(&self) {
+        self.messages_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_message_oversized(&self) {
+        self.messages_oversized.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_message_offloaded(&self) {
+        self.messages_offloaded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_message_throttled(&self) {
+        self.messages_throttled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a message flushed to the server along with its end-to-end latency
+    pub fn record_message_published(&self, subject: &str, bytes: usize, latency: Duration) {
+        self.messages_published.fetch_add(1, Ordering::Relaxed);
+        self.bytes_published
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.publish_latency.lock().unwrap().record(latency);
+
+        let subject = self.stats_subject(subject);
+        let mut subjects = self.subjects.lock().unwrap();
+        if !subjects.contains_key(subject) {
+            subjects.insert(subject.to_string(), SubjectStats::default());
+        }
+        if let Some(subject_stats) = subjects.get_mut(subject) {
+            subject_stats.messages += 1;
+            subject_stats.bytes += bytes as u64;
+            subject_stats.latency.record(latency);
+        }
+    }
+
+    pub fn record_message_acked(&self) {
+        self.messages_acked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a message its consumer acknowledged, along with how long that took
+    pub fn record_consumer_ack(&self, latency: Duration) {
+        self.consumer_acks.fetch_add(1, Ordering::Relaxed);
+        self.consumer_ack_latency.lock().unwrap().record(latency);
+    }
+
+    pub fn record_consumer_ack_timeout(&self) {
+        self.consumer_ack_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_publish_error(&self) {
+        self.publish_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_connection_failure(&self) {
+        self.connection_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_server_error(&self) {
+        self.server_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_worker_restart(&self) {
+        self.worker_restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Add a queued message to the queued bytes gauge, returning the new total
+    pub fn add_queued_bytes(&self, bytes: u64) -> u64 {
+        self.queued_bytes.fetch_add(bytes, Ordering::Relaxed) + bytes
+    }
+
+    /// Remove a message leaving the queue from the queued bytes gauge, returning the new total
+    pub fn remove_queued_bytes(&self, bytes: u64) -> u64 {
+        let previous = self
+            .queued_bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+                Some(queued.saturating_sub(bytes))
+            })
+            .unwrap_or_default();
+        previous.saturating_sub(bytes)
+    }
+
+    /// Set whether the queue is above its high watermark, returning the previous state
+    pub fn set_above_high_watermark(&self, above: bool) -> bool {
+        self.above_high_watermark.swap(above, Ordering::Relaxed)
+    }
+
+    pub fn set_degraded(&self, degraded: bool) {
+        self.degraded.store(degraded, Ordering::Relaxed);
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    /// Record that a message for `slot` was flushed to the server, keeping the highest slot seen
+    pub fn record_slot_published(&self, subject: &str, slot: u64) {
+        self.last_published_slot.fetch_max(slot, Ordering::Relaxed);
+
+        let subject = self.stats_subject(subject);
+        let mut subjects = self.subjects.lock().unwrap();
+        if let Some(subject_stats) = subjects.get_mut(subject) {
+            subject_stats.last_slot = subject_stats.last_slot.max(Some(slot));
+        } else {
+            subjects.insert(
+                subject.to_string(),
+                SubjectStats {
+                    last_slot: Some(slot),
+                    ..SubjectStats::default()
+                },
+            );
+        }
+    }
+
+    pub fn record_error(&self, error: String) {
+        *self.last_error.lock().unwrap() = Some(error);
+    }
+
+    pub fn transactions_selected(&self) -> u64 {
+        self.transactions_selected.load(Ordering::Relaxed)
+    }
+
+    pub fn transactions_filtered(&self) -> u64 {
+        self.transactions_filtered.load(Ordering::Relaxed)
+    }
+
+    pub fn transactions_shed(&self) -> u64 {
+        self.transactions_shed.load(Ordering::Relaxed)
+    }
+
+    pub fn transactions_deduplicated(&self) -> u64 {
+        self.transactions_deduplicated.load(Ordering::Relaxed)
+    }
+
+    pub fn transactions_rate_limited(&self) -> u64 {
+        self.transactions_rate_limited.load(Ordering::Relaxed)
+    }
+
+    pub fn messages_queued(&self) -> u64 {
+        self.messages_queued.load(Ordering::Relaxed)
+    }
+
+    pub fn messages_dropped(&self) -> u64 {
+        self.messages_dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn messages_oversized(&self) -> u64 {
+        self.messages_oversized.load(Ordering::Relaxed)
+    }
+
+    pub fn messages_offloaded(&self) -> u64 {
+        self.messages_offloaded.load(Ordering::Relaxed)
+    }
+
+    pub fn messages_throttled(&self) -> u64 {
+        self.messages_throttled.load(Ordering::Relaxed)
+    }
+
+    pub fn messages_published(&self) -> u64 {
+        self.messages_published.load(Ordering::Relaxed)
+    }
+
+    /// End-to-end latency of every published message, across all subjects
+    pub fn publish_latency(&self) -> LatencyHistogram {
+        self.publish_latency.lock().unwrap().clone()
+    }
+
+    pub fn messages_acked(&self) -> u64 {
+        self.messages_acked.load(Ordering::Relaxed)
+    }
+
+    pub fn consumer_acks(&self) -> u64 {
+        self.consumer_acks.load(Ordering::Relaxed)
+    }
+
+    pub fn consumer_ack_timeouts(&self) -> u64 {
+        self.consumer_ack_timeouts.load(Ordering::Relaxed)
+    }
+
+    pub fn consumer_ack_latency(&self) -> LatencyHistogram {
+        self.consumer_ack_latency.lock().unwrap().clone()
+    }
+
+    pub fn bytes_published(&self) -> u64 {
+        self.bytes_published.load(Ordering::Relaxed)
+    }
+
+    pub fn publish_errors(&self) -> u64 {
+        self.publish_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn connection_failures(&self) -> u64 {
+        self.connection_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    pub fn server_errors(&self) -> u64 {
+        self.server_errors.load(Ordering::Relaxed)
+    }
+
+    pub fn queued_bytes(&self) -> u64 {
+        self.queued_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn is_above_high_watermark(&self) -> bool {
+        self.above_high_watermark.load(Ordering::Relaxed)
+    }
+
+    pub fn worker_restarts(&self) -> u64 {
+        self.worker_restarts.load(Ordering::Relaxed)
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn last_published_slot(&self) -> u64 {
+        self.last_published_slot.load(Ordering::Relaxed)
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Highest published slot of every subject that carried slot data
+    pub fn last_published_slots(&self) -> BTreeMap<String, u64> {
+        self.subjects
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(subject, subject_stats)| {
+                subject_stats.last_slot.map(|slot| (subject.clone(), slot))
+            })
+            .collect()
+    }
+
+    /// Snapshot the statistics of a single subject
+    pub fn subject_stats(&self, subject: &str) -> Option<SubjectStats> {
+        self.subjects.lock().unwrap().get(subject).cloned()
+    }
+
+    /// Snapshot the counts of a rule of the top-level filter
+    pub fn filter_rule_stats(&self, rule: &str) -> Option<FilterStats> {
+        self.filter_rules.lock().unwrap().get(rule).copied()
+    }
+
+    /// Snapshot the counts of a routing rule
+    pub fn route_stats(&self, route: &str) -> Option<FilterStats> {
+        self.routes.lock().unwrap().get(route).copied()
+    }
+
+    /// Counts of every evaluated filter rule and route, as JSON objects keyed by name
+    pub fn filter_stats_json(&self) -> (Value, Value) {
+        let to_json = |results: &Mutex<BTreeMap<String, FilterStats>>| {
+            results
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(name, filter_stats)| (name.clone(), filter_stats.to_json()))
+                .collect::<serde_json::Map<_, _>>()
+        };
+        (
+            Value::Object(to_json(&self.filter_rules)),
+            Value::Object(to_json(&self.routes)),
+        )
+    }
+
+    /// Update per-subject rates and submit them as validator metrics
+    pub fn report_metrics(&self, elapsed: Duration) {
+        let consumer_acks = self.consumer_acks();
+        let consumer_ack_timeouts = self.consumer_ack_timeouts();
+        if consumer_acks > 0 || consumer_ack_timeouts > 0 {
+            let latency = self.consumer_ack_latency();
+            datapoint_info!(
+                "geyser_nats_consumer_acks",
+                ("acks", consumer_acks as i64, i64),
+                ("timeouts", consumer_ack_timeouts as i64, i64),
+                (
+                    "latency_p50_us",
+                    latency.percentile_micros(50.0) as i64,
+                    i64
+                ),
+                (
+                    "latency_p99_us",
+                    latency.percentile_micros(99.0) as i64,
+                    i64
+                ),
+                ("latency_max_us", latency.max_micros() as i64, i64),
+            );
+        }
+
+        for (kind, results) in [("filter", &self.filter_rules), ("route", &self.routes)] {
+            for (name, filter_stats) in results.lock().unwrap().iter() {
+                datapoint_info!(
+                    "geyser_nats_filter",
+                    ("kind", kind, String),
+                    ("name", name.as_str(), String),
+                    ("matched", filter_stats.matched as i64, i64),
+                    ("filtered", filter_stats.filtered as i64, i64),
+                );
+            }
+        }
+
+        let mut subjects = self.subjects.lock().unwrap();
+        for (subject, subject_stats) in subjects.iter_mut() {
+            subject_stats.update_rates(elapsed);
+
+            datapoint_info!(
+                "geyser_nats_subject",
+                ("subject", subject.as_str(), String),
+                ("messages", subject_stats.messages as i64, i64),
+                ("bytes", subject_stats.bytes as i64, i64),
+                ("message_rate", subject_stats.message_rate, f64),
+                ("byte_rate", subject_stats.byte_rate, f64),
+                (
+                    "latency_p50_us",
+                    subject_stats.latency.percentile_micros(50.0) as i64,
+                    i64
+                ),
+                (
+                    "latency_p90_us",
+                    subject_stats.latency.percentile_micros(90.0) as i64,
+                    i64
+                ),
+                (
+                    "latency_p99_us",
+                    subject_stats.latency.percentile_micros(99.0) as i64,
+                    i64
+                ),
+                (
+                    "latency_max_us",
+                    subject_stats.latency.max_micros() as i64,
+                    i64
+                ),
+            );
+        }
+    }
+
+    /// Snapshot all counters as a JSON object
+    pub fn to_json(&self) -> Value {
+        let (filters, routes) = self.filter_stats_json();
+        json!({
+            "transactionsSelected": self.transactions_selected(),
+            "transactionsFiltered": self.transactions_filtered(),
+            "transactionsShed": self.transactions_shed(),
+            "transactionsDeduplicated": self.transactions_deduplicated(),
+            "transactionsRateLimited": self.transactions_rate_limited(),
+            "messagesQueued": self.messages_queued(),
+            "messagesDropped": self.messages_dropped(),
+            "messagesOversized": self.messages_oversized(),
+            "messagesOffloaded": self.messages_offloaded(),
+            "messagesThrottled": self.messages_throttled(),
+            "messagesPublished": self.messages_published(),
+            "messagesAcked": self.messages_acked(),
+            "consumerAcks": self.consumer_acks(),
+            "consumerAckTimeouts": self.consumer_ack_timeouts(),
+            "consumerAckLatencyMicros": self.consumer_ack_latency().to_json(),
+            "bytesPublished": self.bytes_published(),
+            "publishErrors": self.publish_errors(),
+            "connectionFailures": self.connection_failures(),
+            "reconnects": self.reconnects(),
+            "serverErrors": self.server_errors(),
+            "workerRestarts": self.worker_restarts(),
+            "queuedBytes": self.queued_bytes(),
+            "aboveHighWatermark": self.is_above_high_watermark(),
+            "degraded": self.is_degraded(),
+            "connected": self.is_connected(),
+            "lastPublishedSlot": self.last_published_slot(),
+            "lastError": self.last_error(),
+            "filters": filters,
+            "routes": routes,
+            "subjects": self
+                .subjects
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(subject, subject_stats)| (subject.clone(), subject_stats.to_json()))
+                .collect::<serde_json::Map<_, _>>(),
+        })
+    }
+}
+
+/// Periodically reports plugin statistics as validator metrics
+pub struct StatsReporter {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StatsReporter {
+    /// Start reporting the given statistics every `interval`
+    pub fn start(stats: Arc<PluginStats>, interval: Duration) -> Self {
+        info!("Reporting plugin metrics every {interval:?}");
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_clone = shutdown.clone();
+        let handle = thread::spawn(move || {
+            let mut last_report = Instant::now();
+            while !shutdown_clone.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+                if last_report.elapsed() >= interval {
+                    stats.report_metrics(last_report.elapsed());
+                    last_report = Instant::now();
+                }
+            }
+        });
+
+        Self {
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the reporter thread
+    pub fn stop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if let Err(e) = handle.join() {
+                error!("Error joining stats reporter thread: {e:?}");
+            }
+        }
+    }
+}
+
+impl Drop for StatsReporter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
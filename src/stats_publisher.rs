@@ -0,0 +1,61 @@
+use {
+    crate::processor::TransactionProcessor,
+    log::{error, info, warn},
+    std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Periodically publishes the plugin statistics for monitoring through NATS itself
+pub struct StatsPublisher {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StatsPublisher {
+    /// Start publishing the statistics of the processor every `interval`
+    pub fn start(processor: Arc<TransactionProcessor>, interval: Duration) -> Self {
+        info!("Publishing plugin stats every {interval:?}");
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_clone = shutdown.clone();
+        let handle = thread::spawn(move || {
+            let mut last_publish = Instant::now();
+            while !shutdown_clone.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+                if last_publish.elapsed() >= interval {
+                    if let Err(e) = processor.publish_plugin_stats() {
+                        warn!("Failed to publish plugin stats: {e}");
+                    }
+                    last_publish = Instant::now();
+                }
+            }
+        });
+
+        Self {
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the publisher thread
+    pub fn stop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if let Err(e) = handle.join() {
+                error!("Error joining stats publisher thread: {e:?}");
+            }
+        }
+    }
+}
+
+impl Drop for StatsPublisher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
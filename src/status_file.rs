@@ -0,0 +1,104 @@
+use {
+    crate::connection::ConnectionManager,
+    chrono::{SecondsFormat, Utc},
+    log::{error, info, warn},
+    serde_json::{json, Value},
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Periodically writes the plugin status as JSON for file-based monitoring
+pub struct StatusFileWriter {
+    shutdown: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StatusFileWriter {
+    /// Start writing the status of the connection to `path` every `interval`
+    pub fn start(
+        path: PathBuf,
+        interval: Duration,
+        connection_manager: Arc<ConnectionManager>,
+    ) -> Self {
+        info!(
+            "Writing plugin status to {} every {interval:?}",
+            path.display()
+        );
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_clone = shutdown.clone();
+        let handle = thread::spawn(move || {
+            let mut last_write: Option<Instant> = None;
+            while !shutdown_clone.load(Ordering::Relaxed) {
+                if last_write.is_none_or(|last_write| last_write.elapsed() >= interval) {
+                    let status = Self::status(&connection_manager);
+                    if let Err(e) = Self::write_status(&path, &status) {
+                        warn!("Failed to write status file {}: {e}", path.display());
+                    }
+                    last_write = Some(Instant::now());
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        });
+
+        Self {
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// Snapshot the status of a connection
+    pub fn status(connection_manager: &ConnectionManager) -> Value {
+        let stats = connection_manager.stats();
+        json!({
+            "connected": stats.is_connected(),
+            "degraded": stats.is_degraded(),
+            "lastPublishedSlot": stats.last_published_slot(),
+            "queueDepth": connection_manager.queue_depth(),
+            "queuedBytes": stats.queued_bytes(),
+            "messagesQueued": stats.messages_queued(),
+            "messagesPublished": stats.messages_published(),
+            "messagesDropped": stats.messages_dropped(),
+            "transactionsShed": stats.transactions_shed(),
+            "transactionsDeduplicated": stats.transactions_deduplicated(),
+            "transactionsRateLimited": stats.transactions_rate_limited(),
+            "publishErrors": stats.publish_errors(),
+            "connectionFailures": stats.connection_failures(),
+            "lastError": stats.last_error(),
+            "updatedAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        })
+    }
+
+    /// Replace the status file atomically so readers never see a partial write
+    fn write_status(path: &Path, status: &Value) -> io::Result<()> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        fs::write(&tmp_path, serde_json::to_vec_pretty(status)?)?;
+        fs::rename(&tmp_path, path)
+    }
+
+    /// Stop the writer thread
+    pub fn stop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if let Err(e) = handle.join() {
+                error!("Error joining status file writer thread: {e:?}");
+            }
+        }
+    }
+}
+
+impl Drop for StatusFileWriter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,113 @@
+use {
+    crate::{config::BatchingConfig, connection::NatsMessage},
+    std::{collections::HashMap, sync::Mutex},
+};
+
+/// Header listing the byte offset of every transaction within a batch payload
+pub const BATCH_OFFSETS_HEADER: &str = "Solana-Batch-Offsets";
+
+/// Encoded transactions of one slot waiting to be published together
+struct Batch {
+    slot: u64,
+    payload: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+/// Packs the transaction messages of a subject and slot into batch messages, cutting the
+/// message rate wildcard subscribers have to keep up with. A batch payload is its encoded
+/// transactions back to back, with their start offsets in the `Solana-Batch-Offsets` header
+pub struct TransactionBatcher {
+    max_count: usize,
+    max_bytes: usize,
+    batches: Mutex<HashMap<String, Batch>>,
+}
+
+impl TransactionBatcher {
+    /// Create a transaction batcher, or `None` when batching is disabled
+    pub fn new(config: &BatchingConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            max_count: config.max_count.max(1),
+            max_bytes: config.max_bytes,
+            batches: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Add a transaction message to the batch of its subject, returning the batches
+    /// ready to be published. A batch is closed by a transaction of another slot, or once
+    /// it is full; a transaction larger than `max_bytes` makes up a batch of its own
+    pub fn add(&self, message: NatsMessage) -> Vec<NatsMessage> {
+        let slot = message.slot.unwrap_or_default();
+        let mut batches = self.batches.lock().unwrap();
+        let mut ready = Vec::new();
+
+        if let Some(batch) = batches.get(&message.subject) {
+            if batch.slot != slot || batch.payload.len() + message.payload.len() > self.max_bytes {
+                let batch = batches.remove(&message.subject).unwrap();
+                ready.push(Self::batch_message(message.subject.clone(), batch));
+            }
+        }
+
+        let batch = batches
+            .entry(message.subject.clone())
+            .or_insert_with(|| Batch {
+                slot,
+                payload: Vec::new(),
+                offsets: Vec::new(),
+            });
+        batch.offsets.push(batch.payload.len());
+        batch.payload.extend_from_slice(&message.payload);
+
+        if batch.offsets.len() >= self.max_count {
+            let batch = batches.remove(&message.subject).unwrap();
+            ready.push(Self::batch_message(message.subject, batch));
+        }
+        ready
+    }
+
+    /// Close the batches of `slot` and earlier slots, whose transactions are complete
+    pub fn take_through(&self, slot: u64) -> Vec<NatsMessage> {
+        let mut batches = self.batches.lock().unwrap();
+        let subjects: Vec<String> = batches
+            .iter()
+            .filter(|(_, batch)| batch.slot <= slot)
+            .map(|(subject, _)| subject.clone())
+            .collect();
+
+        subjects
+            .into_iter()
+            .filter_map(|subject| {
+                let batch = batches.remove(&subject)?;
+                Some(Self::batch_message(subject, batch))
+            })
+            .collect()
+    }
+
+    /// Close every batch, as the plugin shuts down
+    pub fn take_all(&self) -> Vec<NatsMessage> {
+        self.batches
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(subject, batch)| Self::batch_message(subject, batch))
+            .collect()
+    }
+
+    /// Number of transactions waiting in batches
+    pub fn pending(&self) -> usize {
+        self.batches
+            .lock()
+            .unwrap()
+            .values()
+            .map(|batch| batch.offsets.len())
+            .sum()
+    }
+
+    /// The message publishing a batch
+    fn batch_message(subject: String, batch: Batch) -> NatsMessage {
+        let offsets: Vec<String> = batch.offsets.iter().map(usize::to_string).collect();
+        NatsMessage::new(subject, batch.payload)
+            .with_slot(batch.slot)
+            .with_header("Solana-Slot", &batch.slot.to_string())
+            .with_header(BATCH_OFFSETS_HEADER, &offsets.join(","))
+    }
+}
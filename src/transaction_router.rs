@@ -0,0 +1,553 @@
+use {
+    crate::{
+        config::{AddressMatch, RouteConfig, TransactionFilterConfig, TransactionVersion},
+        filter_expression::FilterExpression,
+        stats::PluginStats,
+        transaction_selector::{SelectorError, TransactionSelector},
+    },
+    chrono::{DateTime, Utc},
+    log::{debug, error, info},
+    regex::RegexSet,
+    sha2::{Digest, Sha256},
+    solana_sdk::{
+        message::{v0::LoadedAddresses, SanitizedMessage},
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::SanitizedTransaction,
+    },
+    solana_transaction_status::{InnerInstructions, TransactionStatusMeta},
+    std::{collections::HashSet, sync::Arc},
+};
+
+/// The rules of a filter, shared by the top-level filter and every route
+pub struct TransactionFilter {
+    selector: Arc<TransactionSelector>,
+    address_match: AddressMatch,
+    excluded_program_ids: HashSet<Pubkey>,
+    program_ids: HashSet<Pubkey>,
+    match_inner_instructions: bool,
+    include_failed: bool,
+    only_failed: bool,
+    log_filter: Option<LogFilter>,
+    sample_rate: u64,
+    publish_window: Option<PublishWindow>,
+    message_shape: Option<MessageShapeFilter>,
+    expression: Option<FilterExpression>,
+}
+
+impl TransactionFilter {
+    pub fn new(filter: &TransactionFilterConfig) -> Result<Self, SelectorError> {
+        Ok(Self {
+            selector: Arc::new(TransactionSelector::from_filter(filter)?),
+            address_match: filter.address_match,
+            excluded_program_ids: parse_program_ids(&filter.excluded_program_ids),
+            program_ids: parse_program_ids(&filter.program_ids),
+            match_inner_instructions: filter.match_inner_instructions,
+            include_failed: filter.include_failed,
+            only_failed: filter.only_failed,
+            log_filter: LogFilter::new(filter),
+            sample_rate: filter.sample_rate,
+            publish_window: PublishWindow::new(filter),
+            message_shape: MessageShapeFilter::new(filter),
+            expression: FilterExpression::new(filter),
+        })
+    }
+
+    /// The selector of the filter's address and vote settings
+    pub fn selector(&self) -> &Arc<TransactionSelector> {
+        &self.selector
+    }
+
+    pub fn address_match(&self) -> AddressMatch {
+        self.address_match
+    }
+
+    /// Whether the filter selects a transaction of a slot, counting in `stats` how every
+    /// configured rule the transaction reaches decides
+    pub fn matches(
+        &self,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+        stats: Option<&PluginStats>,
+    ) -> bool {
+        self.matches_with_selector(
+            &self.selector,
+            is_vote,
+            transaction,
+            meta,
+            transaction.signature(),
+            slot,
+            stats,
+        )
+    }
+
+    /// Whether the filter selects a transaction of a slot, with `selector` standing in
+    /// for the filter's address and vote settings as updated at runtime, and sampling by
+    /// the `signature` the transaction was notified with
+    #[allow(clippy::too_many_arguments)]
+    pub fn matches_with_selector(
+        &self,
+        selector: &TransactionSelector,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        signature: &Signature,
+        slot: u64,
+        stats: Option<&PluginStats>,
+    ) -> bool {
+        let rule = |rule: &str, matched: bool| {
+            if let Some(stats) = stats {
+                stats.record_filter_rule(rule, matched);
+            }
+            matched
+        };
+
+        if self.invokes_excluded_program(transaction, stats) {
+            debug!("Transaction invokes an excluded program: {signature}");
+            return false;
+        }
+        if let Some(publish_window) = &self.publish_window {
+            if !rule("publish_window", publish_window.contains(slot, Utc::now())) {
+                debug!("Transaction outside the publish window: {signature}");
+                return false;
+            }
+        }
+        let account_keys = matched_account_keys(
+            transaction.message(),
+            &meta.loaded_addresses,
+            self.address_match,
+        );
+        if !rule(
+            if is_vote { "vote" } else { "addresses" },
+            selector.is_transaction_selected(is_vote, Box::new(account_keys)),
+        ) {
+            debug!("Transaction filtered out: {signature}");
+            return false;
+        }
+        if (!self.include_failed || self.only_failed)
+            && !rule(
+                "status",
+                selects_status(meta, self.include_failed, self.only_failed),
+            )
+        {
+            debug!("Transaction filtered out by status: {signature}");
+            return false;
+        }
+        if let Some(message_shape) = &self.message_shape {
+            if !rule("message_shape", message_shape.matches(transaction, meta)) {
+                debug!("Transaction version or size not selected: {signature}");
+                return false;
+            }
+        }
+        if !self.program_ids.is_empty()
+            && !rule("programs", self.invokes_selected_program(transaction, meta))
+        {
+            debug!("Transaction invokes none of the selected programs: {signature}");
+            return false;
+        }
+        if let Some(log_filter) = &self.log_filter {
+            if !rule("logs", log_filter.matches(meta)) {
+                debug!("Transaction logs match no log filter: {signature}");
+                return false;
+            }
+        }
+        if let Some(expression) = &self.expression {
+            if !rule(
+                "expression",
+                expression.matches(is_vote, transaction, meta, slot),
+            ) {
+                debug!("Transaction does not match the filter expression: {signature}");
+                return false;
+            }
+        }
+        if self.sample_rate > 1 && !rule("sample", is_sampled(signature, self.sample_rate)) {
+            debug!("Transaction sampled out: {signature}");
+            return false;
+        }
+        true
+    }
+
+    /// Whether any top-level instruction of a transaction invokes an excluded program
+    pub fn invokes_excluded_program(
+        &self,
+        transaction: &SanitizedTransaction,
+        stats: Option<&PluginStats>,
+    ) -> bool {
+        if self.excluded_program_ids.is_empty() {
+            return false;
+        }
+
+        let excluded = invokes_program(transaction, None, &self.excluded_program_ids);
+        if let Some(stats) = stats {
+            stats.record_filter_rule("excluded_programs", !excluded);
+        }
+        excluded
+    }
+
+    /// Whether a transaction invokes one of the selected programs, directly or, when
+    /// enabled, through a cross-program invocation
+    fn invokes_selected_program(
+        &self,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+    ) -> bool {
+        let inner_instructions = self
+            .match_inner_instructions
+            .then_some(meta.inner_instructions.as_deref())
+            .flatten();
+        invokes_program(transaction, inner_instructions, &self.program_ids)
+    }
+}
+
+/// A routing rule publishing the transactions its filter selects to its own subject
+pub struct TransactionRoute {
+    name: String,
+    subject: String,
+    filter: TransactionFilter,
+}
+
+impl TransactionRoute {
+    pub fn new(config: &RouteConfig) -> Result<Self, SelectorError> {
+        Ok(Self {
+            name: config.name.clone(),
+            subject: config.subject.clone(),
+            filter: TransactionFilter::new(&config.filter)?,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Whether the route's filter selects a transaction of a slot
+    pub fn matches(
+        &self,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+    ) -> bool {
+        // Routes are counted as a whole, rule counts are those of the top-level filter
+        self.filter.matches(is_vote, transaction, meta, slot, None)
+    }
+}
+
+/// Selects transactions by their log messages, such as an Anchor event or `Instruction: Swap`
+pub struct LogFilter {
+    substrings: Vec<String>,
+    patterns: RegexSet,
+}
+
+impl LogFilter {
+    /// Create a log filter, or `None` when the filter has no log substrings or patterns
+    pub fn new(filter: &TransactionFilterConfig) -> Option<Self> {
+        if filter.log_contains.is_empty() && filter.log_patterns.is_empty() {
+            return None;
+        }
+
+        // Configuration validation rejects invalid patterns before this is reached
+        let patterns = RegexSet::new(&filter.log_patterns).unwrap_or_else(|e| {
+            error!("Invalid log patterns: {e}");
+            RegexSet::empty()
+        });
+        Some(Self {
+            substrings: filter.log_contains.clone(),
+            patterns,
+        })
+    }
+
+    /// Whether a log message of the transaction contains one of the substrings or matches
+    /// one of the patterns
+    pub fn matches(&self, meta: &TransactionStatusMeta) -> bool {
+        meta.log_messages.iter().flatten().any(|log_message| {
+            self.substrings
+                .iter()
+                .any(|substring| log_message.contains(substring.as_str()))
+                || self.patterns.is_match(log_message)
+        })
+    }
+}
+
+/// Slot range and wall-clock window transactions are published in, for bounded backfills
+/// and experiments
+pub struct PublishWindow {
+    start_slot: Option<u64>,
+    end_slot: Option<u64>,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+}
+
+impl PublishWindow {
+    /// Create a publish window, or `None` when the filter sets no slot or time bounds
+    pub fn new(filter: &TransactionFilterConfig) -> Option<Self> {
+        if filter.start_slot.is_none()
+            && filter.end_slot.is_none()
+            && filter.start_time.is_none()
+            && filter.end_time.is_none()
+        {
+            return None;
+        }
+
+        Some(Self {
+            start_slot: filter.start_slot,
+            end_slot: filter.end_slot,
+            start_time: filter.start_time,
+            end_time: filter.end_time,
+        })
+    }
+
+    /// Whether a transaction of `slot` processed at `now` is published. Slot bounds are
+    /// inclusive, the end time is exclusive
+    pub fn contains(&self, slot: u64, now: DateTime<Utc>) -> bool {
+        self.start_slot.is_none_or(|start_slot| slot >= start_slot)
+            && self.end_slot.is_none_or(|end_slot| slot <= end_slot)
+            && self.start_time.is_none_or(|start_time| now >= start_time)
+            && self.end_time.is_none_or(|end_time| now < end_time)
+    }
+}
+
+/// Selects transactions by message version and by their number of signatures and account
+/// keys, for studying multi-sig or lookup-table adoption
+pub struct MessageShapeFilter {
+    version: Option<TransactionVersion>,
+    min_signatures: Option<usize>,
+    max_signatures: Option<usize>,
+    min_account_keys: Option<usize>,
+    max_account_keys: Option<usize>,
+}
+
+impl MessageShapeFilter {
+    /// Create a message shape filter, or `None` when the filter sets no version or bounds
+    pub fn new(filter: &TransactionFilterConfig) -> Option<Self> {
+        if filter.transaction_version.is_none()
+            && filter.min_signatures.is_none()
+            && filter.max_signatures.is_none()
+            && filter.min_account_keys.is_none()
+            && filter.max_account_keys.is_none()
+        {
+            return None;
+        }
+
+        Some(Self {
+            version: filter.transaction_version,
+            min_signatures: filter.min_signatures,
+            max_signatures: filter.max_signatures,
+            min_account_keys: filter.min_account_keys,
+            max_account_keys: filter.max_account_keys,
+        })
+    }
+
+    /// Whether a transaction has the selected version and its signature and account key
+    /// counts are within the inclusive bounds
+    pub fn matches(
+        &self,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+    ) -> bool {
+        let message = transaction.message();
+        let version = match message {
+            SanitizedMessage::Legacy(_) => TransactionVersion::Legacy,
+            SanitizedMessage::V0(_) => TransactionVersion::V0,
+        };
+        if self.version.is_some_and(|selected| selected != version) {
+            return false;
+        }
+
+        let signatures = transaction.signatures().len();
+        if !within(signatures, self.min_signatures, self.max_signatures) {
+            return false;
+        }
+
+        if self.min_account_keys.is_none() && self.max_account_keys.is_none() {
+            return true;
+        }
+        let account_keys =
+            matched_account_keys(message, &meta.loaded_addresses, AddressMatch::AnyMention).count();
+        within(account_keys, self.min_account_keys, self.max_account_keys)
+    }
+}
+
+fn within(count: usize, min: Option<usize>, max: Option<usize>) -> bool {
+    min.is_none_or(|min| count >= min) && max.is_none_or(|max| count <= max)
+}
+
+/// Routes every transaction to the subjects of the rules selecting it, so one plugin
+/// serves several downstream consumers with their own filters
+pub struct TransactionRouter {
+    routes: Vec<TransactionRoute>,
+}
+
+impl TransactionRouter {
+    /// Create a router, or `None` when no routes are configured and the top-level filter
+    /// applies
+    pub fn new(routes: &[RouteConfig]) -> Result<Option<Self>, SelectorError> {
+        if routes.is_empty() {
+            return Ok(None);
+        }
+
+        for route in routes {
+            info!(
+                "Routing transactions of {} to {}",
+                route.name, route.subject
+            );
+        }
+        Ok(Some(Self {
+            routes: routes
+                .iter()
+                .map(TransactionRoute::new)
+                .collect::<Result<_, _>>()?,
+        }))
+    }
+
+    pub fn routes(&self) -> &[TransactionRoute] {
+        &self.routes
+    }
+
+    /// Subjects of the routes selecting a transaction of a slot, in configuration order
+    pub fn subjects(
+        &self,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+    ) -> Vec<&str> {
+        self.select_subjects(is_vote, transaction, meta, slot, None)
+    }
+
+    /// Subjects of the routes selecting a transaction of a slot, counting in `stats`
+    /// whether each route matched
+    pub fn subjects_with_stats(
+        &self,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+        stats: &PluginStats,
+    ) -> Vec<&str> {
+        self.select_subjects(is_vote, transaction, meta, slot, Some(stats))
+    }
+
+    fn select_subjects(
+        &self,
+        is_vote: bool,
+        transaction: &SanitizedTransaction,
+        meta: &TransactionStatusMeta,
+        slot: u64,
+        stats: Option<&PluginStats>,
+    ) -> Vec<&str> {
+        let mut subjects: Vec<&str> = Vec::new();
+        for route in &self.routes {
+            let matched = route.matches(is_vote, transaction, meta, slot);
+            if let Some(stats) = stats {
+                stats.record_route(route.name(), matched);
+            }
+            // Routes sharing a subject publish the transaction once
+            if matched && !subjects.contains(&route.subject()) {
+                subjects.push(route.subject());
+            }
+        }
+        subjects
+    }
+}
+
+/// The accounts of a message that mentioned addresses are matched against, including the
+/// addresses its lookup tables resolved to
+pub(crate) fn matched_account_keys<'a>(
+    message: &'a SanitizedMessage,
+    loaded_addresses: &'a LoadedAddresses,
+    address_match: AddressMatch,
+) -> impl Iterator<Item = &'a Pubkey> {
+    // A message sanitized without its lookup tables only holds its static keys, so the
+    // addresses the meta recorded stand in for the ones it would have loaded
+    let unresolved = message.account_keys().len() == message.static_account_keys().len();
+    let (writable, readonly): (&[Pubkey], &[Pubkey]) = match address_match {
+        _ if !unresolved => (&[], &[]),
+        AddressMatch::AnyMention => (&loaded_addresses.writable, &loaded_addresses.readonly),
+        AddressMatch::SignerOnly => (&[], &[]),
+        AddressMatch::WritableOnly => (&loaded_addresses.writable, &[]),
+    };
+
+    message
+        .account_keys()
+        .iter()
+        .enumerate()
+        .filter(move |(index, _)| match address_match {
+            AddressMatch::AnyMention => true,
+            AddressMatch::SignerOnly => message.is_signer(*index),
+            AddressMatch::WritableOnly => message.is_writable(*index),
+        })
+        .map(|(_, key)| key)
+        .chain(writable)
+        .chain(readonly)
+}
+
+/// Parse program ids, skipping the invalid ones configuration validation rejects
+pub(crate) fn parse_program_ids(program_ids: &[String]) -> HashSet<Pubkey> {
+    program_ids
+        .iter()
+        .filter_map(|program_id| program_id.parse().ok())
+        .collect()
+}
+
+/// Whether a transaction has an instruction invoking one of `program_ids`, or, when its
+/// inner instructions are given, a cross-program invocation of one
+pub(crate) fn invokes_program(
+    transaction: &SanitizedTransaction,
+    inner_instructions: Option<&[InnerInstructions]>,
+    program_ids: &HashSet<Pubkey>,
+) -> bool {
+    if program_ids.is_empty() {
+        return false;
+    }
+
+    let message = transaction.message();
+    if message
+        .program_instructions_iter()
+        .any(|(program_id, _)| program_ids.contains(program_id))
+    {
+        return true;
+    }
+
+    // Inner instructions index the full account keys, including loaded addresses
+    let account_keys = message.account_keys();
+    inner_instructions
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .filter_map(|ix| account_keys.get(usize::from(ix.instruction.program_id_index)))
+        .any(|program_id| program_ids.contains(program_id))
+}
+
+/// Whether a transaction's outcome is selected: failures unless `include_failed` is off,
+/// successes unless `only_failed` is on
+pub(crate) fn selects_status(
+    meta: &TransactionStatusMeta,
+    include_failed: bool,
+    only_failed: bool,
+) -> bool {
+    if meta.status.is_err() {
+        include_failed
+    } else {
+        !only_failed
+    }
+}
+
+/// Whether a transaction falls in the sample of one in every `sample_rate` transactions.
+/// The sample depends on the signature alone, so every plugin instance and restart picks
+/// the same transactions
+pub fn is_sampled(signature: &Signature, sample_rate: u64) -> bool {
+    if sample_rate <= 1 {
+        return true;
+    }
+
+    let digest = Sha256::digest(signature.as_ref());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(prefix).is_multiple_of(sample_rate)
+}
@@ -1,10 +1,22 @@
-use {log::*, solana_sdk::pubkey::Pubkey, std::collections::HashSet};
+use {
+    crate::config::TransactionFilterConfig, log::*, solana_sdk::pubkey::Pubkey,
+    std::collections::HashSet, thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum SelectorError {
+    #[error("Invalid selector address: {msg}")]
+    InvalidAddress { msg: String },
+}
 
 #[derive(Default)]
 pub struct TransactionSelector {
     pub mentioned_addresses: HashSet<Vec<u8>>,
     pub select_all_transactions: bool,
     pub select_all_vote_transactions: bool,
+    /// When not empty, vote transactions are selected by these addresses instead of the
+    /// mentioned addresses
+    pub vote_addresses: HashSet<Vec<u8>>,
 }
 
 impl TransactionSelector {
@@ -12,38 +24,78 @@ impl TransactionSelector {
     /// To select all transactions use ["*"] or ["all"]
     /// To select all vote transactions, use ["all_votes"]
     /// To select transactions mentioning specific addresses use ["<pubkey1>", "<pubkey2>", ...]
-    pub fn new(mentioned_addresses: &[String]) -> Self {
-        info!("Creating TransactionSelector for addresses: {mentioned_addresses:?}");
+    pub fn new(mentioned_addresses: &[String]) -> Result<Self, SelectorError> {
+        // Address lists loaded from files can be too long to log in full
+        info!(
+            "Creating TransactionSelector for {} addresses",
+            mentioned_addresses.len()
+        );
+        debug!("TransactionSelector addresses: {mentioned_addresses:?}");
 
         let select_all_transactions = mentioned_addresses
             .iter()
             .any(|key| key == "*" || key == "all");
         if select_all_transactions {
-            return Self {
+            return Ok(Self {
                 mentioned_addresses: HashSet::default(),
                 select_all_transactions,
                 select_all_vote_transactions: true,
-            };
+                vote_addresses: HashSet::default(),
+            });
         }
         let select_all_vote_transactions = mentioned_addresses.iter().any(|key| key == "all_votes");
         if select_all_vote_transactions {
-            return Self {
+            return Ok(Self {
                 mentioned_addresses: HashSet::default(),
                 select_all_transactions,
                 select_all_vote_transactions: true,
-            };
+                vote_addresses: HashSet::default(),
+            });
         }
 
-        let mentioned_addresses = mentioned_addresses
-            .iter()
-            .map(|key| bs58::decode(key).into_vec().unwrap())
-            .collect();
-
-        Self {
-            mentioned_addresses,
+        Ok(Self {
+            mentioned_addresses: Self::decode_addresses(mentioned_addresses)?,
             select_all_transactions: false,
             select_all_vote_transactions: false,
+            vote_addresses: HashSet::default(),
+        })
+    }
+
+    /// Create a selector from the vote and address settings of a filter configuration.
+    /// Vote settings combine with mentioned addresses, so one filter can select the votes
+    /// of some validators along with the transactions mentioning some addresses
+    pub fn from_filter(filter_config: &TransactionFilterConfig) -> Result<Self, SelectorError> {
+        if filter_config.select_all_transactions {
+            return Self::new(&["*".to_string()]);
+        }
+
+        let selects_votes =
+            filter_config.select_vote_transactions || !filter_config.vote_addresses.is_empty();
+        if !selects_votes && filter_config.mentioned_addresses.is_empty() {
+            // Default: select all non-vote transactions
+            return Self::new(&["*".to_string()]);
+        }
+
+        let mut selector = Self::new(&filter_config.mentioned_addresses)?;
+        if filter_config.vote_addresses.is_empty() {
+            selector.select_all_vote_transactions |= filter_config.select_vote_transactions;
+        } else {
+            selector.vote_addresses = Self::decode_addresses(&filter_config.vote_addresses)?;
         }
+        Ok(selector)
+    }
+
+    fn decode_addresses(addresses: &[String]) -> Result<HashSet<Vec<u8>>, SelectorError> {
+        addresses
+            .iter()
+            .map(|key| {
+                key.parse::<Pubkey>()
+                    .map(|address| address.to_bytes().to_vec())
+                    .map_err(|e| SelectorError::InvalidAddress {
+                        msg: format!("'{key}': {e}"),
+                    })
+            })
+            .collect()
     }
 
     /// Check if a transaction is of interest.
@@ -70,21 +122,37 @@ impl TransactionSelector {
         }
 
         // Check specific addresses
-        for address in mentioned_addresses {
-            if self.mentioned_addresses.contains(address.as_ref()) {
-                debug!("Transaction selected by address match: {address}");
-                return true;
-            }
+        if let Some(address) = self.matched_addresses(is_vote, mentioned_addresses).first() {
+            debug!("Transaction selected by address match: {address}");
+            return true;
         }
 
         debug!("Transaction not selected by any rule");
         false
     }
 
+    /// The addresses of a transaction that are among the selected addresses: the vote
+    /// addresses for votes when set, the mentioned addresses otherwise
+    pub fn matched_addresses<'a>(
+        &self,
+        is_vote: bool,
+        mentioned_addresses: impl Iterator<Item = &'a Pubkey>,
+    ) -> Vec<&'a Pubkey> {
+        let selected_addresses = if is_vote && !self.vote_addresses.is_empty() {
+            &self.vote_addresses
+        } else {
+            &self.mentioned_addresses
+        };
+        mentioned_addresses
+            .filter(|address| selected_addresses.contains(address.as_ref()))
+            .collect()
+    }
+
     /// Check if any transaction is of interest at all
     pub fn is_enabled(&self) -> bool {
         self.select_all_transactions
             || self.select_all_vote_transactions
             || !self.mentioned_addresses.is_empty()
+            || !self.vote_addresses.is_empty()
     }
 }
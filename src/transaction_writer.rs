@@ -0,0 +1,431 @@
+use {
+    crate::{
+        config::PayloadFormat,
+        serializer::{SerializationError, TransactionSerializer, SCHEMA_VERSION},
+    },
+    base64::{display::Base64Display, engine::general_purpose},
+    serde::{ser::SerializeStruct, Serialize, Serializer},
+    solana_sdk::{
+        instruction::CompiledInstruction,
+        message::{v0::MessageAddressTableLookup, SanitizedMessage},
+        pubkey::Pubkey,
+        transaction::{SanitizedTransaction, TransactionError},
+        transaction_context::TransactionReturnData,
+    },
+    solana_transaction_status::{InnerInstructions, Reward, RewardType, TransactionStatusMeta},
+    std::{cell::RefCell, fmt::Display},
+};
+
+thread_local! {
+    /// Buffer payloads are written into, kept per thread so its capacity is reused
+    static PAYLOAD_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Writes a transaction in the plugin schema straight to payload bytes, without building
+/// the `serde_json::Value` tree of `TransactionSerializer`.
+///
+/// Fields are written in the byte order of their names, so the bytes equal the canonical
+/// payload of the `Value` path in every format. Options rewriting a payload after it is
+/// serialized need that tree, so the processor only writes directly when none is enabled
+pub struct TransactionWriter<'a> {
+    transaction: &'a SanitizedTransaction,
+    meta: &'a TransactionStatusMeta,
+    slot: u64,
+    index: Option<usize>,
+    is_vote: bool,
+    sequence: u64,
+    network: Option<&'a str>,
+}
+
+impl<'a> TransactionWriter<'a> {
+    pub fn new(
+        transaction: &'a SanitizedTransaction,
+        meta: &'a TransactionStatusMeta,
+        slot: u64,
+        index: Option<usize>,
+        is_vote: bool,
+    ) -> Self {
+        Self {
+            transaction,
+            meta,
+            slot,
+            index,
+            is_vote,
+            sequence: 0,
+            network: None,
+        }
+    }
+
+    /// Set the plugin-wide sequence number of the transaction
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    /// Name the network the transaction is published on
+    pub fn with_network(mut self, network: Option<&'a str>) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Write the transaction as the bytes of a payload in the given format. The raw format
+    /// has its own envelope, built by `TransactionSerializer`, so it is written as JSON
+    pub fn encode(&self, format: PayloadFormat) -> Result<Vec<u8>, SerializationError> {
+        PAYLOAD_BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            buffer.clear();
+            let result = match format {
+                PayloadFormat::Json | PayloadFormat::Raw => {
+                    serde_json::to_writer(&mut *buffer, self).map_err(|e| e.to_string())
+                }
+                PayloadFormat::Msgpack => self
+                    .serialize(&mut rmp_serde::Serializer::new(&mut *buffer).with_struct_map())
+                    .map_err(|e| e.to_string()),
+                PayloadFormat::Cbor => {
+                    ciborium::into_writer(self, &mut *buffer).map_err(|e| e.to_string())
+                }
+            };
+            result.map_err(|e| SerializationError::SerializationFailed {
+                msg: format!("Failed to write transaction payload: {e}"),
+            })?;
+            Ok(buffer.to_vec())
+        })
+    }
+}
+
+impl Serialize for TransactionWriter<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = self.transaction.message();
+        // Formats such as MessagePack write the number of fields ahead of them
+        let len = 11 + usize::from(self.network.is_some());
+        let mut state = serializer.serialize_struct("Transaction", len)?;
+        // The block time is only known once the block metadata arrives
+        state.serialize_field("blockTime", &None::<i64>)?;
+        state.serialize_field("feePayer", &Text(message.fee_payer()))?;
+        state.serialize_field("index", &self.index)?;
+        state.serialize_field("isVote", &self.is_vote)?;
+        state.serialize_field(
+            "meta",
+            &Meta {
+                transaction: self.transaction,
+                meta: self.meta,
+            },
+        )?;
+        if let Some(network) = self.network {
+            state.serialize_field("network", network)?;
+        }
+        state.serialize_field(
+            "orderingKey",
+            &TransactionSerializer::ordering_key(self.slot, self.index),
+        )?;
+        state.serialize_field("schemaVersion", &SCHEMA_VERSION)?;
+        state.serialize_field("sequence", &self.sequence)?;
+        state.serialize_field("slot", &self.slot)?;
+        state.serialize_field(
+            "transaction",
+            &Transaction {
+                transaction: self.transaction,
+            },
+        )?;
+        state.serialize_field("version", &0u8)?;
+        state.end()
+    }
+}
+
+/// A value written as its display string, such as a base58 key or signature
+struct Text<'a, T: Display>(&'a T);
+
+impl<T: Display> Serialize for Text<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self.0)
+    }
+}
+
+/// Bytes written as a base64 string
+struct Base64<'a>(&'a [u8]);
+
+impl Serialize for Base64<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Base64Display::new(self.0, &general_purpose::STANDARD))
+    }
+}
+
+/// Keys written as a list of base58 strings
+struct Keys<'a>(&'a [Pubkey]);
+
+impl Serialize for Keys<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(Text))
+    }
+}
+
+struct Transaction<'a> {
+    transaction: &'a SanitizedTransaction,
+}
+
+impl Serialize for Transaction<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Transaction", 2)?;
+        state.serialize_field(
+            "message",
+            &Message {
+                message: self.transaction.message(),
+            },
+        )?;
+        state.serialize_field(
+            "signatures",
+            &Signatures {
+                transaction: self.transaction,
+            },
+        )?;
+        state.end()
+    }
+}
+
+struct Signatures<'a> {
+    transaction: &'a SanitizedTransaction,
+}
+
+impl Serialize for Signatures<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.transaction.signatures().iter().map(Text))
+    }
+}
+
+struct Message<'a> {
+    message: &'a SanitizedMessage,
+}
+
+impl Serialize for Message<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let header = self.message.header();
+        let mut state = serializer.serialize_struct("Message", 5)?;
+        state.serialize_field(
+            "accountKeys",
+            &AccountKeys {
+                message: self.message,
+            },
+        )?;
+        // Legacy messages have no lookups, an empty list keeps the V0 format
+        state.serialize_field(
+            "addressTableLookups",
+            &AddressTableLookups(self.message.message_address_table_lookups()),
+        )?;
+        state.serialize_field(
+            "header",
+            &Header {
+                num_readonly_signed_accounts: header.num_readonly_signed_accounts,
+                num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts,
+                num_required_signatures: header.num_required_signatures,
+            },
+        )?;
+        state.serialize_field("instructions", &Instructions(self.message.instructions()))?;
+        state.serialize_field("recentBlockhash", &Text(self.message.recent_blockhash()))?;
+        state.end()
+    }
+}
+
+/// Account keys flagged like the RPC's jsonParsed encoding
+struct AccountKeys<'a> {
+    message: &'a SanitizedMessage,
+}
+
+impl Serialize for AccountKeys<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.message.static_account_keys().iter().enumerate().map(
+            |(index, pubkey)| AccountKey {
+                pubkey: Text(pubkey),
+                signer: self.message.is_signer(index),
+                writable: self.message.is_writable(index),
+            },
+        ))
+    }
+}
+
+#[derive(Serialize)]
+struct AccountKey<'a> {
+    pubkey: Text<'a, Pubkey>,
+    signer: bool,
+    writable: bool,
+}
+
+struct AddressTableLookups<'a>(&'a [MessageAddressTableLookup]);
+
+impl Serialize for AddressTableLookups<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|lookup| AddressTableLookup {
+            account_key: Text(&lookup.account_key),
+            readonly_indexes: &lookup.readonly_indexes,
+            writable_indexes: &lookup.writable_indexes,
+        }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddressTableLookup<'a> {
+    account_key: Text<'a, Pubkey>,
+    readonly_indexes: &'a [u8],
+    writable_indexes: &'a [u8],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    num_readonly_signed_accounts: u8,
+    num_readonly_unsigned_accounts: u8,
+    num_required_signatures: u8,
+}
+
+struct Instructions<'a>(&'a [CompiledInstruction]);
+
+impl Serialize for Instructions<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|instruction| Instruction {
+            accounts: &instruction.accounts,
+            data: Base64(&instruction.data),
+            program_id_index: instruction.program_id_index,
+        }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Instruction<'a> {
+    accounts: &'a [u8],
+    data: Base64<'a>,
+    program_id_index: u8,
+}
+
+/// Transaction metadata, with the compute budget the transaction requested
+struct Meta<'a> {
+    transaction: &'a SanitizedTransaction,
+    meta: &'a TransactionStatusMeta,
+}
+
+impl Serialize for Meta<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let meta = self.meta;
+        let (compute_unit_limit, compute_unit_price) =
+            TransactionSerializer::requested_compute_budget(self.transaction);
+        let mut state = serializer.serialize_struct("Meta", 12)?;
+        state.serialize_field("computeUnitLimit", &compute_unit_limit)?;
+        state.serialize_field("computeUnitPrice", &compute_unit_price)?;
+        state.serialize_field("computeUnitsConsumed", &meta.compute_units_consumed)?;
+        state.serialize_field("err", &Status(&meta.status))?;
+        state.serialize_field("fee", &meta.fee)?;
+        state.serialize_field(
+            "innerInstructions",
+            &meta.inner_instructions.as_deref().map(InnerInstructionSets),
+        )?;
+        state.serialize_field(
+            "loadedAddresses",
+            &LoadedAddresses {
+                readonly: Keys(&meta.loaded_addresses.readonly),
+                writable: Keys(&meta.loaded_addresses.writable),
+            },
+        )?;
+        state.serialize_field(
+            "logMessages",
+            meta.log_messages.as_deref().unwrap_or_default(),
+        )?;
+        state.serialize_field("postBalances", &meta.post_balances)?;
+        state.serialize_field("preBalances", &meta.pre_balances)?;
+        state.serialize_field("returnData", &meta.return_data.as_ref().map(ReturnData))?;
+        state.serialize_field("rewards", &meta.rewards.as_deref().map(Rewards))?;
+        state.end()
+    }
+}
+
+/// The error of a failed transaction in its debug form, null when it succeeded
+struct Status<'a>(&'a Result<(), TransactionError>);
+
+impl Serialize for Status<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Ok(()) => serializer.serialize_none(),
+            Err(_) => serializer.collect_str(&format_args!("{:?}", self.0)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LoadedAddresses<'a> {
+    readonly: Keys<'a>,
+    writable: Keys<'a>,
+}
+
+struct InnerInstructionSets<'a>(&'a [InnerInstructions]);
+
+impl Serialize for InnerInstructionSets<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|inner| InnerInstructionSet {
+            index: inner.index,
+            instructions: InnerInstructionList(&inner.instructions),
+        }))
+    }
+}
+
+#[derive(Serialize)]
+struct InnerInstructionSet<'a> {
+    index: u8,
+    instructions: InnerInstructionList<'a>,
+}
+
+struct InnerInstructionList<'a>(&'a [solana_transaction_status::InnerInstruction]);
+
+impl Serialize for InnerInstructionList<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|ix| InnerInstruction {
+            accounts: &ix.instruction.accounts,
+            data: Base64(&ix.instruction.data),
+            program_id_index: ix.instruction.program_id_index,
+            stack_height: ix.stack_height,
+        }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InnerInstruction<'a> {
+    accounts: &'a [u8],
+    data: Base64<'a>,
+    program_id_index: u8,
+    stack_height: Option<u32>,
+}
+
+struct ReturnData<'a>(&'a TransactionReturnData);
+
+impl Serialize for ReturnData<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ReturnData", 2)?;
+        state.serialize_field("data", &(Base64(&self.0.data), "base64"))?;
+        state.serialize_field("programId", &Text(&self.0.program_id))?;
+        state.end()
+    }
+}
+
+/// Rewards in the RPC shape, with the fields of `Reward` in name order
+struct Rewards<'a>(&'a [Reward]);
+
+impl Serialize for Rewards<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|reward| SortedReward {
+            commission: reward.commission,
+            lamports: reward.lamports,
+            post_balance: reward.post_balance,
+            pubkey: &reward.pubkey,
+            reward_type: reward.reward_type,
+        }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SortedReward<'a> {
+    commission: Option<u8>,
+    lamports: i64,
+    post_balance: u64,
+    pubkey: &'a str,
+    reward_type: Option<RewardType>,
+}
@@ -0,0 +1,78 @@
+use {
+    log::{debug, warn},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashSet},
+        sync::Mutex,
+    },
+};
+
+/// Aggregated vote activity for a single slot
+#[derive(Debug, Default, Clone)]
+pub struct VoteSummary {
+    /// Number of vote transactions
+    pub vote_count: u64,
+    /// Distinct vote authorities that signed a vote
+    pub voters: HashSet<Pubkey>,
+    /// Sum of the fees paid by the vote transactions
+    pub total_fee_lamports: u64,
+}
+
+/// Aggregates vote transactions per slot until the block metadata arrives
+pub struct VoteSummarizer {
+    pending: Mutex<BTreeMap<u64, VoteSummary>>,
+    max_buffered_slots: usize,
+}
+
+impl VoteSummarizer {
+    /// Create a new vote summarizer buffering at most `max_buffered_slots` slots
+    pub fn new(max_buffered_slots: usize) -> Self {
+        Self {
+            pending: Mutex::new(BTreeMap::new()),
+            max_buffered_slots: max_buffered_slots.max(1),
+        }
+    }
+
+    /// Count a vote transaction signed by `voter` for the given slot
+    pub fn add_vote(&self, slot: u64, voter: Pubkey, fee: u64) {
+        let mut pending = self.pending.lock().unwrap();
+
+        let summary = pending.entry(slot).or_default();
+        summary.vote_count += 1;
+        summary.voters.insert(voter);
+        summary.total_fee_lamports = summary.total_fee_lamports.saturating_add(fee);
+
+        // Slots on abandoned forks never receive block metadata, so evict the oldest ones
+        while pending.len() > self.max_buffered_slots {
+            if let Some((evicted_slot, summary)) = pending.pop_first() {
+                warn!(
+                    "Discarding vote summary of {} votes for slot {evicted_slot}: no block metadata received",
+                    summary.vote_count
+                );
+            }
+        }
+    }
+
+    /// Remove and return the vote summary for a slot
+    pub fn take_summary(&self, slot: u64) -> VoteSummary {
+        let summary = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(&slot)
+            .unwrap_or_default();
+
+        debug!(
+            "Summarized {} votes from {} voters for slot {slot}",
+            summary.vote_count,
+            summary.voters.len()
+        );
+
+        summary
+    }
+
+    /// Number of slots currently buffered
+    pub fn buffered_slots(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
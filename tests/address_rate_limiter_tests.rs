@@ -0,0 +1,66 @@
+use {
+    solana_geyser_plugin_nats::{AddressRateLimitConfig, AddressRateLimiter},
+    solana_sdk::pubkey::Pubkey,
+    std::time::{Duration, Instant},
+};
+
+fn limiter(messages_per_sec: u64) -> AddressRateLimiter {
+    AddressRateLimiter::new(&AddressRateLimitConfig { messages_per_sec }).unwrap()
+}
+
+#[test]
+fn test_unlimited_by_default() {
+    assert!(AddressRateLimiter::new(&AddressRateLimitConfig::default()).is_none());
+}
+
+#[test]
+fn test_each_address_limited_separately() {
+    let limiter = limiter(2);
+    let busy = Pubkey::new_unique();
+    let quiet = Pubkey::new_unique();
+    let now = Instant::now();
+
+    assert!(limiter.allow(&[&busy], now));
+    assert!(limiter.allow(&[&busy], now));
+    assert!(!limiter.allow(&[&busy], now));
+    // The busy address does not use up the quiet one's budget
+    assert!(limiter.allow(&[&quiet], now));
+    assert_eq!(limiter.tracked_addresses(), 2);
+}
+
+#[test]
+fn test_limit_refills_over_time() {
+    let limiter = limiter(2);
+    let address = Pubkey::new_unique();
+    let now = Instant::now();
+
+    assert!(limiter.allow(&[&address], now));
+    assert!(limiter.allow(&[&address], now));
+    assert!(!limiter.allow(&[&address], now));
+    assert!(limiter.allow(&[&address], now + Duration::from_secs(1)));
+}
+
+#[test]
+fn test_busy_address_does_not_suppress_shared_transaction() {
+    let limiter = limiter(1);
+    let busy = Pubkey::new_unique();
+    let quiet = Pubkey::new_unique();
+    let now = Instant::now();
+
+    assert!(limiter.allow(&[&busy], now));
+    assert!(!limiter.allow(&[&busy], now));
+    // Published through the quiet address, which is charged for it
+    assert!(limiter.allow(&[&busy, &quiet], now));
+    assert!(!limiter.allow(&[&busy, &quiet], now));
+}
+
+#[test]
+fn test_transaction_without_matched_addresses_allowed() {
+    let limiter = limiter(1);
+    let now = Instant::now();
+
+    for _ in 0..3 {
+        assert!(limiter.allow(&[], now));
+    }
+    assert_eq!(limiter.tracked_addresses(), 0);
+}
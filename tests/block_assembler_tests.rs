@@ -0,0 +1,74 @@
+use {
+    serde_json::json,
+    solana_geyser_plugin_nats::{block_assembler::BlockAssembler, connection::NatsMessage},
+};
+
+#[test]
+fn test_take_slot_orders_by_index() {
+    let assembler = BlockAssembler::new(4);
+
+    assembler.add_transaction(100, Some(2), json!({"id": "c"}), Vec::new());
+    assembler.add_transaction(100, Some(0), json!({"id": "a"}), Vec::new());
+    assembler.add_transaction(100, Some(1), json!({"id": "b"}), Vec::new());
+
+    let transactions = assembler.take_slot(100).transactions;
+    let ids: Vec<&str> = transactions
+        .iter()
+        .map(|tx| tx["id"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(ids, vec!["a", "b", "c"]);
+    assert_eq!(assembler.buffered_slots(), 0);
+}
+
+#[test]
+fn test_unindexed_transactions_keep_arrival_order() {
+    let assembler = BlockAssembler::new(4);
+
+    assembler.add_transaction(100, None, json!({"id": "first"}), Vec::new());
+    assembler.add_transaction(100, None, json!({"id": "second"}), Vec::new());
+
+    let transactions = assembler.take_slot(100).transactions;
+    assert_eq!(transactions[0]["id"], "first");
+    assert_eq!(transactions[1]["id"], "second");
+}
+
+#[test]
+fn test_take_slot_for_unknown_slot() {
+    let assembler = BlockAssembler::new(4);
+    assembler.add_transaction(100, Some(0), json!({}), Vec::new());
+
+    assert!(assembler.take_slot(101).transactions.is_empty());
+    assert_eq!(assembler.buffered_slots(), 1);
+}
+
+#[test]
+fn test_oldest_slots_are_evicted() {
+    let assembler = BlockAssembler::new(2);
+
+    assembler.add_transaction(100, Some(0), json!({}), Vec::new());
+    assembler.add_transaction(101, Some(0), json!({}), Vec::new());
+    assembler.add_transaction(102, Some(0), json!({}), Vec::new());
+
+    assert_eq!(assembler.buffered_slots(), 2);
+    assert!(assembler.take_slot(100).transactions.is_empty());
+    assert_eq!(assembler.take_slot(101).transactions.len(), 1);
+    assert_eq!(assembler.take_slot(102).transactions.len(), 1);
+}
+
+#[test]
+fn test_notifications_follow_transaction_order() {
+    let assembler = BlockAssembler::new(4);
+
+    let notification = |subject: &str| NatsMessage::new(subject.to_string(), Vec::new());
+    assembler.add_transaction(100, Some(1), json!({}), vec![notification("b")]);
+    assembler.add_transaction(100, Some(0), json!({}), vec![notification("a")]);
+
+    let subjects: Vec<String> = assembler
+        .take_slot(100)
+        .notifications
+        .into_iter()
+        .map(|message| message.subject)
+        .collect();
+    assert_eq!(subjects, vec!["a", "b"]);
+}
@@ -0,0 +1,60 @@
+use {
+    solana_geyser_plugin_nats::{circuit_breaker::CircuitBreaker, CircuitBreakerConfig},
+    std::{thread, time::Duration},
+};
+
+fn circuit_breaker(failure_threshold: u32, cooldown_secs: u64) -> CircuitBreaker {
+    CircuitBreaker::new(&CircuitBreakerConfig {
+        enabled: true,
+        failure_threshold,
+        cooldown_secs,
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_disabled_by_default() {
+    assert!(CircuitBreaker::new(&CircuitBreakerConfig::default()).is_none());
+}
+
+#[test]
+fn test_opens_after_consecutive_failures() {
+    let circuit_breaker = circuit_breaker(3, 60);
+
+    circuit_breaker.record_failure();
+    circuit_breaker.record_failure();
+    assert!(!circuit_breaker.is_open());
+
+    circuit_breaker.record_failure();
+    assert!(circuit_breaker.is_open());
+}
+
+#[test]
+fn test_success_resets_failures_and_closes() {
+    let circuit_breaker = circuit_breaker(2, 60);
+
+    circuit_breaker.record_failure();
+    circuit_breaker.record_success();
+    circuit_breaker.record_failure();
+    assert!(!circuit_breaker.is_open());
+
+    circuit_breaker.record_failure();
+    assert!(circuit_breaker.is_open());
+    circuit_breaker.record_success();
+    assert!(!circuit_breaker.is_open());
+}
+
+#[test]
+fn test_reopens_on_failure_after_cooldown() {
+    let circuit_breaker = circuit_breaker(1, 1);
+
+    circuit_breaker.record_failure();
+    assert!(circuit_breaker.is_open());
+
+    thread::sleep(Duration::from_millis(1100));
+    assert!(!circuit_breaker.is_open());
+
+    // Still failing once the cooldown is over
+    circuit_breaker.record_failure();
+    assert!(circuit_breaker.is_open());
+}
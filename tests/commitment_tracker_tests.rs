@@ -0,0 +1,60 @@
+use solana_geyser_plugin_nats::commitment_tracker::{Commitment, CommitmentTracker};
+
+#[test]
+fn test_confirmed_then_rooted() {
+    let tracker = CommitmentTracker::new(16);
+    tracker.record_transaction(10, "sig1".to_string());
+    tracker.record_transaction(10, "sig2".to_string());
+
+    assert_eq!(
+        tracker.upgrade(10, Commitment::Confirmed),
+        Some(vec!["sig1".to_string(), "sig2".to_string()])
+    );
+    // Each commitment level is reported once
+    assert_eq!(tracker.upgrade(10, Commitment::Confirmed), None);
+    assert_eq!(tracker.tracked_slots(), 1);
+
+    assert_eq!(
+        tracker.upgrade(10, Commitment::Rooted),
+        Some(vec!["sig1".to_string(), "sig2".to_string()])
+    );
+    assert_eq!(tracker.upgrade(10, Commitment::Rooted), None);
+    assert_eq!(tracker.tracked_slots(), 0);
+}
+
+#[test]
+fn test_untracked_slot_not_upgraded() {
+    let tracker = CommitmentTracker::new(16);
+
+    assert_eq!(tracker.upgrade(10, Commitment::Confirmed), None);
+    assert_eq!(tracker.upgrade(10, Commitment::Rooted), None);
+}
+
+#[test]
+fn test_root_forgets_older_slots() {
+    let tracker = CommitmentTracker::new(16);
+    tracker.record_transaction(8, "forked".to_string());
+    tracker.record_transaction(10, "rooted".to_string());
+    tracker.record_transaction(11, "pending".to_string());
+
+    assert_eq!(
+        tracker.upgrade(10, Commitment::Rooted),
+        Some(vec!["rooted".to_string()])
+    );
+    assert_eq!(tracker.tracked_slots(), 1);
+    assert_eq!(tracker.upgrade(8, Commitment::Confirmed), None);
+}
+
+#[test]
+fn test_forgotten_and_evicted_slots() {
+    let tracker = CommitmentTracker::new(2);
+    tracker.record_transaction(1, "a".to_string());
+    tracker.record_transaction(2, "b".to_string());
+    tracker.record_transaction(3, "c".to_string());
+    assert_eq!(tracker.tracked_slots(), 2);
+    assert_eq!(tracker.upgrade(1, Commitment::Confirmed), None);
+
+    tracker.forget(2);
+    assert_eq!(tracker.upgrade(2, Commitment::Confirmed), None);
+    assert_eq!(tracker.tracked_slots(), 1);
+}
@@ -0,0 +1,69 @@
+use {
+    flate2::read::GzDecoder,
+    solana_geyser_plugin_nats::{
+        config::{CompressionAlgorithm, CompressionConfig},
+        connection::NatsMessage,
+        PayloadCompressor,
+    },
+    std::io::Read,
+};
+
+fn create_compressor(algorithm: CompressionAlgorithm) -> PayloadCompressor {
+    PayloadCompressor::new(&CompressionConfig {
+        algorithm,
+        min_bytes: 64,
+    })
+    .expect("compression is enabled")
+}
+
+fn create_large_message() -> NatsMessage {
+    NatsMessage::new("solana.transactions".to_string(), vec![b'a'; 4096])
+}
+
+#[test]
+fn test_no_compressor_when_disabled() {
+    assert!(PayloadCompressor::new(&CompressionConfig::default()).is_none());
+}
+
+#[test]
+fn test_gzip_payload_round_trip() {
+    let message = create_compressor(CompressionAlgorithm::Gzip).compress(create_large_message());
+    let message = message.expect("Failed to compress");
+
+    assert_eq!(
+        message.headers,
+        vec![("Content-Encoding".to_string(), "gzip".to_string())]
+    );
+    let mut payload = Vec::new();
+    GzDecoder::new(message.payload.as_slice())
+        .read_to_end(&mut payload)
+        .unwrap();
+    assert_eq!(payload, vec![b'a'; 4096]);
+}
+
+#[test]
+fn test_zstd_payload_round_trip() {
+    let message = create_compressor(CompressionAlgorithm::Zstd).compress(create_large_message());
+    let message = message.expect("Failed to compress");
+
+    assert_eq!(
+        message.headers,
+        vec![("Content-Encoding".to_string(), "zstd".to_string())]
+    );
+    assert!(message.payload.len() < 4096);
+    assert_eq!(
+        zstd::decode_all(message.payload.as_slice()).unwrap(),
+        vec![b'a'; 4096]
+    );
+}
+
+#[test]
+fn test_small_payload_left_uncompressed() {
+    let message = NatsMessage::new("solana.control".to_string(), b"small".to_vec());
+    let message = create_compressor(CompressionAlgorithm::Zstd)
+        .compress(message)
+        .expect("Failed to compress");
+
+    assert!(message.headers.is_empty());
+    assert_eq!(message.payload, b"small");
+}
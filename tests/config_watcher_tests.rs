@@ -0,0 +1,133 @@
+use {
+    solana_geyser_plugin_nats::{
+        config::{ConfigurationManager, NatsPluginConfig},
+        config_watcher::ConfigWatcher,
+        connection::ConnectionManager,
+        processor::TransactionProcessor,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{fs, path::Path, sync::Arc, thread, time::Duration},
+    tempfile::tempdir,
+};
+
+fn create_test_connection_manager() -> Arc<ConnectionManager> {
+    // Nothing listens on this port, so queued messages stay queued
+    Arc::new(ConnectionManager::new("nats://127.0.0.1:9999", 100, 1).unwrap())
+}
+
+fn write_config(path: &Path, routes: &str) {
+    fs::write(
+        path,
+        format!(
+            r#"{{
+                "libpath": "libsolana_geyser_plugin_nats.so",
+                "nats_url": "nats://127.0.0.1:9999",
+                "routes": {routes}
+            }}"#
+        ),
+    )
+    .unwrap();
+}
+
+/// Wait for the watcher to pick up a change, up to a few seconds
+fn wait_for(condition: impl Fn() -> bool) -> bool {
+    for _ in 0..100 {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_changed_routes_applied() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.json");
+    write_config(&path, "[]");
+    let config = ConfigurationManager::load_config(path.to_str().unwrap()).unwrap();
+    let processor = Arc::new(
+        TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap(),
+    );
+    assert!(processor.transaction_router().is_none());
+
+    let mut watcher = ConfigWatcher::start(
+        path.clone(),
+        Duration::from_millis(100),
+        config,
+        processor.clone(),
+    );
+
+    // Let the watcher record the initial modification time before changing the file
+    thread::sleep(Duration::from_millis(300));
+    write_config(
+        &path,
+        r#"[{"name": "failures", "subject": "team.failures", "filter": {"only_failed": true}}]"#,
+    );
+    assert!(wait_for(|| processor.transaction_router().is_some()));
+
+    // An invalid file keeps the current filters
+    fs::write(&path, "{ not json").unwrap();
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(processor.transaction_router().unwrap().routes().len(), 1);
+
+    write_config(&path, "[]");
+    assert!(wait_for(|| processor.transaction_router().is_none()));
+    watcher.stop();
+}
+
+#[test]
+fn test_changed_address_file_applied() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.json");
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    fs::write(dir.path().join("wallets.txt"), format!("{first}\n")).unwrap();
+    fs::write(
+        &path,
+        r#"{
+            "libpath": "libsolana_geyser_plugin_nats.so",
+            "nats_url": "nats://127.0.0.1:9999",
+            "filter": { "select_all_transactions": false, "mentioned_addresses_file": "wallets.txt" }
+        }"#,
+    )
+    .unwrap();
+    let config = ConfigurationManager::load_config(path.to_str().unwrap()).unwrap();
+    let processor = Arc::new(
+        TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap(),
+    );
+    let selects = |address: &Pubkey| {
+        processor
+            .transaction_selector()
+            .mentioned_addresses
+            .contains(address.as_ref())
+    };
+    assert!(selects(&first));
+
+    let mut watcher = ConfigWatcher::start(
+        path.clone(),
+        Duration::from_millis(100),
+        config,
+        processor.clone(),
+    );
+
+    // Let the watcher record the initial modification times before changing the list
+    thread::sleep(Duration::from_millis(300));
+    fs::write(dir.path().join("wallets.txt"), format!("{second}\n")).unwrap();
+    assert!(wait_for(|| selects(&second)));
+    assert!(!selects(&first));
+    watcher.stop();
+}
+
+#[test]
+fn test_restart_required_for_other_settings() {
+    let config = NatsPluginConfig::default();
+
+    let mut reloaded = config.clone();
+    reloaded.subject = "solana.other".to_string();
+    reloaded.filter.select_vote_transactions = true;
+    assert!(!config.requires_restart(&reloaded));
+
+    reloaded.nats_url = "nats://other:4222".to_string();
+    assert!(config.requires_restart(&reloaded));
+}
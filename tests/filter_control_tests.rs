@@ -0,0 +1,175 @@
+use {
+    serde_json::Value,
+    solana_geyser_plugin_nats::{
+        config::{FilterControlConfig, NatsPluginConfig, TransactionFilterConfig},
+        connection::RequestHandler,
+        filter_control::FilterControl,
+        message_signer::{MESSAGE_SIGNATURE_HEADER, MESSAGE_SIGNER_HEADER},
+    },
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+    },
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+fn create_filter_control(signer: &Keypair) -> FilterControl {
+    FilterControl::new(&NatsPluginConfig {
+        filter: TransactionFilterConfig {
+            select_all_transactions: false,
+            mentioned_addresses: vec![Pubkey::new_unique().to_string()],
+            ..TransactionFilterConfig::default()
+        },
+        filter_control: FilterControlConfig {
+            enabled: true,
+            authorized_signers: vec![signer.pubkey().to_string()],
+            ..FilterControlConfig::default()
+        },
+        ..NatsPluginConfig::default()
+    })
+    .unwrap()
+    .unwrap()
+}
+
+fn signed_headers(signer: &Keypair, payload: &[u8]) -> Vec<(String, String)> {
+    vec![
+        (
+            MESSAGE_SIGNATURE_HEADER.to_string(),
+            signer.sign_message(payload).to_string(),
+        ),
+        (
+            MESSAGE_SIGNER_HEADER.to_string(),
+            signer.pubkey().to_string(),
+        ),
+    ]
+}
+
+/// Current Unix time in seconds, which update versions are
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn address_update(version: u64, address: &Pubkey) -> Vec<u8> {
+    format!(r#"{{"version": {version}, "mentioned_addresses": ["{address}"]}}"#).into_bytes()
+}
+
+#[test]
+fn test_filter_control_disabled_by_default() {
+    assert!(FilterControl::new(&NatsPluginConfig::default())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_signed_update_swaps_selector() {
+    let signer = Keypair::new();
+    let filter_control = create_filter_control(&signer);
+    assert_eq!(
+        filter_control.subjects(),
+        vec!["solana.plugin.control.filters"]
+    );
+    let before = filter_control.selector();
+
+    let address = Pubkey::new_unique();
+    let version = unix_secs() + 1;
+    let payload = address_update(version, &address);
+    let reply = filter_control
+        .handle_request(
+            "solana.plugin.control.filters",
+            &signed_headers(&signer, &payload),
+            &payload,
+        )
+        .unwrap();
+
+    let reply: Value = serde_json::from_slice(&reply).unwrap();
+    assert_eq!(reply["applied"], true);
+    assert_eq!(reply["version"], version);
+    assert_eq!(filter_control.version(), version);
+
+    // Holders of the previous selector keep a consistent view
+    let selector = filter_control.selector();
+    assert!(selector.is_transaction_selected(false, Box::new([address].iter())));
+    assert!(!before.is_transaction_selected(false, Box::new([address].iter())));
+}
+
+#[test]
+fn test_unauthorized_updates_rejected() {
+    let signer = Keypair::new();
+    let filter_control = create_filter_control(&signer);
+    let loaded = filter_control.version();
+    let payload = address_update(unix_secs() + 1, &Pubkey::new_unique());
+
+    let mut tampered = signed_headers(&signer, &payload);
+    tampered[0].1 = signer.sign_message(b"other payload").to_string();
+    for headers in [vec![], signed_headers(&Keypair::new(), &payload), tampered] {
+        assert!(filter_control.apply_update(&headers, &payload).is_err());
+    }
+    assert_eq!(filter_control.version(), loaded);
+}
+
+#[test]
+fn test_invalid_and_replayed_updates_rejected() {
+    let signer = Keypair::new();
+    let filter_control = create_filter_control(&signer);
+
+    let version = unix_secs() + 2;
+    let applied = address_update(version, &Pubkey::new_unique());
+    filter_control
+        .apply_update(&signed_headers(&signer, &applied), &applied)
+        .unwrap();
+
+    let next = version + 1;
+    for payload in [
+        applied.clone(),
+        address_update(version - 1, &Pubkey::new_unique()),
+        format!(r#"{{"version": {next}, "mentioned_addresses": ["not-base58-0OIl"]}}"#)
+            .into_bytes(),
+        format!(r#"{{"version": {next}, "mentioned_addresses": ["3yZe7d"]}}"#).into_bytes(),
+        format!(r#"{{"version": {next}, "program_ids": []}}"#).into_bytes(),
+        b"not json".to_vec(),
+    ] {
+        let result = filter_control.apply_update(&signed_headers(&signer, &payload), &payload);
+        assert!(result.is_err(), "{}", String::from_utf8_lossy(&payload));
+    }
+    assert_eq!(filter_control.version(), version);
+
+    let reply = filter_control
+        .handle_request("solana.plugin.control.filters", &[], &applied)
+        .unwrap();
+    let reply: Value = serde_json::from_slice(&reply).unwrap();
+    assert_eq!(reply["applied"], false);
+    assert!(reply["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Unauthorized filter update"));
+}
+
+#[test]
+fn test_update_replayed_after_restart_rejected() {
+    let signer = Keypair::new();
+
+    // Signed and applied by an earlier run, then replayed once the plugin reloads
+    let captured = address_update(unix_secs() - 1, &Pubkey::new_unique());
+    let filter_control = create_filter_control(&signer);
+    let loaded = filter_control.version();
+
+    let result = filter_control.apply_update(&signed_headers(&signer, &captured), &captured);
+    assert!(result.unwrap_err().to_string().contains("not newer"));
+    assert_eq!(filter_control.version(), loaded);
+}
+
+#[test]
+fn test_update_outside_clock_skew_rejected() {
+    let signer = Keypair::new();
+    let filter_control = create_filter_control(&signer);
+
+    let payload = address_update(unix_secs() + 3600, &Pubkey::new_unique());
+    let result = filter_control.apply_update(&signed_headers(&signer, &payload), &payload);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("away from the current time"));
+}
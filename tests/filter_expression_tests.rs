@@ -0,0 +1,198 @@
+use {
+    solana_geyser_plugin_nats::{
+        config::{RouteConfig, TransactionFilterConfig},
+        filter_expression::{FilterExpression, FilterExpressionError},
+        transaction_router::TransactionRouter,
+    },
+    solana_sdk::{
+        instruction::CompiledInstruction,
+        message::Message,
+        pubkey::Pubkey,
+        signature::Signature,
+        system_instruction, system_program,
+        transaction::{SanitizedTransaction, Transaction, TransactionError},
+    },
+    solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta},
+    std::collections::HashSet,
+};
+
+fn create_transfer(from: &Pubkey, to: &Pubkey) -> SanitizedTransaction {
+    let message = Message::new(&[system_instruction::transfer(from, to, 1_000)], Some(from));
+    SanitizedTransaction::try_from_legacy_transaction(
+        Transaction {
+            signatures: vec![Signature::default()],
+            message,
+        },
+        &HashSet::new(),
+    )
+    .unwrap()
+}
+
+fn create_meta(fee: u64, failed: bool) -> TransactionStatusMeta {
+    TransactionStatusMeta {
+        status: if failed {
+            Err(TransactionError::InsufficientFundsForFee)
+        } else {
+            Ok(())
+        },
+        fee,
+        compute_units_consumed: Some(150),
+        ..TransactionStatusMeta::default()
+    }
+}
+
+fn matches(source: &str, transaction: &SanitizedTransaction, meta: &TransactionStatusMeta) -> bool {
+    FilterExpression::parse(source)
+        .unwrap()
+        .matches(false, transaction, meta, 100)
+}
+
+#[test]
+fn test_fields_and_operators() {
+    let from = Pubkey::new_unique();
+    let transaction = create_transfer(&from, &Pubkey::new_unique());
+    let meta = create_meta(20_000, false);
+
+    assert!(matches("fee > 10000 && !is_vote", &transaction, &meta));
+    assert!(!matches("fee > 10000 && is_vote", &transaction, &meta));
+    assert!(matches("fee < 100 || slot == 100", &transaction, &meta));
+    assert!(matches(
+        "!(failed || compute_units >= 200)",
+        &transaction,
+        &meta
+    ));
+    assert!(matches(
+        "compute_units <= 150 && slot != 99",
+        &transaction,
+        &meta
+    ));
+    assert!(matches(
+        &format!("fee_payer == \"{from}\""),
+        &transaction,
+        &meta
+    ));
+    assert!(matches("failed == false && true", &transaction, &meta));
+    assert!(!matches("failed", &transaction, &create_meta(0, false)));
+    assert!(matches("failed", &transaction, &create_meta(0, true)));
+}
+
+#[test]
+fn test_address_lists() {
+    let to = Pubkey::new_unique();
+    let transaction = create_transfer(&Pubkey::new_unique(), &to);
+    let meta = create_meta(5_000, false);
+
+    assert!(matches(
+        &format!("programs.contains(\"{}\")", system_program::id()),
+        &transaction,
+        &meta
+    ));
+    assert!(matches(
+        &format!("accounts.contains(\"{to}\")"),
+        &transaction,
+        &meta
+    ));
+    assert!(!matches(
+        &format!("accounts.contains(\"{}\")", Pubkey::new_unique()),
+        &transaction,
+        &meta
+    ));
+    assert!(matches(
+        "accounts.len() == 3 && programs.len() == 1",
+        &transaction,
+        &meta
+    ));
+}
+
+#[test]
+fn test_programs_include_inner_instructions() {
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let account_keys = transaction.message().account_keys();
+    let inner_program = account_keys[1];
+    let meta = TransactionStatusMeta {
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 0,
+            instructions: vec![InnerInstruction {
+                instruction: CompiledInstruction::new_from_raw_parts(1, vec![], vec![]),
+                stack_height: Some(2),
+            }],
+        }]),
+        ..create_meta(5_000, false)
+    };
+
+    assert!(matches(
+        &format!("programs.contains(\"{inner_program}\")"),
+        &transaction,
+        &meta
+    ));
+    assert!(matches("programs.len() == 2", &transaction, &meta));
+}
+
+#[test]
+fn test_invalid_expressions_rejected() {
+    for source in [
+        "",
+        "fee >",
+        "fee > 10 &&",
+        "(fee > 10",
+        "fee > 10 & is_vote",
+        "fee = 10",
+        "\"unterminated",
+        "fee > 99999999999999999999999",
+        "fee > 10 fee",
+        "#",
+    ] {
+        assert!(
+            matches!(
+                FilterExpression::parse(source),
+                Err(FilterExpressionError::SyntaxError { .. })
+            ),
+            "{source}"
+        );
+    }
+
+    for source in [
+        "fee",
+        "unknown > 1",
+        "fee > \"1\"",
+        "!fee",
+        "fee && is_vote",
+        "fee_payer < \"a\"",
+        "programs == accounts",
+        "programs.contains(\"not an address\")",
+        "programs.contains(fee)",
+        "programs.first()",
+        "fee.len() > 1",
+    ] {
+        assert!(
+            matches!(
+                FilterExpression::parse(source),
+                Err(FilterExpressionError::TypeError { .. })
+            ),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn test_route_expression() {
+    let router = TransactionRouter::new(&[RouteConfig {
+        name: "expensive".to_string(),
+        subject: "team.expensive".to_string(),
+        filter: TransactionFilterConfig {
+            expression: Some("fee >= 10000".to_string()),
+            ..TransactionFilterConfig::default()
+        },
+    }])
+    .unwrap()
+    .unwrap();
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+
+    assert_eq!(
+        router.subjects(false, &transaction, &create_meta(10_000, false), 1),
+        vec!["team.expensive"]
+    );
+    assert!(router
+        .subjects(false, &transaction, &create_meta(5_000, false), 1)
+        .is_empty());
+}
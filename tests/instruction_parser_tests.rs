@@ -0,0 +1,188 @@
+use {
+    agave_geyser_plugin_interface::geyser_plugin_interface::ReplicaTransactionInfoV2,
+    serde_json::json,
+    solana_geyser_plugin_nats::{
+        instruction_parser::InstructionParser, serializer::TransactionSerializer,
+    },
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{AccountMeta, CompiledInstruction, Instruction},
+        message::{AccountKeys, Message, VersionedMessage},
+        pubkey::Pubkey,
+        signature::Signature,
+        system_instruction,
+        transaction::{SanitizedTransaction, VersionedTransaction},
+    },
+    solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta},
+    std::{collections::HashSet, str::FromStr},
+};
+
+fn spl_token_id() -> Pubkey {
+    Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap()
+}
+
+fn spl_memo_id() -> Pubkey {
+    Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap()
+}
+
+/// Compile a single instruction against its own account keys and decode it
+fn parse(instruction: Instruction) -> Option<(String, serde_json::Value)> {
+    let payer = Pubkey::new_unique();
+    let message = Message::new(&[instruction], Some(&payer));
+    let account_keys = AccountKeys::new(&message.account_keys, None);
+    let compiled = &message.instructions[0];
+    let program_id = account_keys[compiled.program_id_index as usize];
+    InstructionParser::parse(&program_id, compiled, &account_keys)
+}
+
+#[test]
+fn test_parse_system_transfer() {
+    let from = Pubkey::new_unique();
+    let to = Pubkey::new_unique();
+
+    let (program, parsed) = parse(system_instruction::transfer(&from, &to, 42)).unwrap();
+
+    assert_eq!(program, "system");
+    assert_eq!(
+        parsed,
+        json!({
+            "type": "transfer",
+            "info": {
+                "source": from.to_string(),
+                "destination": to.to_string(),
+                "lamports": 42,
+            },
+        })
+    );
+}
+
+#[test]
+fn test_parse_spl_token_transfer() {
+    let source = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let mut data = vec![3];
+    data.extend(1_000u64.to_le_bytes());
+
+    let (program, parsed) = parse(Instruction::new_with_bytes(
+        spl_token_id(),
+        &data,
+        vec![
+            AccountMeta::new(source, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+    ))
+    .unwrap();
+
+    assert_eq!(program, "spl-token");
+    assert_eq!(parsed["type"], "transfer");
+    assert_eq!(parsed["info"]["source"], source.to_string());
+    assert_eq!(parsed["info"]["destination"], destination.to_string());
+    assert_eq!(parsed["info"]["authority"], authority.to_string());
+    assert_eq!(parsed["info"]["amount"], "1000");
+}
+
+#[test]
+fn test_parse_memo_and_compute_budget() {
+    let (program, parsed) =
+        parse(Instruction::new_with_bytes(spl_memo_id(), b"hello", vec![])).unwrap();
+    assert_eq!(program, "spl-memo");
+    assert_eq!(parsed, json!("hello"));
+
+    let (program, parsed) =
+        parse(ComputeBudgetInstruction::set_compute_unit_limit(200_000)).unwrap();
+    assert_eq!(program, "compute-budget");
+    assert_eq!(
+        parsed,
+        json!({ "type": "setComputeUnitLimit", "info": { "units": 200_000 } })
+    );
+
+    let (_, parsed) = parse(ComputeBudgetInstruction::set_compute_unit_price(5_000)).unwrap();
+    assert_eq!(
+        parsed,
+        json!({ "type": "setComputeUnitPrice", "info": { "microLamports": 5_000 } })
+    );
+}
+
+#[test]
+fn test_unknown_and_malformed_instructions_not_parsed() {
+    let unknown = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![]);
+    assert!(parse(unknown).is_none());
+
+    let truncated =
+        Instruction::new_with_bytes(solana_sdk::compute_budget::id(), &[2, 0x40], vec![]);
+    assert!(parse(truncated).is_none());
+}
+
+#[test]
+fn test_attach_to_serialized_transaction() {
+    let from = Pubkey::new_unique();
+    let to = Pubkey::new_unique();
+    let custom_program = Pubkey::new_unique();
+    let message = Message::new(
+        &[
+            system_instruction::transfer(&from, &to, 42),
+            Instruction::new_with_bytes(custom_program, &[9], vec![AccountMeta::new(to, false)]),
+        ],
+        Some(&from),
+    );
+    let transaction = SanitizedTransaction::try_from_legacy_transaction(
+        VersionedTransaction {
+            message: VersionedMessage::Legacy(message.clone()),
+            signatures: vec![Signature::new_unique()],
+        }
+        .into_legacy_transaction()
+        .unwrap(),
+        &HashSet::new(),
+    )
+    .unwrap();
+
+    // The custom program invokes a transfer back to the payer
+    let system_program_index = message
+        .account_keys
+        .iter()
+        .position(|key| *key == solana_sdk::system_program::id())
+        .unwrap() as u8;
+    let mut transfer_data = vec![2, 0, 0, 0];
+    transfer_data.extend(7u64.to_le_bytes());
+    let meta = TransactionStatusMeta {
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 1,
+            instructions: vec![InnerInstruction {
+                instruction: CompiledInstruction::new_from_raw_parts(
+                    system_program_index,
+                    transfer_data,
+                    vec![1, 0],
+                ),
+                stack_height: Some(2),
+            }],
+        }]),
+        ..TransactionStatusMeta::default()
+    };
+
+    let mut value = TransactionSerializer::serialize_transaction_v2(
+        &ReplicaTransactionInfoV2 {
+            signature: &transaction.signatures()[0],
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        },
+        1,
+    )
+    .unwrap();
+    InstructionParser::attach(&mut value, &transaction, &meta);
+
+    let instructions = &value["transaction"]["message"]["instructions"];
+    assert_eq!(instructions[0]["program"], "system");
+    assert_eq!(instructions[0]["parsed"]["info"]["lamports"], 42);
+    assert!(instructions[0].get("data").is_some());
+    assert!(instructions[1].get("parsed").is_none());
+
+    let inner = &value["meta"]["innerInstructions"][0]["instructions"][0];
+    assert_eq!(inner["parsed"]["type"], "transfer");
+    assert_eq!(inner["parsed"]["info"]["source"], to.to_string());
+    assert_eq!(inner["parsed"]["info"]["destination"], from.to_string());
+    assert_eq!(inner["parsed"]["info"]["lamports"], 7);
+}
@@ -38,6 +38,7 @@ fn test_plugin_workflow_with_nats() {
         max_retries: 5,
         timeout_secs: 10,
         filter: TransactionFilterConfig::default(),
+        ..NatsPluginConfig::default()
     };
     let config_json = serde_json::to_string(&config).expect("Failed to serialize config");
     fs::write(&temp_file, config_json).expect("Failed to write to temp file");
@@ -70,6 +71,7 @@ fn test_nats_connection_failure_handling() {
         max_retries: 5,
         timeout_secs: 10,
         filter: TransactionFilterConfig::default(),
+        ..NatsPluginConfig::default()
     };
     let config_json = serde_json::to_string(&config).expect("Failed to serialize config");
     fs::write(&temp_file, config_json).expect("Failed to write to temp file");
@@ -128,6 +130,7 @@ fn test_plugin_with_real_nats_server() {
         max_retries: 5,
         timeout_secs: 10,
         filter: TransactionFilterConfig::default(),
+        ..NatsPluginConfig::default()
     };
     let config_json = serde_json::to_string(&config).expect("Failed to serialize config");
     fs::write(&temp_file, config_json).expect("Failed to write to temp file");
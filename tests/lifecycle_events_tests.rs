@@ -0,0 +1,86 @@
+use {
+    serde_json::Value,
+    solana_geyser_plugin_nats::{lifecycle_events::LifecycleEvents, NatsMessage},
+};
+
+fn lifecycle_events() -> LifecycleEvents {
+    LifecycleEvents::new(
+        "mainnet.solana.plugin.events".to_string(),
+        "primary",
+        "validator-1",
+        Some("mainnet"),
+    )
+}
+
+fn payload(message: &NatsMessage) -> Value {
+    serde_json::from_slice(&message.payload).unwrap()
+}
+
+#[test]
+fn test_event_payload() {
+    let lifecycle_events = lifecycle_events();
+    lifecycle_events.record(
+        "reconnecting",
+        Some("nats.example.com:4222"),
+        Some("Connection refused"),
+        Some(2),
+    );
+
+    let events = lifecycle_events.take();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].subject, "mainnet.solana.plugin.events");
+    assert_eq!(
+        events[0].headers,
+        vec![("Solana-Network".to_string(), "mainnet".to_string())]
+    );
+
+    let event = payload(&events[0]);
+    assert_eq!(event["event"], "reconnecting");
+    assert_eq!(event["cluster"], "primary");
+    assert_eq!(event["clientName"], "validator-1");
+    assert_eq!(event["server"], "nats.example.com:4222");
+    assert_eq!(event["reason"], "Connection refused");
+    assert_eq!(event["attempt"], 2);
+    assert_eq!(event["network"], "mainnet");
+    assert_eq!(event["schemaVersion"], 2);
+    assert!(event["timestamp"].is_string());
+}
+
+#[test]
+fn test_take_drains_in_order() {
+    let lifecycle_events = lifecycle_events();
+    lifecycle_events.record("disconnected", None, Some("Connection lost"), None);
+    lifecycle_events.record("connected", None, None, None);
+
+    let events: Vec<_> = lifecycle_events.take().iter().map(payload).collect();
+    assert_eq!(events[0]["event"], "disconnected");
+    assert_eq!(events[1]["event"], "connected");
+    assert_eq!(lifecycle_events.pending(), 0);
+}
+
+#[test]
+fn test_restored_events_stay_ahead() {
+    let lifecycle_events = lifecycle_events();
+    lifecycle_events.record("disconnected", None, None, None);
+    let unpublished = lifecycle_events.take();
+    lifecycle_events.record("reconnecting", None, None, Some(1));
+
+    lifecycle_events.restore(unpublished);
+
+    let events: Vec<_> = lifecycle_events.take().iter().map(payload).collect();
+    assert_eq!(events[0]["event"], "disconnected");
+    assert_eq!(events[1]["event"], "reconnecting");
+}
+
+#[test]
+fn test_oldest_events_dropped_when_full() {
+    let lifecycle_events = lifecycle_events();
+    for attempt in 1..=150 {
+        lifecycle_events.record("reconnecting", None, None, Some(attempt));
+    }
+
+    let events = lifecycle_events.take();
+    assert_eq!(events.len(), 100);
+    assert_eq!(payload(&events[0])["attempt"], 51);
+    assert_eq!(payload(&events[99])["attempt"], 150);
+}
@@ -0,0 +1,64 @@
+use {
+    solana_geyser_plugin_nats::{
+        config::LoadSheddingConfig,
+        load_shedder::{LoadShedder, SheddingLevel},
+    },
+    solana_sdk::pubkey::Pubkey,
+};
+
+fn create_shedder(priority_addresses: &[Pubkey]) -> LoadShedder {
+    LoadShedder::new(&LoadSheddingConfig {
+        enabled: true,
+        vote_threshold: 10,
+        sampling_threshold: 100,
+        sample_rate: 4,
+        priority_addresses: priority_addresses.iter().map(Pubkey::to_string).collect(),
+    })
+}
+
+#[test]
+fn test_nothing_shed_below_thresholds() {
+    let shedder = create_shedder(&[]);
+    let keys = [Pubkey::new_unique()];
+
+    assert!(!shedder.should_shed(9, true, keys.iter()));
+    assert!(!shedder.should_shed(9, false, keys.iter()));
+    assert_eq!(shedder.level(), SheddingLevel::None);
+}
+
+#[test]
+fn test_votes_shed_first() {
+    let shedder = create_shedder(&[]);
+    let keys = [Pubkey::new_unique()];
+
+    assert!(shedder.should_shed(10, true, keys.iter()));
+    assert!(!shedder.should_shed(10, false, keys.iter()));
+    assert_eq!(shedder.level(), SheddingLevel::Votes);
+}
+
+#[test]
+fn test_sampling_keeps_priority_transactions() {
+    let priority = Pubkey::new_unique();
+    let shedder = create_shedder(&[priority]);
+    let other = [Pubkey::new_unique()];
+
+    let published = (0..8)
+        .filter(|_| !shedder.should_shed(100, false, other.iter()))
+        .count();
+    assert_eq!(published, 2);
+    assert_eq!(shedder.level(), SheddingLevel::Sampling);
+
+    let keys = [Pubkey::new_unique(), priority];
+    assert!((0..8).all(|_| !shedder.should_shed(100, false, keys.iter())));
+    assert!(shedder.should_shed(100, true, keys.iter()));
+}
+
+#[test]
+fn test_full_publishing_restored_when_queue_drains() {
+    let shedder = create_shedder(&[]);
+    let keys = [Pubkey::new_unique()];
+
+    assert!(shedder.should_shed(500, true, keys.iter()));
+    assert!(!shedder.should_shed(0, true, keys.iter()));
+    assert_eq!(shedder.level(), SheddingLevel::None);
+}
@@ -0,0 +1,86 @@
+use {
+    serde_json::{json, Value},
+    solana_geyser_plugin_nats::{config::LogLimitsConfig, LogLimiter},
+};
+
+fn create_limiter(max_lines: usize, max_line_bytes: usize, max_payload_bytes: usize) -> LogLimiter {
+    LogLimiter::new(&LogLimitsConfig {
+        max_lines,
+        max_line_bytes,
+        max_payload_bytes,
+    })
+    .expect("log limits are set")
+}
+
+fn create_payload(logs: &[&str]) -> Value {
+    json!({
+        "slot": 300,
+        "meta": { "fee": 5000, "logMessages": logs },
+    })
+}
+
+#[test]
+fn test_no_limiter_without_limits() {
+    assert!(LogLimiter::new(&LogLimitsConfig::default()).is_none());
+}
+
+#[test]
+fn test_limit_lines() {
+    let limiter = create_limiter(2, 0, 0);
+
+    let mut payload = create_payload(&["one", "two", "three"]);
+    limiter.limit_lines(&mut payload);
+    assert_eq!(payload["meta"]["logMessages"], json!(["one", "two"]));
+    assert_eq!(payload["truncated"], true);
+
+    // Payloads within the limits are not marked
+    let mut payload = create_payload(&["one", "two"]);
+    limiter.limit_lines(&mut payload);
+    assert!(payload.get("truncated").is_none());
+}
+
+#[test]
+fn test_limit_line_bytes_on_char_boundary() {
+    let limiter = create_limiter(0, 5, 0);
+
+    let mut payload = create_payload(&["Program log: hello", "short", "abcdé"]);
+    limiter.limit_lines(&mut payload);
+    assert_eq!(
+        payload["meta"]["logMessages"],
+        json!(["Progr...", "short", "abcd..."])
+    );
+    assert_eq!(payload["truncated"], true);
+}
+
+#[test]
+fn test_shed_logs_of_oversized_payload() {
+    let limiter = create_limiter(0, 0, 100);
+    assert!(limiter.limits_payload());
+
+    let logs: Vec<String> = (0..10).map(|line| format!("Program log: {line}")).collect();
+    let mut payload = create_payload(&logs.iter().map(String::as_str).collect::<Vec<_>>());
+    payload["meta"]["parsedLogs"] = json!([{ "programId": "11111111111111111111111111111111" }]);
+    let payload_len = payload.to_string().len();
+    assert!(limiter.is_oversized(payload_len));
+
+    assert!(limiter.shed(&mut payload, payload_len));
+    assert!(!limiter.is_oversized(payload.to_string().len()));
+    assert!(payload["meta"].get("parsedLogs").is_none());
+    // The first log messages are kept
+    let kept = payload["meta"]["logMessages"].as_array().unwrap();
+    assert!(!kept.is_empty() && kept.len() < logs.len());
+    assert_eq!(kept[0], "Program log: 0");
+    assert_eq!(payload["truncated"], true);
+
+    limiter.shed_all(&mut payload);
+    assert_eq!(payload["meta"]["logMessages"], json!([]));
+}
+
+#[test]
+fn test_nothing_shed_without_logs() {
+    let limiter = create_limiter(0, 0, 10);
+
+    let mut payload = json!({ "slot": 300, "transaction": "AQID" });
+    assert!(!limiter.shed(&mut payload, 1000));
+    assert!(payload.get("truncated").is_none());
+}
@@ -0,0 +1,67 @@
+use {
+    solana_geyser_plugin_nats::{
+        connection::NatsMessage,
+        message_signer::{MessageSigner, MESSAGE_SIGNATURE_HEADER, MESSAGE_SIGNER_HEADER},
+    },
+    solana_sdk::{
+        signature::{write_keypair_file, Keypair, Signature, Signer},
+        signer::keypair::read_keypair_file,
+    },
+    std::str::FromStr,
+    tempfile::NamedTempFile,
+};
+
+fn create_keypair_file() -> NamedTempFile {
+    let keypair_file = NamedTempFile::new().unwrap();
+    write_keypair_file(&Keypair::new(), keypair_file.path()).unwrap();
+    keypair_file
+}
+
+fn header<'a>(message: &'a NatsMessage, name: &str) -> &'a str {
+    message
+        .headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_else(|| panic!("{name} header missing"))
+}
+
+#[test]
+fn test_signer_disabled_without_keypair() {
+    assert!(MessageSigner::new(None).is_none());
+    assert!(MessageSigner::new(Some("/nonexistent/keypair.json")).is_none());
+}
+
+#[test]
+fn test_signature_verifies_against_payload() {
+    let keypair_file = create_keypair_file();
+    let keypair = read_keypair_file(keypair_file.path()).unwrap();
+    let signer = MessageSigner::new(keypair_file.path().to_str()).unwrap();
+    assert_eq!(signer.pubkey(), keypair.pubkey());
+
+    let mut message = NatsMessage::new("solana.transactions".to_string(), b"payload".to_vec());
+    signer.sign(&mut message);
+
+    assert_eq!(
+        header(&message, MESSAGE_SIGNER_HEADER),
+        keypair.pubkey().to_string()
+    );
+    let signature = Signature::from_str(header(&message, MESSAGE_SIGNATURE_HEADER)).unwrap();
+    assert!(signature.verify(keypair.pubkey().as_ref(), b"payload"));
+    assert!(!signature.verify(keypair.pubkey().as_ref(), b"tampered"));
+}
+
+#[test]
+fn test_resigning_replaces_headers() {
+    let keypair_file = create_keypair_file();
+    let signer = MessageSigner::new(keypair_file.path().to_str()).unwrap();
+
+    let mut message = NatsMessage::new("solana.transactions".to_string(), b"first".to_vec());
+    signer.sign(&mut message);
+    message.payload = b"second".to_vec();
+    signer.sign(&mut message);
+
+    assert_eq!(message.headers.len(), 2);
+    let signature = Signature::from_str(header(&message, MESSAGE_SIGNATURE_HEADER)).unwrap();
+    assert!(signature.verify(signer.pubkey().as_ref(), b"second"));
+}
@@ -0,0 +1,75 @@
+use {
+    serde_json::{json, Value},
+    solana_geyser_plugin_nats::{config::PayloadFieldsConfig, PayloadShaper},
+};
+
+fn create_payload() -> Value {
+    json!({
+        "transaction": { "signatures": ["sig"], "message": {} },
+        "version": "legacy",
+        "slot": 300,
+        "meta": {
+            "fee": 5000,
+            "err": null,
+            "preBalances": [1, 2],
+            "postBalances": [1, 2],
+            "logMessages": ["Program log: ok"],
+        },
+    })
+}
+
+fn shape(include: &[&str], exclude: &[&str]) -> Value {
+    let shaper = PayloadShaper::new(&PayloadFieldsConfig {
+        include: include.iter().map(|field| field.to_string()).collect(),
+        exclude: exclude.iter().map(|field| field.to_string()).collect(),
+    })
+    .expect("payload shaping is enabled");
+
+    let mut payload = create_payload();
+    shaper.apply(&mut payload);
+    payload
+}
+
+#[test]
+fn test_no_shaper_without_fields() {
+    assert!(PayloadShaper::new(&PayloadFieldsConfig::default()).is_none());
+}
+
+#[test]
+fn test_exclude_top_level_and_meta_fields() {
+    let payload = shape(&[], &["version", "meta.logMessages", "meta.preBalances"]);
+
+    assert!(payload.get("version").is_none());
+    assert_eq!(payload["slot"], 300);
+    assert_eq!(payload["transaction"], create_payload()["transaction"]);
+    assert_eq!(
+        payload["meta"],
+        json!({ "fee": 5000, "err": null, "postBalances": [1, 2] })
+    );
+}
+
+#[test]
+fn test_include_only_listed_fields() {
+    let payload = shape(&["slot", "meta.fee", "meta.err"], &[]);
+
+    assert_eq!(
+        payload,
+        json!({ "slot": 300, "meta": { "fee": 5000, "err": null } })
+    );
+}
+
+#[test]
+fn test_include_whole_field_and_exclude_nested() {
+    let payload = shape(&["slot", "meta.fee", "meta"], &["meta.logMessages"]);
+
+    // Including `meta` keeps all of it, short of the excluded fields
+    let mut meta = create_payload()["meta"].clone();
+    meta.as_object_mut().unwrap().remove("logMessages");
+    assert_eq!(payload, json!({ "slot": 300, "meta": meta }));
+}
+
+#[test]
+fn test_missing_fields_ignored() {
+    let payload = shape(&[], &["rewards", "meta.returnData", "slot.nested"]);
+    assert_eq!(payload, create_payload());
+}
@@ -1,20 +1,32 @@
 use {
     agave_geyser_plugin_interface::geyser_plugin_interface::{
-        ReplicaTransactionInfo, ReplicaTransactionInfoV2, ReplicaTransactionInfoVersions,
+        ReplicaBlockInfo, ReplicaBlockInfoVersions, ReplicaTransactionInfo,
+        ReplicaTransactionInfoV2, ReplicaTransactionInfoVersions, SlotStatus,
     },
     solana_geyser_plugin_nats::{
-        config::TransactionFilterConfig,
-        connection::ConnectionManager,
+        circuit_breaker::CircuitBreaker,
+        config::{
+            AddressFanoutConfig, AddressMatch, AddressRateLimitConfig, BatchingConfig,
+            BlockAssemblyConfig, CircuitBreakerConfig, CommitmentUpdatesConfig,
+            DeduplicationConfig, MessageTtlConfig, NatsPluginConfig, PayloadFormat, RewardsConfig,
+            RouteConfig, SlotDropConfig, SubjectsConfig, TransactionFilterConfig,
+            TransactionSchema, VoteSummaryConfig,
+        },
+        connection::{ConnectionManager, ConnectionOptions},
         processor::{ProcessingError, TransactionProcessor},
+        transaction_router,
     },
     solana_sdk::{
+        instruction::CompiledInstruction,
         message::Message,
         pubkey::Pubkey,
         signature::Signature,
         system_instruction,
         transaction::{SanitizedTransaction, Transaction},
     },
-    solana_transaction_status::TransactionStatusMeta,
+    solana_transaction_status::{
+        InnerInstruction, InnerInstructions, Reward, RewardType, TransactionStatusMeta,
+    },
     std::{collections::HashSet, sync::Arc, thread},
 };
 
@@ -109,7 +121,8 @@ mod processor_creation_tests {
         let filter_config = TransactionFilterConfig::default();
         let subject = "test.subject".to_string();
 
-        let processor = TransactionProcessor::new(connection_manager, &filter_config, subject);
+        let processor =
+            TransactionProcessor::new(connection_manager, &filter_config, subject).unwrap();
 
         assert!(processor.is_enabled());
         assert!(processor.transaction_selector().select_all_transactions);
@@ -127,13 +140,15 @@ mod processor_creation_tests {
             select_all_transactions: false,
             select_vote_transactions: true,
             mentioned_addresses: vec![],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         assert!(processor.is_enabled());
         assert!(!processor.transaction_selector().select_all_transactions);
@@ -152,13 +167,15 @@ mod processor_creation_tests {
             select_all_transactions: false,
             select_vote_transactions: false,
             mentioned_addresses: vec![test_address],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         assert!(processor.is_enabled());
         assert!(!processor.transaction_selector().select_all_transactions);
@@ -180,13 +197,15 @@ mod processor_creation_tests {
             select_all_transactions: false,
             select_vote_transactions: false,
             mentioned_addresses: vec![],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         // Should default to select_all_transactions
         assert!(processor.is_enabled());
@@ -206,7 +225,8 @@ mod transaction_processing_tests {
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         let tx_info = create_replica_transaction_info_v2(false);
         let transaction_info = ReplicaTransactionInfoVersions::V0_0_2(&tx_info);
@@ -226,7 +246,8 @@ mod transaction_processing_tests {
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         let tx_info = create_replica_transaction_info_v1(false);
         let transaction_info = ReplicaTransactionInfoVersions::V0_0_1(&tx_info);
@@ -235,6 +256,47 @@ mod transaction_processing_tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_process_transaction_raw_format() {
+        let config = NatsPluginConfig {
+            format: PayloadFormat::Raw,
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345);
+        assert!(result.is_ok());
+
+        let tx_info = create_replica_transaction_info_v1(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_1(&tx_info), 12345);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_process_transaction_rpc_schema() {
+        let config = NatsPluginConfig {
+            transaction_schema: TransactionSchema::Rpc,
+            parsed_instructions: true,
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345);
+        assert!(result.is_ok());
+
+        let tx_info = create_replica_transaction_info_v1(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_1(&tx_info), 12345);
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn test_process_transaction_vote_included_when_enabled() {
         let connection_manager = create_test_connection_manager();
@@ -242,13 +304,15 @@ mod transaction_processing_tests {
             select_all_transactions: false,
             select_vote_transactions: true,
             mentioned_addresses: vec![],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         let tx_info = create_replica_transaction_info_v2(true); // is_vote = true
         let transaction_info = ReplicaTransactionInfoVersions::V0_0_2(&tx_info);
@@ -267,13 +331,15 @@ mod transaction_processing_tests {
             select_all_transactions: false,
             select_vote_transactions: false,
             mentioned_addresses: vec![random_address],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         let tx_info = create_replica_transaction_info_v2(true); // is_vote = true
         let transaction_info = ReplicaTransactionInfoVersions::V0_0_2(&tx_info);
@@ -296,13 +362,15 @@ mod transaction_processing_tests {
             select_all_transactions: false,
             select_vote_transactions: false,
             mentioned_addresses: vec![target_address],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         let transaction_info = ReplicaTransactionInfoVersions::V0_0_2(&tx_info);
 
@@ -320,13 +388,15 @@ mod transaction_processing_tests {
             select_all_transactions: false,
             select_vote_transactions: false,
             mentioned_addresses: vec![random_address],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.subject".to_string(),
-        );
+        )
+        .unwrap();
 
         let tx_info = create_replica_transaction_info_v2(false);
         let transaction_info = ReplicaTransactionInfoVersions::V0_0_2(&tx_info);
@@ -335,6 +405,404 @@ mod transaction_processing_tests {
         let result = processor.process_transaction(transaction_info, 12345);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_process_transaction_excluded_program_dropped() {
+        let connection_manager = create_test_connection_manager();
+        let filter_config = TransactionFilterConfig {
+            excluded_program_ids: vec![solana_sdk::system_program::id().to_string()],
+            ..TransactionFilterConfig::default()
+        };
+
+        let processor = TransactionProcessor::new(
+            connection_manager.clone(),
+            &filter_config,
+            "test.subject".to_string(),
+        )
+        .unwrap();
+
+        // The test transaction is a system program transfer
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345);
+        assert!(result.is_ok());
+
+        assert_eq!(connection_manager.stats().transactions_filtered(), 1);
+        assert_eq!(connection_manager.stats().messages_queued(), 0);
+    }
+
+    #[test]
+    fn test_process_transaction_selected_by_program_id() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::new(
+            connection_manager.clone(),
+            &TransactionFilterConfig {
+                program_ids: vec![solana_sdk::system_program::id().to_string()],
+                ..TransactionFilterConfig::default()
+            },
+            "test.subject".to_string(),
+        )
+        .unwrap();
+        let tx_info = create_replica_transaction_info_v2(false);
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+            .unwrap();
+
+        assert_eq!(connection_manager.stats().transactions_selected(), 1);
+        assert_eq!(connection_manager.stats().transactions_filtered(), 0);
+
+        // The transfer recipient is mentioned by the transaction but never invoked
+        let recipient = tx_info.transaction.message().account_keys()[1];
+        let processor = TransactionProcessor::new(
+            connection_manager.clone(),
+            &TransactionFilterConfig {
+                program_ids: vec![recipient.to_string()],
+                ..TransactionFilterConfig::default()
+            },
+            "test.subject".to_string(),
+        )
+        .unwrap();
+        let tx_info = create_replica_transaction_info_v1(false);
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_1(&tx_info), 12345)
+            .unwrap();
+
+        assert_eq!(connection_manager.stats().transactions_filtered(), 1);
+    }
+
+    #[test]
+    fn test_process_transaction_address_match_signer_only() {
+        let transaction = create_test_transaction();
+        let meta = create_test_meta();
+        let account_keys = transaction.message().account_keys();
+        let (sender, recipient) = (account_keys[0], account_keys[1]);
+        let tx_info = ReplicaTransactionInfoV2 {
+            signature: transaction.signature(),
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        };
+
+        for (address, selected) in [(sender, 1), (recipient, 0)] {
+            let connection_manager = create_test_connection_manager();
+            let processor = TransactionProcessor::new(
+                connection_manager.clone(),
+                &TransactionFilterConfig {
+                    select_all_transactions: false,
+                    mentioned_addresses: vec![address.to_string()],
+                    address_match: AddressMatch::SignerOnly,
+                    ..TransactionFilterConfig::default()
+                },
+                "test.subject".to_string(),
+            )
+            .unwrap();
+            processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+                .unwrap();
+
+            assert_eq!(connection_manager.stats().transactions_selected(), selected);
+        }
+    }
+
+    #[test]
+    fn test_process_transaction_filtered_by_status() {
+        let transaction = create_test_transaction();
+        let succeeded = create_test_meta();
+        let failed = TransactionStatusMeta {
+            status: Err(solana_sdk::transaction::TransactionError::InsufficientFundsForFee),
+            ..create_test_meta()
+        };
+
+        for (include_failed, only_failed, selected) in [
+            (true, false, [true, true]),
+            (false, false, [true, false]),
+            (true, true, [false, true]),
+        ] {
+            let connection_manager = create_test_connection_manager();
+            let processor = TransactionProcessor::new(
+                connection_manager.clone(),
+                &TransactionFilterConfig {
+                    include_failed,
+                    only_failed,
+                    ..TransactionFilterConfig::default()
+                },
+                "test.subject".to_string(),
+            )
+            .unwrap();
+
+            for (meta, selected) in [&succeeded, &failed].into_iter().zip(selected) {
+                let before = connection_manager.stats().transactions_selected();
+                let tx_info = ReplicaTransactionInfoV2 {
+                    signature: transaction.signature(),
+                    is_vote: false,
+                    transaction: &transaction,
+                    transaction_status_meta: meta,
+                    index: 0,
+                };
+                processor
+                    .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+                    .unwrap();
+
+                assert_eq!(
+                    connection_manager.stats().transactions_selected() > before,
+                    selected,
+                    "include_failed={include_failed}, only_failed={only_failed}, failed={}",
+                    meta.status.is_err()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_transaction_filtered_by_logs() {
+        let transaction = create_test_transaction();
+        let meta = create_test_meta();
+
+        for (log_contains, log_patterns, selected) in [
+            (vec!["invoke [1]"], vec![], true),
+            (vec!["Instruction: Swap"], vec![], false),
+            (vec![], vec![r"^Program \w+ success$"], true),
+            (vec!["Instruction: Swap"], vec![r"invoke \[[2-9]\]"], false),
+        ] {
+            let connection_manager = create_test_connection_manager();
+            let processor = TransactionProcessor::new(
+                connection_manager.clone(),
+                &TransactionFilterConfig {
+                    log_contains: log_contains.iter().map(ToString::to_string).collect(),
+                    log_patterns: log_patterns.iter().map(ToString::to_string).collect(),
+                    ..TransactionFilterConfig::default()
+                },
+                "test.subject".to_string(),
+            )
+            .unwrap();
+            let tx_info = ReplicaTransactionInfoV2 {
+                signature: transaction.signature(),
+                is_vote: false,
+                transaction: &transaction,
+                transaction_status_meta: &meta,
+                index: 0,
+            };
+            processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+                .unwrap();
+
+            assert_eq!(
+                connection_manager.stats().transactions_selected() == 1,
+                selected,
+                "log_contains={log_contains:?}, log_patterns={log_patterns:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_rule_stats() {
+        let transaction = create_test_transaction();
+        let meta = create_test_meta();
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::new(
+            connection_manager.clone(),
+            &TransactionFilterConfig {
+                program_ids: vec![Pubkey::new_unique().to_string()],
+                log_contains: vec!["invoke [1]".to_string()],
+                ..TransactionFilterConfig::default()
+            },
+            "test.subject".to_string(),
+        )
+        .unwrap();
+        let tx_info = ReplicaTransactionInfoV2 {
+            signature: transaction.signature(),
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        };
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+            .unwrap();
+
+        let stats = connection_manager.stats();
+        let addresses = stats.filter_rule_stats("addresses").unwrap();
+        assert_eq!((addresses.matched, addresses.filtered), (1, 0));
+        let programs = stats.filter_rule_stats("programs").unwrap();
+        assert_eq!((programs.matched, programs.filtered), (0, 1));
+        // Rules after the one filtering the transaction out are not reached, and rules
+        // left unconfigured are not counted
+        assert!(stats.filter_rule_stats("logs").is_none());
+        assert!(stats.filter_rule_stats("status").is_none());
+        assert_eq!(stats.to_json()["filters"]["programs"]["filtered"], 1);
+    }
+
+    #[test]
+    fn test_process_transaction_filtered_by_expression() {
+        let transaction = create_test_transaction();
+        let meta = create_test_meta();
+
+        for (expression, selected) in [
+            ("fee >= 5000 && !is_vote", true),
+            ("fee > 5000 || failed", false),
+            ("slot == 12345 && accounts.len() > 0", true),
+        ] {
+            let connection_manager = create_test_connection_manager();
+            let processor = TransactionProcessor::new(
+                connection_manager.clone(),
+                &TransactionFilterConfig {
+                    expression: Some(expression.to_string()),
+                    ..TransactionFilterConfig::default()
+                },
+                "test.subject".to_string(),
+            )
+            .unwrap();
+            let tx_info = ReplicaTransactionInfoV2 {
+                signature: transaction.signature(),
+                is_vote: false,
+                transaction: &transaction,
+                transaction_status_meta: &meta,
+                index: 0,
+            };
+            processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+                .unwrap();
+
+            assert_eq!(
+                connection_manager.stats().transactions_selected() == 1,
+                selected,
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_transaction_sampled_by_signature() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::new(
+            connection_manager.clone(),
+            &TransactionFilterConfig {
+                sample_rate: 4,
+                ..TransactionFilterConfig::default()
+            },
+            "test.subject".to_string(),
+        )
+        .unwrap();
+        let transaction = create_test_transaction();
+        let meta = create_test_meta();
+
+        let signatures: Vec<Signature> = (0..200).map(|_| Signature::new_unique()).collect();
+        for signature in &signatures {
+            let tx_info = ReplicaTransactionInfoV2 {
+                signature,
+                is_vote: false,
+                transaction: &transaction,
+                transaction_status_meta: &meta,
+                index: 0,
+            };
+            processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+                .unwrap();
+        }
+
+        let sampled = signatures
+            .iter()
+            .filter(|signature| transaction_router::is_sampled(signature, 4))
+            .count();
+        assert!(sampled > 0 && sampled < signatures.len());
+        assert_eq!(
+            connection_manager.stats().transactions_selected(),
+            sampled as u64
+        );
+    }
+
+    #[test]
+    fn test_process_transaction_in_publish_window() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::new(
+            connection_manager.clone(),
+            &TransactionFilterConfig {
+                start_slot: Some(100),
+                end_slot: Some(200),
+                ..TransactionFilterConfig::default()
+            },
+            "test.subject".to_string(),
+        )
+        .unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        for slot in [99, 100, 200, 201] {
+            processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), slot)
+                .unwrap();
+        }
+
+        assert_eq!(connection_manager.stats().transactions_selected(), 2);
+        assert_eq!(connection_manager.stats().transactions_filtered(), 2);
+    }
+
+    #[test]
+    fn test_process_transaction_after_end_time_dropped() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::new(
+            connection_manager.clone(),
+            &TransactionFilterConfig {
+                end_time: Some("2020-01-01T00:00:00Z".parse().unwrap()),
+                ..TransactionFilterConfig::default()
+            },
+            "test.subject".to_string(),
+        )
+        .unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 100)
+            .unwrap();
+
+        assert_eq!(connection_manager.stats().transactions_selected(), 0);
+    }
+
+    #[test]
+    fn test_process_transaction_selected_by_inner_instruction_program() {
+        let transaction = create_test_transaction();
+        let invoked_program = transaction.message().account_keys()[1];
+        let meta = TransactionStatusMeta {
+            inner_instructions: Some(vec![InnerInstructions {
+                index: 0,
+                instructions: vec![InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(1, vec![], vec![0]),
+                    stack_height: Some(2),
+                }],
+            }]),
+            ..create_test_meta()
+        };
+        let tx_info = ReplicaTransactionInfoV2 {
+            signature: transaction.signature(),
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        };
+
+        for (match_inner_instructions, selected) in [(false, 0), (true, 1)] {
+            let connection_manager = create_test_connection_manager();
+            let processor = TransactionProcessor::new(
+                connection_manager.clone(),
+                &TransactionFilterConfig {
+                    program_ids: vec![invoked_program.to_string()],
+                    match_inner_instructions,
+                    ..TransactionFilterConfig::default()
+                },
+                "test.subject".to_string(),
+            )
+            .unwrap();
+            processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 12345)
+                .unwrap();
+
+            assert_eq!(connection_manager.stats().transactions_selected(), selected);
+            assert_eq!(
+                connection_manager.stats().transactions_filtered(),
+                1 - selected
+            );
+        }
+    }
 }
 
 #[cfg(test)]
@@ -357,8 +825,7 @@ mod error_handling_tests {
 
         // Test 2: Invalid port - may succeed or fail depending on host resolution
         let result = ConnectionManager::new("nats://127.0.0.1:99999", 1, 1);
-        if result.is_ok() {
-            let mut manager = result.unwrap();
+        if let Ok(mut manager) = result {
             manager.shutdown();
         }
         // Both success and failure are valid outcomes for this test case
@@ -381,6 +848,24 @@ mod error_handling_tests {
         let display_string = format!("{filtering_error}");
         assert!(display_string.contains("Test filtering error"));
     }
+
+    #[test]
+    fn test_invalid_selector_address_returns_error() {
+        let result = TransactionProcessor::new(
+            create_test_connection_manager(),
+            &TransactionFilterConfig {
+                select_all_transactions: false,
+                mentioned_addresses: vec!["not-base58-0OIl".to_string()],
+                ..TransactionFilterConfig::default()
+            },
+            "test.subject".to_string(),
+        );
+
+        let Err(ProcessingError::Selector(e)) = result else {
+            panic!("Expected a selector error");
+        };
+        assert!(e.to_string().contains("not-base58-0OIl"));
+    }
 }
 
 #[cfg(test)]
@@ -395,7 +880,8 @@ mod integration_tests {
             connection_manager,
             &filter_config,
             "integration.test".to_string(),
-        );
+        )
+        .unwrap();
 
         // Process multiple transactions
         let tx_v2 = create_replica_transaction_info_v2(false);
@@ -414,11 +900,14 @@ mod integration_tests {
     fn test_concurrent_processing() {
         let connection_manager = create_test_connection_manager();
         let filter_config = TransactionFilterConfig::default();
-        let processor = Arc::new(TransactionProcessor::new(
-            connection_manager,
-            &filter_config,
-            "concurrent.test".to_string(),
-        ));
+        let processor = Arc::new(
+            TransactionProcessor::new(
+                connection_manager,
+                &filter_config,
+                "concurrent.test".to_string(),
+            )
+            .unwrap(),
+        );
 
         let num_threads = 5;
         let transactions_per_thread = 10;
@@ -457,7 +946,8 @@ mod integration_tests {
             connection_manager.clone(),
             &TransactionFilterConfig::default(),
             "test1".to_string(),
-        );
+        )
+        .unwrap();
         assert!(processor1.is_enabled());
 
         // Test enabled with vote-only config
@@ -465,12 +955,14 @@ mod integration_tests {
             select_all_transactions: false,
             select_vote_transactions: true,
             mentioned_addresses: vec![],
+            ..TransactionFilterConfig::default()
         };
         let processor2 = TransactionProcessor::new(
             connection_manager.clone(),
             &vote_config,
             "test2".to_string(),
-        );
+        )
+        .unwrap();
         assert!(processor2.is_enabled());
 
         // Test enabled with specific addresses
@@ -478,9 +970,11 @@ mod integration_tests {
             select_all_transactions: false,
             select_vote_transactions: false,
             mentioned_addresses: vec![Pubkey::new_unique().to_string()],
+            ..TransactionFilterConfig::default()
         };
         let processor3 =
-            TransactionProcessor::new(connection_manager, &address_config, "test3".to_string());
+            TransactionProcessor::new(connection_manager, &address_config, "test3".to_string())
+                .unwrap();
         assert!(processor3.is_enabled());
     }
 
@@ -491,13 +985,15 @@ mod integration_tests {
             select_all_transactions: true,
             select_vote_transactions: true,
             mentioned_addresses: vec![],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.transactions".to_string(),
-        );
+        )
+        .unwrap();
 
         // Test multiple transaction scenarios
         let v1_non_vote = create_replica_transaction_info_v1(false);
@@ -521,13 +1017,15 @@ mod integration_tests {
             select_all_transactions: false,
             select_vote_transactions: false,
             mentioned_addresses: vec![],
+            ..TransactionFilterConfig::default()
         };
 
         let processor = TransactionProcessor::new(
             connection_manager,
             &filter_config,
             "test.transactions".to_string(),
-        );
+        )
+        .unwrap();
 
         let vote_transaction = create_replica_transaction_info_v1(true);
         let result = processor.process_transaction(
@@ -538,3 +1036,1202 @@ mod integration_tests {
         assert!(result.is_ok());
     }
 }
+
+#[cfg(test)]
+mod secondary_cluster_tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_queued_on_both_clusters() {
+        let primary = create_test_connection_manager();
+        let secondary = create_test_connection_manager();
+
+        let processor = TransactionProcessor::new(
+            primary.clone(),
+            &TransactionFilterConfig::default(),
+            "test.subject".to_string(),
+        )
+        .unwrap()
+        .with_secondary_connection(secondary.clone());
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 100);
+        assert!(result.is_ok());
+
+        assert_eq!(primary.stats().messages_queued(), 1);
+        assert_eq!(secondary.stats().messages_queued(), 1);
+    }
+
+    fn create_open_circuit_connection_manager() -> Arc<ConnectionManager> {
+        let circuit_breaker = CircuitBreaker::new(&CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 1,
+            cooldown_secs: 60,
+        })
+        .map(Arc::new)
+        .unwrap();
+        circuit_breaker.record_failure();
+
+        Arc::new(
+            ConnectionManager::with_options(
+                "nats://127.0.0.1:9999",
+                ConnectionOptions {
+                    max_retries: 100,
+                    timeout_secs: 10,
+                    circuit_breaker: Some(circuit_breaker),
+                    ..ConnectionOptions::default()
+                },
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_secondary_published_while_primary_circuit_open() {
+        let primary = create_open_circuit_connection_manager();
+        let secondary = create_test_connection_manager();
+
+        let processor = TransactionProcessor::new(
+            primary.clone(),
+            &TransactionFilterConfig::default(),
+            "test.subject".to_string(),
+        )
+        .unwrap()
+        .with_secondary_connection(secondary.clone());
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 100);
+        assert!(result.is_ok());
+
+        assert_eq!(primary.stats().messages_queued(), 0);
+        assert_eq!(primary.stats().messages_dropped(), 1);
+        assert_eq!(secondary.stats().messages_queued(), 1);
+    }
+
+    #[test]
+    fn test_dropped_while_every_circuit_open() {
+        let primary = create_open_circuit_connection_manager();
+        let secondary = create_open_circuit_connection_manager();
+
+        let processor = TransactionProcessor::new(
+            primary.clone(),
+            &TransactionFilterConfig::default(),
+            "test.subject".to_string(),
+        )
+        .unwrap()
+        .with_secondary_connection(secondary.clone());
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 100);
+        assert!(result.is_ok());
+
+        for cluster in [&primary, &secondary] {
+            assert_eq!(cluster.stats().messages_queued(), 0);
+            assert_eq!(cluster.stats().messages_dropped(), 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod block_assembly_tests {
+    use super::*;
+
+    fn create_block_assembly_processor() -> TransactionProcessor {
+        let config = NatsPluginConfig {
+            block_assembly: BlockAssemblyConfig {
+                enabled: true,
+                ..BlockAssemblyConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+
+        TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap()
+    }
+
+    #[test]
+    fn test_transactions_buffered_until_block_metadata() {
+        let processor = create_block_assembly_processor();
+        let block_assembler = processor
+            .block_assembler()
+            .expect("Block assembly should be enabled");
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 500);
+        assert!(result.is_ok());
+        assert_eq!(block_assembler.buffered_slots(), 1);
+
+        let block_info = ReplicaBlockInfo {
+            slot: 500,
+            blockhash: "test_blockhash",
+            rewards: &[],
+            block_time: Some(1_700_000_000),
+            block_height: Some(400),
+        };
+        let result =
+            processor.process_block_metadata(ReplicaBlockInfoVersions::V0_0_1(&block_info));
+        assert!(result.is_ok());
+        assert_eq!(block_assembler.buffered_slots(), 0);
+    }
+
+    #[test]
+    fn test_block_metadata_ignored_without_block_assembly() {
+        let processor = TransactionProcessor::new(
+            create_test_connection_manager(),
+            &TransactionFilterConfig::default(),
+            "test.subject".to_string(),
+        )
+        .unwrap();
+        assert!(processor.block_assembler().is_none());
+
+        let block_info = ReplicaBlockInfo {
+            slot: 500,
+            blockhash: "test_blockhash",
+            rewards: &[],
+            block_time: None,
+            block_height: None,
+        };
+        let result =
+            processor.process_block_metadata(ReplicaBlockInfoVersions::V0_0_1(&block_info));
+        assert!(result.is_ok());
+    }
+}
+
+#[cfg(test)]
+mod routing_tests {
+    use super::*;
+
+    fn create_route(name: &str, subject: &str, filter: TransactionFilterConfig) -> RouteConfig {
+        RouteConfig {
+            name: name.to_string(),
+            subject: subject.to_string(),
+            filter,
+        }
+    }
+
+    #[test]
+    fn test_transaction_published_to_every_matching_route() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            routes: vec![
+                create_route("all", "team.all", TransactionFilterConfig::default()),
+                create_route(
+                    "failures",
+                    "team.failures",
+                    TransactionFilterConfig {
+                        only_failed: true,
+                        ..TransactionFilterConfig::default()
+                    },
+                ),
+                create_route(
+                    "elsewhere",
+                    "team.elsewhere",
+                    TransactionFilterConfig {
+                        program_ids: vec![Pubkey::new_unique().to_string()],
+                        ..TransactionFilterConfig::default()
+                    },
+                ),
+            ],
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+        assert_eq!(processor.transaction_router().unwrap().routes().len(), 3);
+
+        let transaction = create_test_transaction();
+        let succeeded = create_test_meta();
+        let failed = TransactionStatusMeta {
+            status: Err(solana_sdk::transaction::TransactionError::InsufficientFundsForFee),
+            ..create_test_meta()
+        };
+        for meta in [&succeeded, &failed] {
+            let tx_info = ReplicaTransactionInfoV2 {
+                signature: transaction.signature(),
+                is_vote: false,
+                transaction: &transaction,
+                transaction_status_meta: meta,
+                index: 0,
+            };
+            processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 100)
+                .unwrap();
+        }
+
+        // One message for the successful transaction, two for the failed one
+        assert_eq!(connection_manager.stats().transactions_selected(), 2);
+        assert_eq!(connection_manager.stats().messages_queued(), 3);
+
+        let stats = connection_manager.stats();
+        let route_stats = |route| stats.route_stats(route).unwrap();
+        assert_eq!(
+            (route_stats("all").matched, route_stats("all").filtered),
+            (2, 0)
+        );
+        assert_eq!(
+            (
+                route_stats("failures").matched,
+                route_stats("failures").filtered
+            ),
+            (1, 1)
+        );
+        assert_eq!(
+            (
+                route_stats("elsewhere").matched,
+                route_stats("elsewhere").filtered
+            ),
+            (0, 2)
+        );
+    }
+
+    #[test]
+    fn test_transaction_matching_no_route_filtered() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            routes: vec![create_route(
+                "failures",
+                "team.failures",
+                TransactionFilterConfig {
+                    only_failed: true,
+                    ..TransactionFilterConfig::default()
+                },
+            )],
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        let tx_info = create_replica_transaction_info_v1(false);
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_1(&tx_info), 100)
+            .unwrap();
+
+        assert_eq!(connection_manager.stats().transactions_filtered(), 1);
+        assert_eq!(connection_manager.stats().messages_queued(), 0);
+    }
+}
+
+#[cfg(test)]
+mod batching_tests {
+    use super::*;
+
+    #[test]
+    fn test_transactions_batched_until_block_metadata() {
+        let config = NatsPluginConfig {
+            batching: BatchingConfig {
+                enabled: true,
+                ..BatchingConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+        let transaction_batcher = processor
+            .transaction_batcher()
+            .expect("Batching should be enabled");
+
+        for _ in 0..3 {
+            let tx_info = create_replica_transaction_info_v2(false);
+            let result = processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 500);
+            assert!(result.is_ok());
+        }
+        assert_eq!(transaction_batcher.pending(), 3);
+
+        // A status update of the same slot keeps its batch open
+        let result = processor.process_slot_status(500, Some(499), &SlotStatus::Processed);
+        assert!(result.is_ok());
+        assert_eq!(transaction_batcher.pending(), 3);
+
+        let block_info = ReplicaBlockInfo {
+            slot: 500,
+            blockhash: "test_blockhash",
+            rewards: &[],
+            block_time: None,
+            block_height: None,
+        };
+        let result =
+            processor.process_block_metadata(ReplicaBlockInfoVersions::V0_0_1(&block_info));
+        assert!(result.is_ok());
+        assert_eq!(transaction_batcher.pending(), 0);
+    }
+
+    #[test]
+    fn test_batches_flushed_on_later_slot_and_shutdown() {
+        let config = NatsPluginConfig {
+            batching: BatchingConfig {
+                enabled: true,
+                ..BatchingConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+        let transaction_batcher = processor.transaction_batcher().unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 500);
+        assert!(result.is_ok());
+        let result = processor.process_slot_status(501, Some(500), &SlotStatus::Processed);
+        assert!(result.is_ok());
+        assert_eq!(transaction_batcher.pending(), 0);
+
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 501);
+        assert!(result.is_ok());
+        assert_eq!(transaction_batcher.pending(), 1);
+        assert!(processor.flush_batches().is_ok());
+        assert_eq!(transaction_batcher.pending(), 0);
+    }
+}
+
+#[cfg(test)]
+mod vote_summary_tests {
+    use super::*;
+
+    fn create_vote_summary_processor(
+        connection_manager: Arc<ConnectionManager>,
+        filter: TransactionFilterConfig,
+    ) -> TransactionProcessor {
+        let config = NatsPluginConfig {
+            filter,
+            vote_summary: VoteSummaryConfig {
+                enabled: true,
+                ..VoteSummaryConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+
+        TransactionProcessor::from_config(connection_manager, &config).unwrap()
+    }
+
+    #[test]
+    fn test_votes_summarized_until_block_metadata() {
+        let connection_manager = create_test_connection_manager();
+        let processor = create_vote_summary_processor(
+            connection_manager.clone(),
+            TransactionFilterConfig::default(),
+        );
+        let vote_summarizer = processor
+            .vote_summarizer()
+            .expect("Vote summaries should be enabled");
+        let stats = connection_manager.stats();
+
+        let tx_info = create_replica_transaction_info_v2(true);
+        for _ in 0..3 {
+            let result = processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 600);
+            assert!(result.is_ok());
+        }
+        assert_eq!(vote_summarizer.buffered_slots(), 1);
+        assert_eq!(stats.transactions_selected(), 0);
+
+        let block_info = ReplicaBlockInfo {
+            slot: 600,
+            blockhash: "test_blockhash",
+            rewards: &[],
+            block_time: None,
+            block_height: None,
+        };
+        let result =
+            processor.process_block_metadata(ReplicaBlockInfoVersions::V0_0_1(&block_info));
+        assert!(result.is_ok());
+        assert_eq!(vote_summarizer.buffered_slots(), 0);
+    }
+
+    #[test]
+    fn test_non_votes_published_individually() {
+        let connection_manager = create_test_connection_manager();
+        let processor = create_vote_summary_processor(
+            connection_manager.clone(),
+            TransactionFilterConfig::default(),
+        );
+        let stats = connection_manager.stats();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 600);
+        assert!(result.is_ok());
+
+        assert_eq!(processor.vote_summarizer().unwrap().buffered_slots(), 0);
+        assert_eq!(stats.transactions_selected(), 1);
+    }
+
+    #[test]
+    fn test_votes_excluded_by_filter_not_summarized() {
+        let connection_manager = create_test_connection_manager();
+        let tx_info = create_replica_transaction_info_v2(true);
+        let fee_payer = tx_info.transaction.message().fee_payer();
+
+        for (vote_address, summarized) in [(Pubkey::new_unique(), 0), (*fee_payer, 1)] {
+            let processor = create_vote_summary_processor(
+                connection_manager.clone(),
+                TransactionFilterConfig {
+                    select_all_transactions: false,
+                    vote_addresses: vec![vote_address.to_string()],
+                    ..TransactionFilterConfig::default()
+                },
+            );
+            let result = processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 600);
+            assert!(result.is_ok());
+            assert_eq!(
+                processor.vote_summarizer().unwrap().buffered_slots(),
+                summarized
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod address_fanout_tests {
+    use super::*;
+
+    fn create_address_fanout_processor(
+        connection_manager: Arc<ConnectionManager>,
+        writable_only: bool,
+    ) -> TransactionProcessor {
+        let config = NatsPluginConfig {
+            address_fanout: AddressFanoutConfig {
+                enabled: true,
+                writable_only,
+                ..AddressFanoutConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+
+        TransactionProcessor::from_config(connection_manager, &config).unwrap()
+    }
+
+    #[test]
+    fn test_notification_per_mentioned_address() {
+        let connection_manager = create_test_connection_manager();
+        let processor = create_address_fanout_processor(connection_manager.clone(), false);
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 700);
+        assert!(result.is_ok());
+
+        // Sender, recipient and the system program, plus the transaction itself
+        assert_eq!(connection_manager.stats().messages_queued(), 4);
+    }
+
+    #[test]
+    fn test_notification_per_writable_address() {
+        let connection_manager = create_test_connection_manager();
+        let processor = create_address_fanout_processor(connection_manager.clone(), true);
+
+        let tx_info = create_replica_transaction_info_v1(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_1(&tx_info), 700);
+        assert!(result.is_ok());
+
+        // Sender and recipient, plus the transaction itself
+        assert_eq!(connection_manager.stats().messages_queued(), 3);
+    }
+
+    #[test]
+    fn test_notifications_wait_for_assembled_block() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            address_fanout: AddressFanoutConfig {
+                enabled: true,
+                ..AddressFanoutConfig::default()
+            },
+            block_assembly: BlockAssemblyConfig {
+                enabled: true,
+                ..BlockAssemblyConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        let result =
+            processor.process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 700);
+        assert!(result.is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 0);
+
+        let block_info = ReplicaBlockInfo {
+            slot: 700,
+            blockhash: "test_blockhash",
+            rewards: &[],
+            block_time: None,
+            block_height: None,
+        };
+        let result =
+            processor.process_block_metadata(ReplicaBlockInfoVersions::V0_0_1(&block_info));
+        assert!(result.is_ok());
+
+        // The block, then sender, recipient and the system program
+        assert_eq!(connection_manager.stats().messages_queued(), 4);
+    }
+}
+
+#[cfg(test)]
+mod message_ttl_tests {
+    use super::*;
+
+    #[test]
+    fn test_message_ttl_matches_subjects() {
+        let config = NatsPluginConfig {
+            message_ttls: vec![
+                MessageTtlConfig {
+                    subject: "solana.votes".to_string(),
+                    ttl_secs: 30,
+                },
+                MessageTtlConfig {
+                    subject: "solana.address.*".to_string(),
+                    ttl_secs: 60,
+                },
+                MessageTtlConfig {
+                    subject: "archive.>".to_string(),
+                    ttl_secs: 86_400,
+                },
+            ],
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+
+        assert_eq!(processor.message_ttl("solana.votes"), Some(30));
+        assert_eq!(processor.message_ttl("solana.address.abc"), Some(60));
+        assert_eq!(processor.message_ttl("archive.blocks.1"), Some(86_400));
+        assert_eq!(processor.message_ttl("solana.address"), None);
+        assert_eq!(processor.message_ttl("solana.address.abc.def"), None);
+        assert_eq!(processor.message_ttl("solana.transactions"), None);
+        assert_eq!(processor.message_ttl("archive"), None);
+    }
+}
+
+#[cfg(test)]
+mod control_message_tests {
+    use super::*;
+
+    #[test]
+    fn test_startup_complete_reports_highest_slot() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(
+            connection_manager.clone(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(processor.highest_slot(), None);
+
+        processor.record_slot(10);
+        processor.record_slot(8);
+        assert_eq!(processor.highest_slot(), Some(10));
+
+        assert!(processor.publish_startup_complete().is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 1);
+    }
+
+    #[test]
+    fn test_plugin_stats_queued() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(
+            connection_manager.clone(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap();
+
+        assert!(processor.publish_plugin_stats().is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 1);
+        assert_eq!(connection_manager.queue_depth(), 1);
+    }
+
+    #[test]
+    fn test_plugin_stats_queued_on_both_clusters() {
+        let connection_manager = create_test_connection_manager();
+        let secondary = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(
+            connection_manager.clone(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap()
+        .with_secondary_connection(secondary.clone());
+
+        assert!(processor.publish_plugin_stats().is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 1);
+        assert_eq!(secondary.stats().messages_queued(), 1);
+    }
+}
+
+mod filter_reload_tests {
+    use super::*;
+
+    #[test]
+    fn test_reloaded_filter_and_subject_apply_to_next_transaction() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(
+            connection_manager.clone(),
+            &NatsPluginConfig {
+                filter: TransactionFilterConfig {
+                    select_all_transactions: false,
+                    mentioned_addresses: vec![Pubkey::new_unique().to_string()],
+                    ..TransactionFilterConfig::default()
+                },
+                ..NatsPluginConfig::default()
+            },
+        )
+        .unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 100)
+            .unwrap();
+        assert_eq!(connection_manager.stats().transactions_selected(), 0);
+
+        processor
+            .reload_filters(&NatsPluginConfig {
+                subject: "solana.reloaded".to_string(),
+                ..NatsPluginConfig::default()
+            })
+            .unwrap();
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 101)
+            .unwrap();
+
+        assert_eq!(connection_manager.stats().transactions_selected(), 1);
+        assert!(processor.transaction_selector().select_all_transactions);
+        // The connection and its queue are kept
+        assert_eq!(connection_manager.queue_depth(), 1);
+    }
+
+    #[test]
+    fn test_invalid_reloaded_filter_keeps_current_filters() {
+        let processor = TransactionProcessor::from_config(
+            create_test_connection_manager(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap();
+
+        let result = processor.reload_filters(&NatsPluginConfig {
+            filter: TransactionFilterConfig {
+                select_all_transactions: false,
+                mentioned_addresses: vec!["not-base58-0OIl".to_string()],
+                ..TransactionFilterConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        });
+
+        assert!(matches!(result, Err(ProcessingError::Selector(_))));
+        assert!(processor.transaction_selector().select_all_transactions);
+    }
+}
+
+mod filter_control_tests {
+    use {
+        super::*,
+        solana_geyser_plugin_nats::{
+            config::FilterControlConfig,
+            filter_control::FilterControl,
+            message_signer::{MESSAGE_SIGNATURE_HEADER, MESSAGE_SIGNER_HEADER},
+        },
+        solana_sdk::signature::{Keypair, Signer},
+    };
+
+    #[test]
+    fn test_filter_update_applies_to_next_transaction() {
+        let signer = Keypair::new();
+        let config = NatsPluginConfig {
+            filter: TransactionFilterConfig {
+                select_all_transactions: false,
+                mentioned_addresses: vec![Pubkey::new_unique().to_string()],
+                ..TransactionFilterConfig::default()
+            },
+            filter_control: FilterControlConfig {
+                enabled: true,
+                authorized_signers: vec![signer.pubkey().to_string()],
+                ..FilterControlConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let filter_control = Arc::new(FilterControl::new(&config).unwrap().unwrap());
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(connection_manager.clone(), &config)
+            .unwrap()
+            .with_filter_control(filter_control.clone());
+
+        let transaction = create_test_transaction();
+        let meta = create_test_meta();
+        let tx_info = ReplicaTransactionInfoV2 {
+            signature: transaction.signature(),
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        };
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 100)
+            .unwrap();
+        assert_eq!(connection_manager.stats().transactions_selected(), 0);
+
+        let fee_payer = transaction.message().fee_payer();
+        let version = filter_control.version() + 1;
+        let payload =
+            format!(r#"{{"version": {version}, "mentioned_addresses": ["{fee_payer}"]}}"#)
+                .into_bytes();
+        let headers = vec![
+            (
+                MESSAGE_SIGNATURE_HEADER.to_string(),
+                signer.sign_message(&payload).to_string(),
+            ),
+            (
+                MESSAGE_SIGNER_HEADER.to_string(),
+                signer.pubkey().to_string(),
+            ),
+        ];
+        filter_control.apply_update(&headers, &payload).unwrap();
+
+        processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 101)
+            .unwrap();
+        assert_eq!(connection_manager.stats().transactions_selected(), 1);
+        assert!(processor
+            .transaction_selector()
+            .mentioned_addresses
+            .contains(fee_payer.as_ref()));
+    }
+}
+
+mod deduplication_tests {
+    use super::*;
+
+    #[test]
+    fn test_replayed_transaction_published_once() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            deduplication: DeduplicationConfig {
+                enabled: true,
+                ..DeduplicationConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        for slot in [500, 500, 501, 500] {
+            assert!(processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), slot)
+                .is_ok());
+        }
+
+        // Slot 501 is another fork, so only the replays in slot 500 are skipped
+        assert_eq!(connection_manager.stats().messages_queued(), 2);
+        assert_eq!(connection_manager.stats().transactions_deduplicated(), 2);
+        assert_eq!(
+            processor
+                .signature_deduplicator()
+                .unwrap()
+                .tracked_signatures(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_replayed_transaction_published_again_when_disabled() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(
+            connection_manager.clone(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap();
+        assert!(processor.signature_deduplicator().is_none());
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        for _ in 0..2 {
+            assert!(processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 500)
+                .is_ok());
+        }
+
+        assert_eq!(connection_manager.stats().messages_queued(), 2);
+        assert_eq!(connection_manager.stats().transactions_deduplicated(), 0);
+    }
+}
+
+mod address_rate_limit_tests {
+    use super::*;
+
+    #[test]
+    fn test_transactions_over_the_address_limit_dropped() {
+        let connection_manager = create_test_connection_manager();
+        let tx_info = create_replica_transaction_info_v2(false);
+        let fee_payer = tx_info.transaction.message().fee_payer();
+        let config = NatsPluginConfig {
+            filter: TransactionFilterConfig {
+                select_all_transactions: false,
+                mentioned_addresses: vec![fee_payer.to_string()],
+                ..TransactionFilterConfig::default()
+            },
+            address_rate_limit: AddressRateLimitConfig {
+                messages_per_sec: 2,
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        for slot in 500..505 {
+            assert!(processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), slot)
+                .is_ok());
+        }
+
+        assert_eq!(connection_manager.stats().transactions_selected(), 2);
+        assert_eq!(connection_manager.stats().transactions_rate_limited(), 3);
+        assert_eq!(
+            processor
+                .address_rate_limiter()
+                .unwrap()
+                .tracked_addresses(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_transactions_not_rate_limited_by_default() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(
+            connection_manager.clone(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap();
+        assert!(processor.address_rate_limiter().is_none());
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        for slot in 500..505 {
+            assert!(processor
+                .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), slot)
+                .is_ok());
+        }
+
+        assert_eq!(connection_manager.stats().transactions_selected(), 5);
+        assert_eq!(connection_manager.stats().transactions_rate_limited(), 0);
+    }
+}
+
+mod slot_drop_tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_slot_publishes_slot_dropped() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            slot_drop: SlotDropConfig {
+                enabled: true,
+                ..SlotDropConfig::default()
+            },
+            block_assembly: BlockAssemblyConfig {
+                enabled: true,
+                ..BlockAssemblyConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        assert!(processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 500)
+            .is_ok());
+        assert_eq!(processor.slot_tracker().unwrap().tracked_slots(), 1);
+
+        assert!(processor
+            .process_slot_status(500, Some(499), &SlotStatus::Dead("failed".to_string()))
+            .is_ok());
+        assert_eq!(processor.slot_tracker().unwrap().tracked_slots(), 0);
+        assert_eq!(processor.block_assembler().unwrap().buffered_slots(), 0);
+        assert_eq!(connection_manager.stats().messages_queued(), 1);
+    }
+
+    #[test]
+    fn test_slot_status_ignored_when_disabled() {
+        let connection_manager = create_test_connection_manager();
+        let processor = TransactionProcessor::from_config(
+            connection_manager.clone(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap();
+
+        assert!(processor.slot_tracker().is_none());
+        assert!(processor
+            .process_slot_status(500, Some(499), &SlotStatus::Dead("failed".to_string()))
+            .is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 0);
+    }
+}
+
+mod subject_routing_tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_status_published_to_slots_subject() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            subjects: SubjectsConfig {
+                slots: Some("solana.slots".to_string()),
+                ..SubjectsConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        assert!(processor
+            .process_slot_status(500, Some(499), &SlotStatus::Confirmed)
+            .is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 1);
+    }
+
+    #[test]
+    fn test_vote_transactions_routed_separately() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            subjects: SubjectsConfig {
+                vote_transactions: Some("solana.votes.raw".to_string()),
+                ..SubjectsConfig::default()
+            },
+            filter: TransactionFilterConfig {
+                select_all_transactions: true,
+                select_vote_transactions: true,
+                ..TransactionFilterConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        let vote_info = create_replica_transaction_info_v2(true);
+        let tx_info = create_replica_transaction_info_v2(false);
+        assert!(processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&vote_info), 500)
+            .is_ok());
+        assert!(processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 500)
+            .is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 2);
+    }
+}
+
+mod rewards_tests {
+    use super::*;
+
+    fn create_reward(pubkey: &Pubkey, reward_type: RewardType) -> Reward {
+        Reward {
+            pubkey: pubkey.to_string(),
+            lamports: 5000,
+            post_balance: 1_000_000,
+            reward_type: Some(reward_type),
+            commission: None,
+        }
+    }
+
+    fn publish_block_rewards(rewards_config: RewardsConfig, rewards: &[Reward]) -> u64 {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            rewards: rewards_config,
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        let block_info = ReplicaBlockInfo {
+            slot: 700,
+            blockhash: "test_blockhash",
+            rewards,
+            block_time: None,
+            block_height: None,
+        };
+        assert!(processor
+            .process_block_metadata(ReplicaBlockInfoVersions::V0_0_1(&block_info))
+            .is_ok());
+
+        connection_manager.stats().messages_queued()
+    }
+
+    #[test]
+    fn test_rewards_published_when_enabled() {
+        let rewards = [create_reward(&Pubkey::new_unique(), RewardType::Fee)];
+
+        let enabled = RewardsConfig {
+            enabled: true,
+            ..RewardsConfig::default()
+        };
+        assert_eq!(publish_block_rewards(enabled, &rewards), 1);
+        assert_eq!(publish_block_rewards(RewardsConfig::default(), &rewards), 0);
+    }
+
+    #[test]
+    fn test_rewards_filtered_by_address_and_type() {
+        let validator = Pubkey::new_unique();
+        let rewards = [
+            create_reward(&validator, RewardType::Voting),
+            create_reward(&Pubkey::new_unique(), RewardType::Fee),
+        ];
+
+        let by_address = RewardsConfig {
+            enabled: true,
+            addresses: vec![validator.to_string()],
+            ..RewardsConfig::default()
+        };
+        assert_eq!(publish_block_rewards(by_address, &rewards), 1);
+
+        let by_type = RewardsConfig {
+            enabled: true,
+            reward_types: vec!["Staking".to_string()],
+            ..RewardsConfig::default()
+        };
+        assert_eq!(publish_block_rewards(by_type, &rewards), 0);
+
+        let unmatched_address = RewardsConfig {
+            enabled: true,
+            addresses: vec![Pubkey::new_unique().to_string()],
+            ..RewardsConfig::default()
+        };
+        assert_eq!(publish_block_rewards(unmatched_address, &rewards), 0);
+    }
+}
+
+mod vote_event_tests {
+    use super::*;
+
+    #[test]
+    fn test_undecodable_vote_published_in_full() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            decode_votes: true,
+            filter: TransactionFilterConfig {
+                select_all_transactions: true,
+                select_vote_transactions: true,
+                ..TransactionFilterConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        // Flagged as a vote, but carries a transfer instruction
+        let vote_info = create_replica_transaction_info_v2(true);
+        assert!(processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&vote_info), 500)
+            .is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 1);
+    }
+}
+
+mod commitment_update_tests {
+    use super::*;
+
+    #[test]
+    fn test_commitment_updates_published() {
+        let connection_manager = create_test_connection_manager();
+        let config = NatsPluginConfig {
+            commitment_updates: CommitmentUpdatesConfig {
+                enabled: true,
+                ..CommitmentUpdatesConfig::default()
+            },
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(connection_manager.clone(), &config).unwrap();
+
+        let tx_info = create_replica_transaction_info_v2(false);
+        assert!(processor
+            .process_transaction(ReplicaTransactionInfoVersions::V0_0_2(&tx_info), 500)
+            .is_ok());
+        assert_eq!(connection_manager.stats().messages_queued(), 1);
+
+        for status in [
+            SlotStatus::Processed,
+            SlotStatus::Confirmed,
+            SlotStatus::Confirmed,
+            SlotStatus::Rooted,
+        ] {
+            assert!(processor.process_slot_status(500, None, &status).is_ok());
+        }
+
+        // The transaction, then one update per commitment level
+        assert_eq!(connection_manager.stats().messages_queued(), 3);
+        assert_eq!(processor.commitment_tracker().unwrap().tracked_slots(), 0);
+    }
+}
+
+mod metadata_header_tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_headers_when_enabled() {
+        let signature = Signature::new_unique();
+        let config = NatsPluginConfig {
+            metadata_headers: true,
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+
+        let headers = processor.transaction_headers(500, &signature, true);
+        assert_eq!(
+            headers,
+            vec![
+                ("Content-Type", "application/json".to_string()),
+                ("Solana-Slot", "500".to_string()),
+                ("Solana-Signature", signature.to_string()),
+                ("Solana-Is-Vote", "true".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_type_header_follows_format() {
+        let config = NatsPluginConfig {
+            metadata_headers: true,
+            format: PayloadFormat::Msgpack,
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+
+        let headers = processor.transaction_headers(500, &Signature::new_unique(), false);
+        assert_eq!(
+            headers[0],
+            ("Content-Type", "application/msgpack".to_string())
+        );
+    }
+
+    #[test]
+    fn test_no_transaction_headers_by_default() {
+        let processor = TransactionProcessor::from_config(
+            create_test_connection_manager(),
+            &NatsPluginConfig::default(),
+        )
+        .unwrap();
+
+        assert!(processor
+            .transaction_headers(500, &Signature::new_unique(), false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_message_id_header_when_enabled() {
+        let signature = Signature::new_unique();
+        let config = NatsPluginConfig {
+            message_ids: true,
+            ..NatsPluginConfig::default()
+        };
+        let processor =
+            TransactionProcessor::from_config(create_test_connection_manager(), &config).unwrap();
+
+        assert_eq!(
+            processor.transaction_headers(500, &signature, false),
+            vec![("Nats-Msg-Id", format!("{signature}:500"))]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use {
+    solana_geyser_plugin_nats::{rate_limiter::RateLimiter, RateLimitConfig},
+    std::time::{Duration, Instant},
+};
+
+fn limiter(messages_per_sec: u64, bytes_per_sec: u64) -> Option<RateLimiter> {
+    RateLimiter::new(&RateLimitConfig {
+        messages_per_sec,
+        bytes_per_sec,
+    })
+}
+
+#[test]
+fn test_unlimited_by_default() {
+    assert!(RateLimiter::new(&RateLimitConfig::default()).is_none());
+}
+
+#[test]
+fn test_message_rate_limited_after_burst() {
+    let mut limiter = limiter(10, 0).unwrap();
+    let now = Instant::now();
+
+    for _ in 0..10 {
+        assert_eq!(limiter.acquire(100, now), Duration::ZERO);
+    }
+    let delay = limiter.acquire(100, now);
+    assert!(delay > Duration::from_millis(90) && delay <= Duration::from_millis(100));
+
+    // A second later the bucket is full again
+    let later = now + Duration::from_secs(1);
+    assert_eq!(limiter.acquire(100, later), Duration::ZERO);
+}
+
+#[test]
+fn test_byte_rate_limited() {
+    let mut limiter = limiter(0, 1000).unwrap();
+    let now = Instant::now();
+
+    assert_eq!(limiter.acquire(1000, now), Duration::ZERO);
+    let delay = limiter.acquire(500, now);
+    assert!(delay > Duration::from_millis(490) && delay <= Duration::from_millis(500));
+}
+
+#[test]
+fn test_message_larger_than_burst_delayed() {
+    let mut limiter = limiter(0, 1000).unwrap();
+    let now = Instant::now();
+
+    let delay = limiter.acquire(3000, now);
+    assert!(delay > Duration::from_millis(1990) && delay <= Duration::from_secs(2));
+}
+
+#[test]
+fn test_longest_delay_wins() {
+    let mut limiter = limiter(1, 1_000_000).unwrap();
+    let now = Instant::now();
+
+    assert_eq!(limiter.acquire(10, now), Duration::ZERO);
+    let delay = limiter.acquire(10, now);
+    assert!(delay > Duration::from_millis(990));
+}
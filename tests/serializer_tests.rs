@@ -1,19 +1,35 @@
 use {
     agave_geyser_plugin_interface::geyser_plugin_interface::{
-        ReplicaTransactionInfo, ReplicaTransactionInfoV2,
+        ReplicaBlockInfoV2, ReplicaBlockInfoVersions, ReplicaTransactionInfo,
+        ReplicaTransactionInfoV2,
     },
     base64::{engine::general_purpose, Engine as _},
     serde_json::Value,
-    solana_geyser_plugin_nats::serializer::TransactionSerializer,
+    solana_geyser_plugin_nats::{
+        config::PayloadFormat,
+        serializer::{TransactionSerializer, SCHEMA_VERSION},
+        stats::PluginStats,
+        vote_summarizer::VoteSummarizer,
+    },
     solana_sdk::{
-        instruction::{AccountMeta, Instruction},
-        message::{Message, VersionedMessage},
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::{AccountMeta, CompiledInstruction, Instruction},
+        message::{
+            v0::{self, LoadedAddresses, MessageAddressTableLookup},
+            Message, MessageHeader, SimpleAddressLoader, VersionedMessage,
+        },
         pubkey::Pubkey,
         signature::Signature,
         system_instruction,
-        transaction::{SanitizedTransaction, VersionedTransaction},
+        transaction::{MessageHash, SanitizedTransaction, Transaction, VersionedTransaction},
+        transaction_context::TransactionReturnData,
+        vote::{instruction as vote_instruction, state::Vote},
+    },
+    solana_transaction_status::{
+        EncodedConfirmedTransactionWithStatusMeta, InnerInstruction, InnerInstructions, Reward,
+        RewardType, TransactionStatusMeta,
     },
-    solana_transaction_status::TransactionStatusMeta,
     std::collections::HashSet,
 };
 
@@ -202,6 +218,202 @@ fn test_serialize_transaction_with_default_meta() {
     let meta_obj = &serialized["meta"];
     assert_eq!(meta_obj["fee"], 0);
     assert_eq!(meta_obj["computeUnitsConsumed"], Value::Null);
+    assert_eq!(meta_obj["innerInstructions"], Value::Null);
+    assert_eq!(meta_obj["rewards"], Value::Null);
+    assert_eq!(meta_obj["returnData"], Value::Null);
+}
+
+#[test]
+fn test_serialize_address_table_lookups_and_loaded_addresses() {
+    let payer = Pubkey::new_unique();
+    let program_id = Pubkey::new_unique();
+    let lookup_table = Pubkey::new_unique();
+    let loaded_addresses = LoadedAddresses {
+        writable: vec![Pubkey::new_unique()],
+        readonly: vec![Pubkey::new_unique()],
+    };
+
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        },
+        account_keys: vec![payer, program_id],
+        recent_blockhash: Hash::new_unique(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(
+            1,
+            vec![7],
+            vec![0, 2, 3],
+        )],
+        address_table_lookups: vec![MessageAddressTableLookup {
+            account_key: lookup_table,
+            writable_indexes: vec![4],
+            readonly_indexes: vec![9],
+        }],
+    };
+    let transaction = SanitizedTransaction::try_create(
+        VersionedTransaction {
+            message: VersionedMessage::V0(message),
+            signatures: vec![Signature::new_unique()],
+        },
+        MessageHash::Compute,
+        Some(false),
+        SimpleAddressLoader::Enabled(loaded_addresses.clone()),
+        &HashSet::new(),
+    )
+    .expect("Failed to create v0 sanitized transaction");
+    let meta = TransactionStatusMeta {
+        loaded_addresses: loaded_addresses.clone(),
+        ..create_test_meta()
+    };
+    let transaction_info = ReplicaTransactionInfoV2 {
+        signature: &transaction.signatures()[0],
+        is_vote: false,
+        transaction: &transaction,
+        transaction_status_meta: &meta,
+        index: 0,
+    };
+
+    let serialized = TransactionSerializer::serialize_transaction_v2(&transaction_info, 1).unwrap();
+
+    let message = &serialized["transaction"]["message"];
+    assert_eq!(message["accountKeys"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        message["addressTableLookups"],
+        serde_json::json!([{
+            "accountKey": lookup_table.to_string(),
+            "writableIndexes": [4],
+            "readonlyIndexes": [9],
+        }])
+    );
+    assert_eq!(
+        serialized["meta"]["loadedAddresses"],
+        serde_json::json!({
+            "writable": [loaded_addresses.writable[0].to_string()],
+            "readonly": [loaded_addresses.readonly[0].to_string()],
+        })
+    );
+}
+
+#[test]
+fn test_serialize_fee_payer_and_account_key_flags() {
+    let transaction = create_test_transaction();
+    let meta = create_test_meta();
+    let account_keys = transaction.message().account_keys();
+
+    let serialized = TransactionSerializer::serialize_transaction_v2(
+        &ReplicaTransactionInfoV2 {
+            signature: &transaction.signatures()[0],
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        },
+        1,
+    )
+    .unwrap();
+
+    assert_eq!(serialized["feePayer"], account_keys[0].to_string());
+    assert_eq!(
+        serialized["transaction"]["message"]["accountKeys"],
+        serde_json::json!([
+            { "pubkey": account_keys[0].to_string(), "signer": true, "writable": true },
+            { "pubkey": account_keys[1].to_string(), "signer": false, "writable": true },
+            { "pubkey": account_keys[2].to_string(), "signer": false, "writable": false },
+        ])
+    );
+}
+
+#[test]
+fn test_serialize_meta_rewards_and_return_data() {
+    let transaction = create_test_transaction();
+    let program_id = Pubkey::new_unique();
+    let meta = TransactionStatusMeta {
+        rewards: Some(vec![Reward {
+            pubkey: Pubkey::new_unique().to_string(),
+            lamports: -5000,
+            post_balance: 995_000,
+            reward_type: Some(RewardType::Rent),
+            commission: None,
+        }]),
+        return_data: Some(TransactionReturnData {
+            program_id,
+            data: vec![1, 2, 3],
+        }),
+        ..create_test_meta()
+    };
+    let transaction_info = ReplicaTransactionInfoV2 {
+        signature: &transaction.signatures()[0],
+        is_vote: false,
+        transaction: &transaction,
+        transaction_status_meta: &meta,
+        index: 0,
+    };
+
+    let serialized = TransactionSerializer::serialize_transaction_v2(&transaction_info, 1).unwrap();
+
+    let meta = &serialized["meta"];
+    assert_eq!(meta["rewards"][0]["lamports"], -5000);
+    assert_eq!(meta["rewards"][0]["postBalance"], 995_000);
+    assert_eq!(meta["rewards"][0]["rewardType"], "Rent");
+    assert_eq!(meta["returnData"]["programId"], program_id.to_string());
+    assert_eq!(
+        meta["returnData"]["data"],
+        serde_json::json!(["AQID", "base64"])
+    );
+}
+
+#[test]
+fn test_serialize_inner_instructions() {
+    let transaction = create_test_transaction();
+    let meta = TransactionStatusMeta {
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 0,
+            instructions: vec![
+                InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(
+                        2,
+                        vec![1, 2, 3],
+                        vec![0, 1],
+                    ),
+                    stack_height: Some(2),
+                },
+                InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(3, vec![], vec![1]),
+                    stack_height: None,
+                },
+            ],
+        }]),
+        ..create_test_meta()
+    };
+    let transaction_info = ReplicaTransactionInfoV2 {
+        signature: &transaction.signatures()[0],
+        is_vote: false,
+        transaction: &transaction,
+        transaction_status_meta: &meta,
+        index: 0,
+    };
+
+    let serialized = TransactionSerializer::serialize_transaction_v2(&transaction_info, 1).unwrap();
+
+    let inner_instructions = &serialized["meta"]["innerInstructions"];
+    assert_eq!(inner_instructions.as_array().unwrap().len(), 1);
+    assert_eq!(inner_instructions[0]["index"], 0);
+
+    let instructions = inner_instructions[0]["instructions"].as_array().unwrap();
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions[0]["programIdIndex"], 2);
+    assert_eq!(instructions[0]["accounts"], serde_json::json!([0, 1]));
+    assert_eq!(
+        general_purpose::STANDARD
+            .decode(instructions[0]["data"].as_str().unwrap())
+            .unwrap(),
+        vec![1, 2, 3]
+    );
+    assert_eq!(instructions[0]["stackHeight"], 2);
+    assert_eq!(instructions[1]["data"], "");
+    assert_eq!(instructions[1]["stackHeight"], Value::Null);
 }
 
 #[test]
@@ -231,11 +443,17 @@ fn test_serialize_transaction_v1_and_v2_comprehensive() {
     assert!(result_v1.is_ok());
     assert!(result_v2.is_ok());
 
-    let serialized_v1 = result_v1.unwrap();
-    let serialized_v2 = result_v2.unwrap();
+    let mut serialized_v1 = result_v1.unwrap();
+    let mut serialized_v2 = result_v2.unwrap();
 
-    // Both should produce identical results
+    // Both should produce identical results, apart from the index only V2 carries
+    assert_eq!(serialized_v1["index"], Value::Null);
+    assert_eq!(serialized_v2["index"], 0);
+    serialized_v1["index"] = Value::Null;
+    serialized_v2["index"] = Value::Null;
     assert_eq!(serialized_v1, serialized_v2);
+    assert_eq!(serialized_v1["isVote"], false);
+    assert_eq!(serialized_v1["blockTime"], Value::Null);
 
     // Verify basic structure
     assert!(serialized_v1.get("transaction").is_some());
@@ -348,6 +566,67 @@ fn test_serialize_balances_and_logs() {
     }
 }
 
+#[test]
+fn test_serialize_raw_transaction() {
+    let transaction = create_test_transaction();
+    let signature = transaction.signatures()[0];
+    let meta = create_error_meta();
+
+    let serialized =
+        TransactionSerializer::serialize_raw_transaction(&signature, &transaction, &meta, 12345)
+            .unwrap();
+
+    assert_eq!(serialized["slot"], 12345);
+    assert_eq!(serialized["signature"], signature.to_string());
+    assert!(serialized["err"].is_string());
+    assert_eq!(serialized["transaction"][1], "base64");
+    assert!(serialized.get("meta").is_none());
+
+    // The wire format decodes back to the original transaction
+    let wire_transaction = general_purpose::STANDARD
+        .decode(serialized["transaction"][0].as_str().unwrap())
+        .unwrap();
+    let decoded: VersionedTransaction = bincode::deserialize(&wire_transaction).unwrap();
+    assert_eq!(decoded, transaction.to_versioned_transaction());
+}
+
+#[test]
+fn test_serialize_rpc_transaction() {
+    let transaction = create_test_transaction();
+    let meta = create_test_meta();
+
+    let serialized =
+        TransactionSerializer::serialize_rpc_transaction(&transaction, &meta, 12345, false)
+            .unwrap();
+
+    // The layout of a getTransaction result
+    assert_eq!(serialized["slot"], 12345);
+    assert_eq!(serialized["blockTime"], Value::Null);
+    assert_eq!(serialized["version"], "legacy");
+    assert_eq!(
+        serialized["transaction"]["signatures"][0],
+        transaction.signatures()[0].to_string()
+    );
+    assert!(serialized["transaction"]["message"]["accountKeys"][0].is_string());
+    assert_eq!(serialized["meta"]["fee"], 5000);
+    assert_eq!(
+        serialized["meta"]["status"],
+        serde_json::json!({ "Ok": null })
+    );
+    assert!(serialized["meta"]["logMessages"].is_array());
+    let decoded: EncodedConfirmedTransactionWithStatusMeta =
+        serde_json::from_value(serialized).unwrap();
+    assert_eq!(decoded.slot, 12345);
+
+    // jsonParsed names every account and decodes the transfer
+    let parsed =
+        TransactionSerializer::serialize_rpc_transaction(&transaction, &meta, 12345, true).unwrap();
+    let message = &parsed["transaction"]["message"];
+    assert!(message["accountKeys"][0]["pubkey"].is_string());
+    assert_eq!(message["instructions"][0]["program"], "system");
+    assert_eq!(message["instructions"][0]["parsed"]["type"], "transfer");
+}
+
 #[test]
 fn test_serialize_vote_transaction() {
     let transaction = create_test_transaction();
@@ -359,7 +638,7 @@ fn test_serialize_vote_transaction() {
         is_vote: true, // Mark as vote transaction
         transaction: &transaction,
         transaction_status_meta: &meta,
-        index: 0,
+        index: 7,
     };
 
     let result = TransactionSerializer::serialize_transaction_v2(&transaction_info, slot);
@@ -367,13 +646,15 @@ fn test_serialize_vote_transaction() {
 
     let serialized = result.unwrap();
 
-    // Vote transactions should serialize the same way as regular transactions
-    // The is_vote flag is not included in the serialized output but is used
-    // for filtering in the processor
+    // Vote transactions serialize the same way as regular transactions, flagged as votes
     assert!(serialized.get("transaction").is_some());
     assert!(serialized.get("version").is_some());
     assert!(serialized.get("slot").is_some());
     assert!(serialized.get("meta").is_some());
+    assert_eq!(serialized["isVote"], true);
+    assert_eq!(serialized["index"], 7);
+    // The block time is only known once the block metadata arrives
+    assert_eq!(serialized["blockTime"], Value::Null);
 }
 
 #[test]
@@ -429,7 +710,7 @@ fn test_json_serialization_roundtrip() {
 #[test]
 fn test_serialize_multiple_transactions_consistency() {
     // Test that serializing multiple transactions produces consistent results
-    let transactions = vec![create_test_transaction(), create_complex_test_transaction()];
+    let transactions = [create_test_transaction(), create_complex_test_transaction()];
 
     let meta = create_test_meta();
     let slot = 12345;
@@ -538,3 +819,484 @@ fn test_serialize_log_message_scenarios() {
     let normal_logs = serialized_normal["meta"]["logMessages"].as_array().unwrap();
     assert_eq!(normal_logs.len(), 2);
 }
+
+#[test]
+fn test_serialize_block_with_transactions() {
+    let rewards = vec![Reward {
+        pubkey: Pubkey::new_unique().to_string(),
+        lamports: 5000,
+        post_balance: 1_005_000,
+        reward_type: Some(RewardType::Fee),
+        commission: None,
+    }];
+    let block_info = ReplicaBlockInfoV2 {
+        parent_slot: 99,
+        parent_blockhash: "parent_hash",
+        slot: 100,
+        blockhash: "block_hash",
+        rewards: &rewards,
+        block_time: Some(1_700_000_000),
+        block_height: Some(90),
+        executed_transaction_count: 2,
+    };
+
+    let transactions = vec![
+        serde_json::json!({"signature": "a"}),
+        serde_json::json!({"signature": "b"}),
+    ];
+    let serialized = TransactionSerializer::serialize_block(
+        &ReplicaBlockInfoVersions::V0_0_2(&block_info),
+        transactions,
+    )
+    .unwrap();
+
+    assert_eq!(serialized["slot"], 100);
+    assert_eq!(serialized["parentSlot"], 99);
+    assert_eq!(serialized["blockhash"], "block_hash");
+    assert_eq!(serialized["blockTime"], 1_700_000_000);
+    assert_eq!(serialized["rewards"][0]["rewardType"], "Fee");
+    assert_eq!(serialized["rewards"][0]["postBalance"], 1_005_000);
+    assert_eq!(serialized["transactions"].as_array().unwrap().len(), 2);
+    assert_eq!(serialized["transactions"][1]["signature"], "b");
+}
+
+#[test]
+fn test_serialize_requested_compute_budget() {
+    let from_pubkey = Pubkey::new_unique();
+    let message = Message::new(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+            ComputeBudgetInstruction::set_compute_unit_price(25_000),
+            system_instruction::transfer(&from_pubkey, &Pubkey::new_unique(), 1),
+        ],
+        Some(&from_pubkey),
+    );
+    let transaction = SanitizedTransaction::try_from_legacy_transaction(
+        Transaction {
+            signatures: vec![Signature::new_unique()],
+            message,
+        },
+        &HashSet::new(),
+    )
+    .unwrap();
+    let meta = create_test_meta();
+
+    let serialized = TransactionSerializer::serialize_transaction_v2(
+        &ReplicaTransactionInfoV2 {
+            signature: &transaction.signatures()[0],
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        },
+        100,
+    )
+    .unwrap();
+    assert_eq!(serialized["meta"]["computeUnitLimit"], 300_000);
+    assert_eq!(serialized["meta"]["computeUnitPrice"], 25_000);
+    assert_eq!(
+        serialized["meta"]["computeUnitsConsumed"],
+        serde_json::json!(meta.compute_units_consumed)
+    );
+
+    // Transactions without Compute Budget instructions leave them unset
+    let transaction = create_test_transaction();
+    let serialized = TransactionSerializer::serialize_transaction_v2(
+        &ReplicaTransactionInfoV2 {
+            signature: &transaction.signatures()[0],
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &meta,
+            index: 0,
+        },
+        100,
+    )
+    .unwrap();
+    assert_eq!(serialized["meta"]["computeUnitLimit"], Value::Null);
+    assert_eq!(serialized["meta"]["computeUnitPrice"], Value::Null);
+}
+
+#[test]
+fn test_serialize_compute_breakdown() {
+    let logs: Vec<String> = [
+        "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+        "Program ComputeBudget111111111111111111111111111111 success",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]",
+        "Program log: Instruction: Route",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA consumed 4645 of 180000 compute units",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 consumed 25000 of 199850 compute units",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 success",
+    ]
+    .iter()
+    .map(|log| log.to_string())
+    .collect();
+
+    let breakdown = TransactionSerializer::serialize_compute_breakdown(&logs);
+    let invocations = breakdown.as_array().unwrap();
+
+    assert_eq!(invocations.len(), 3);
+    assert_eq!(
+        invocations[0]["programId"],
+        "ComputeBudget111111111111111111111111111111"
+    );
+    assert!(invocations[0]["computeUnitsConsumed"].is_null());
+    assert_eq!(invocations[1]["depth"], 1);
+    assert_eq!(invocations[1]["computeUnitsConsumed"], 25000);
+    assert_eq!(invocations[1]["computeUnitsLimit"], 199850);
+    assert_eq!(invocations[2]["depth"], 2);
+    assert_eq!(invocations[2]["computeUnitsConsumed"], 4645);
+    assert_eq!(invocations[2]["computeUnitsLimit"], 180000);
+}
+
+#[test]
+fn test_serialize_parsed_logs() {
+    let logs: Vec<String> = [
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]",
+        "Program log: Instruction: Route",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+        "Program log: Instruction: Transfer",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA consumed 4645 of 180000 compute units",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+        "Program data: AQID BAUG",
+        "Program return: JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 CgAAAAAAAAA=",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 consumed 25000 of 199850 compute units",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 success",
+        "Program 11111111111111111111111111111111 invoke [1]",
+        "Transfer: insufficient lamports 10, need 20",
+        "Program 11111111111111111111111111111111 failed: custom program error: 0x1",
+        "Program Vote111111111111111111111111111111111111111 invoke [1]",
+        "Log truncated",
+    ]
+    .iter()
+    .map(|log| log.to_string())
+    .collect();
+
+    let parsed = TransactionSerializer::serialize_parsed_logs(&logs);
+    let invocations = parsed.as_array().unwrap();
+    assert_eq!(invocations.len(), 3);
+
+    let route = &invocations[0];
+    assert_eq!(
+        route["programId"],
+        "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"
+    );
+    assert_eq!(route["depth"], 1);
+    assert_eq!(route["success"], true);
+    assert_eq!(route["error"], Value::Null);
+    assert_eq!(route["computeUnitsConsumed"], 25000);
+    assert_eq!(route["computeUnitsLimit"], 199850);
+    assert_eq!(route["logs"], serde_json::json!(["Instruction: Route"]));
+    assert_eq!(route["data"], serde_json::json!(["AQID", "BAUG"]));
+    assert_eq!(route["returnData"], "CgAAAAAAAAA=");
+
+    let transfer = &route["invocations"][0];
+    assert_eq!(transfer["depth"], 2);
+    assert_eq!(transfer["success"], true);
+    assert_eq!(transfer["computeUnitsConsumed"], 4645);
+    assert_eq!(
+        transfer["logs"],
+        serde_json::json!(["Instruction: Transfer"])
+    );
+    assert_eq!(transfer["invocations"], serde_json::json!([]));
+
+    let failed = &invocations[1];
+    assert_eq!(failed["success"], false);
+    assert_eq!(failed["error"], "custom program error: 0x1");
+    assert_eq!(
+        failed["logs"],
+        serde_json::json!(["Transfer: insufficient lamports 10, need 20"])
+    );
+
+    // Truncated logs leave the last invocation open
+    let truncated = &invocations[2];
+    assert_eq!(truncated["success"], Value::Null);
+    assert_eq!(truncated["logs"], serde_json::json!(["Log truncated"]));
+}
+
+#[test]
+fn test_compute_breakdown_follows_parsed_logs() {
+    let logs: Vec<String> = [
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA consumed 4645 of 180000 compute units",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+        "Program 11111111111111111111111111111111 invoke [2]",
+        "Log truncated",
+    ]
+    .iter()
+    .map(|log| log.to_string())
+    .collect();
+
+    let parsed = TransactionSerializer::serialize_parsed_logs(&logs);
+    let route = &parsed[0];
+    let breakdown = TransactionSerializer::serialize_compute_breakdown(&logs);
+
+    // Every invocation of the tree, in the order it was invoked
+    let expected = [route, &route["invocations"][0], &route["invocations"][1]];
+    assert_eq!(breakdown.as_array().unwrap().len(), expected.len());
+    for (entry, invocation) in breakdown.as_array().unwrap().iter().zip(expected) {
+        for field in [
+            "programId",
+            "depth",
+            "computeUnitsConsumed",
+            "computeUnitsLimit",
+        ] {
+            assert_eq!(entry[field], invocation[field], "{field}");
+        }
+    }
+    assert_eq!(breakdown[1]["computeUnitsConsumed"], 4645);
+    assert_eq!(
+        breakdown[2]["programId"],
+        "11111111111111111111111111111111"
+    );
+}
+
+#[test]
+fn test_ordering_key() {
+    assert_eq!(TransactionSerializer::ordering_key(1, Some(0)), 1 << 20);
+    assert_eq!(
+        TransactionSerializer::ordering_key(1, Some(5)),
+        (1 << 20) | 5
+    );
+    assert_eq!(TransactionSerializer::ordering_key(7, None), 7 << 20);
+    assert!(
+        TransactionSerializer::ordering_key(100, Some(999))
+            < TransactionSerializer::ordering_key(101, Some(0))
+    );
+}
+
+#[test]
+fn test_serialize_vote_event() {
+    let vote_account = Pubkey::new_unique();
+    let authorized_voter = Pubkey::new_unique();
+    let bank_hash = Hash::new_unique();
+    let vote = Vote {
+        slots: vec![40, 41],
+        hash: bank_hash,
+        timestamp: Some(1_700_000_000),
+    };
+    let instruction = vote_instruction::vote(&vote_account, &authorized_voter, vote);
+    let message = Message::new(&[instruction], Some(&authorized_voter));
+    let transaction = SanitizedTransaction::try_from_legacy_transaction(
+        Transaction {
+            signatures: vec![Signature::new_unique()],
+            message,
+        },
+        &HashSet::new(),
+    )
+    .unwrap();
+    let meta = create_test_meta();
+
+    let serialized = TransactionSerializer::serialize_vote_event(&transaction, &meta, 42)
+        .expect("Vote should be decoded");
+
+    assert_eq!(serialized["event"], "vote_event");
+    assert_eq!(serialized["slot"], 42);
+    assert_eq!(serialized["voteAccount"], vote_account.to_string());
+    assert_eq!(serialized["votedSlots"], serde_json::json!([40, 41]));
+    assert_eq!(serialized["hash"], bank_hash.to_string());
+    assert_eq!(serialized["timestamp"], 1_700_000_000);
+    assert_eq!(serialized["switchProofHash"], Value::Null);
+
+    assert!(
+        TransactionSerializer::serialize_vote_event(&create_test_transaction(), &meta, 42)
+            .is_none()
+    );
+}
+
+#[test]
+fn test_serialize_block_rewards() {
+    let rewards = vec![Reward {
+        pubkey: Pubkey::new_unique().to_string(),
+        lamports: 2500,
+        post_balance: 10_000,
+        reward_type: Some(RewardType::Staking),
+        commission: Some(7),
+    }];
+
+    let serialized = TransactionSerializer::serialize_block_rewards(88, &rewards);
+
+    assert_eq!(serialized["slot"], 88);
+    assert_eq!(serialized["rewards"][0]["lamports"], 2500);
+    assert_eq!(serialized["rewards"][0]["postBalance"], 10_000);
+    assert_eq!(serialized["rewards"][0]["rewardType"], "Staking");
+    assert_eq!(serialized["rewards"][0]["commission"], 7);
+}
+
+#[test]
+fn test_serialize_vote_summary() {
+    let summarizer = VoteSummarizer::new(4);
+    let voter = Pubkey::new_unique();
+    summarizer.add_vote(42, voter, 5000);
+    summarizer.add_vote(42, voter, 5000);
+    summarizer.add_vote(42, Pubkey::new_unique(), 5000);
+
+    let serialized =
+        TransactionSerializer::serialize_vote_summary(42, &summarizer.take_summary(42));
+
+    assert_eq!(serialized["slot"], 42);
+    assert_eq!(serialized["voteCount"], 3);
+    assert_eq!(serialized["uniqueVoters"], 2);
+    assert_eq!(serialized["totalFeeLamports"], 15000);
+    assert_eq!(serialized["voters"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_serialize_shutdown_notice() {
+    let slots = [
+        ("solana.transactions".to_string(), 120),
+        ("solana.blocks".to_string(), 119),
+    ]
+    .into_iter()
+    .collect();
+
+    let serialized = TransactionSerializer::serialize_shutdown_notice(&slots);
+
+    assert_eq!(serialized["event"], "shutdown");
+    assert_eq!(serialized["lastPublishedSlot"], 120);
+    assert_eq!(serialized["lastPublishedSlots"]["solana.blocks"], 119);
+    assert!(serialized["timestamp"].is_string());
+}
+
+#[test]
+fn test_serialize_startup_complete() {
+    let serialized = TransactionSerializer::serialize_startup_complete(Some(300));
+    assert_eq!(serialized["event"], "startup_complete");
+    assert_eq!(serialized["slot"], 300);
+
+    let serialized = TransactionSerializer::serialize_startup_complete(None);
+    assert!(serialized["slot"].is_null());
+}
+
+#[test]
+fn test_serialize_offloaded_payload() {
+    let serialized = TransactionSerializer::serialize_offloaded_payload(
+        "geyser-payloads",
+        "solana.transactions/300/00000000000000ff",
+        Some("SHA-256=47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU="),
+        2_000_000,
+        Some(300),
+        Some("zstd"),
+    );
+
+    assert_eq!(serialized["event"], "offloaded_payload");
+    assert_eq!(serialized["bucket"], "geyser-payloads");
+    assert_eq!(
+        serialized["object"],
+        "solana.transactions/300/00000000000000ff"
+    );
+    assert!(serialized["digest"]
+        .as_str()
+        .unwrap()
+        .starts_with("SHA-256="));
+    assert_eq!(serialized["size"], 2_000_000);
+    assert_eq!(serialized["slot"], 300);
+    assert_eq!(serialized["contentEncoding"], "zstd");
+}
+
+#[test]
+fn test_set_schema_version() {
+    let mut value = serde_json::json!({ "slot": 300 });
+    TransactionSerializer::set_schema_version(&mut value);
+    assert_eq!(value["schemaVersion"], SCHEMA_VERSION);
+    assert_eq!(value["slot"], 300);
+
+    // Only objects carry fields
+    let mut value = serde_json::json!([300]);
+    TransactionSerializer::set_schema_version(&mut value);
+    assert_eq!(value, serde_json::json!([300]));
+}
+
+#[test]
+fn test_canonical_json() {
+    let value = serde_json::json!({
+        "slot": 300,
+        "meta": { "fee": 5000, "err": null, "computeUnitsConsumed": 3.0 },
+        "isVote": false,
+        "logs": ["Program log: \"quoted\"\n", "ünïcode"],
+        "rate": 1.5,
+        "delta": -0.0,
+        "balance": -20,
+    });
+
+    let json = TransactionSerializer::encode(&value, PayloadFormat::Json, "test").unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        r#"{"balance":-20,"delta":0,"isVote":false,"logs":["Program log: \"quoted\"\n","ünïcode"],"meta":{"computeUnitsConsumed":3,"err":null,"fee":5000},"rate":1.5,"slot":300}"#
+    );
+
+    // Insertion order does not change the bytes
+    let mut reordered = serde_json::Map::new();
+    for key in ["slot", "rate", "meta", "logs", "isVote", "delta", "balance"] {
+        reordered.insert(key.to_string(), value[key].clone());
+    }
+    assert_eq!(
+        TransactionSerializer::encode(&Value::Object(reordered), PayloadFormat::Json, "test")
+            .unwrap(),
+        TransactionSerializer::encode(&value, PayloadFormat::Json, "test").unwrap()
+    );
+}
+
+#[test]
+fn test_encode_payload_formats() {
+    let value = serde_json::json!({
+        "signature": "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW",
+        "slot": 300,
+        "isVote": false,
+        "meta": { "fee": 5000, "logMessages": ["Program log: ok"] },
+    });
+
+    let json = TransactionSerializer::encode(&value, PayloadFormat::Json, "test").unwrap();
+    assert_eq!(serde_json::from_slice::<Value>(&json).unwrap(), value);
+
+    // Maps keep their field names, so consumers need no schema
+    let msgpack = TransactionSerializer::encode(&value, PayloadFormat::Msgpack, "test").unwrap();
+    assert_eq!(rmp_serde::from_slice::<Value>(&msgpack).unwrap(), value);
+    assert!(msgpack.len() < json.len());
+
+    let cbor = TransactionSerializer::encode(&value, PayloadFormat::Cbor, "test").unwrap();
+    assert_eq!(
+        ciborium::from_reader::<Value, _>(cbor.as_slice()).unwrap(),
+        value
+    );
+    assert!(cbor.len() < json.len());
+}
+
+#[test]
+fn test_serialize_plugin_stats() {
+    let stats = PluginStats::new();
+    stats.record_message_queued();
+    stats.record_message_queued();
+    stats.record_message_dropped();
+    stats.record_reconnect();
+    stats.record_slot_published("solana.transactions", 42);
+
+    let serialized = TransactionSerializer::serialize_plugin_stats(&stats, 1);
+    assert_eq!(serialized["event"], "plugin_stats");
+    assert_eq!(serialized["queued"], 2);
+    assert_eq!(serialized["published"], 0);
+    assert_eq!(serialized["dropped"], 1);
+    assert_eq!(serialized["reconnects"], 1);
+    assert_eq!(serialized["lastSlot"], 42);
+    assert_eq!(serialized["queueDepth"], 1);
+    assert!(serialized["timestamp"].is_string());
+}
+
+#[test]
+fn test_serialize_cluster_stats() {
+    let stats = PluginStats::new();
+    stats.record_message_queued();
+    stats.record_message_dropped();
+    stats.record_publish_error();
+    stats.record_error("Failed to queue message".to_string());
+
+    let serialized = TransactionSerializer::serialize_cluster_stats(&stats, 3);
+    assert_eq!(serialized["queued"], 1);
+    assert_eq!(serialized["dropped"], 1);
+    assert_eq!(serialized["publishErrors"], 1);
+    assert_eq!(serialized["lastError"], "Failed to queue message");
+    assert_eq!(serialized["queueDepth"], 3);
+    assert_eq!(serialized["degraded"], false);
+}
@@ -0,0 +1,94 @@
+use {
+    serde_json::Value,
+    solana_geyser_plugin_nats::{
+        connection::RequestHandler, service::NatsService, stats::PluginStats,
+    },
+    std::{sync::Arc, time::Duration},
+};
+
+fn create_test_service() -> NatsService {
+    NatsService::new(
+        "geyser-test",
+        "Test service",
+        vec!["solana.transactions".to_string()],
+        Arc::new(PluginStats::new()),
+    )
+}
+
+fn request(service: &NatsService, subject: &str) -> Value {
+    let response = service
+        .handle_request(subject, &[], b"")
+        .expect("Service should answer request");
+    serde_json::from_slice(&response).expect("Response should be valid JSON")
+}
+
+#[test]
+fn test_service_subjects() {
+    let service = create_test_service();
+    let subjects = service.subjects();
+
+    assert_eq!(subjects.len(), 9);
+    assert!(subjects.contains(&"$SRV.PING".to_string()));
+    assert!(subjects.contains(&"$SRV.INFO.geyser-test".to_string()));
+    assert!(subjects.contains(&format!("$SRV.STATS.geyser-test.{}", service.id())));
+}
+
+#[test]
+fn test_ping_response() {
+    let service = create_test_service();
+    let response = request(&service, "$SRV.PING");
+
+    assert_eq!(response["type"], "io.nats.micro.v1.ping_response");
+    assert_eq!(response["name"], "geyser-test");
+    assert_eq!(response["id"], service.id());
+    assert_eq!(response["version"], env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_info_response_reports_subjects() {
+    let service = create_test_service();
+    let response = request(&service, "$SRV.INFO.geyser-test");
+
+    assert_eq!(response["type"], "io.nats.micro.v1.info_response");
+    assert_eq!(response["description"], "Test service");
+    assert_eq!(response["metadata"]["subjects"], "solana.transactions");
+    assert_eq!(response["endpoints"][0]["name"], "publish");
+    assert_eq!(response["endpoints"][0]["subject"], "solana.transactions");
+}
+
+#[test]
+fn test_stats_response_reports_counters() {
+    let stats = Arc::new(PluginStats::new());
+    stats.record_message_published("solana.transactions", 128, Duration::from_micros(10));
+    stats.record_message_published("solana.transactions", 128, Duration::from_micros(30));
+    stats.record_publish_error();
+    stats.record_error("connection reset".to_string());
+
+    let service = NatsService::new(
+        "geyser-test",
+        "Test service",
+        vec!["solana.transactions".to_string()],
+        stats,
+    );
+    let response = request(
+        &service,
+        &format!("$SRV.STATS.geyser-test.{}", service.id()),
+    );
+
+    assert_eq!(response["type"], "io.nats.micro.v1.stats_response");
+    let endpoint = &response["endpoints"][0];
+    assert_eq!(endpoint["name"], "publish");
+    assert_eq!(endpoint["num_requests"], 2);
+    assert_eq!(endpoint["num_errors"], 1);
+    assert_eq!(endpoint["processing_time"], 40_000);
+    assert_eq!(endpoint["average_processing_time"], 20_000);
+    assert_eq!(endpoint["last_error"], "connection reset");
+    assert_eq!(endpoint["data"]["bytesPublished"], 256);
+}
+
+#[test]
+fn test_unknown_request_ignored() {
+    let service = create_test_service();
+    assert!(service.handle_request("$SRV.UNKNOWN", &[], b"").is_none());
+    assert!(service.handle_request("other.subject", &[], b"").is_none());
+}
@@ -0,0 +1,61 @@
+use {
+    solana_geyser_plugin_nats::signature_deduplicator::SignatureDeduplicator,
+    solana_sdk::signature::Signature,
+};
+
+#[test]
+fn test_replayed_transaction_is_duplicate() {
+    let deduplicator = SignatureDeduplicator::new(16);
+    let signature = Signature::new_unique();
+
+    assert!(!deduplicator.is_duplicate(&signature, 10));
+    assert!(deduplicator.is_duplicate(&signature, 10));
+    assert!(deduplicator.is_duplicate(&signature, 10));
+    assert_eq!(deduplicator.tracked_signatures(), 1);
+}
+
+#[test]
+fn test_same_signature_in_another_slot_is_not_duplicate() {
+    let deduplicator = SignatureDeduplicator::new(16);
+    let signature = Signature::new_unique();
+
+    assert!(!deduplicator.is_duplicate(&signature, 10));
+    assert!(!deduplicator.is_duplicate(&signature, 11));
+    assert!(deduplicator.is_duplicate(&signature, 11));
+    assert_eq!(deduplicator.tracked_signatures(), 2);
+}
+
+#[test]
+fn test_least_recently_seen_transaction_evicted() {
+    let deduplicator = SignatureDeduplicator::new(2);
+    let first = Signature::new_unique();
+    let second = Signature::new_unique();
+    let third = Signature::new_unique();
+
+    assert!(!deduplicator.is_duplicate(&first, 1));
+    assert!(!deduplicator.is_duplicate(&second, 1));
+    // Seeing the first again makes the second the least recently seen
+    assert!(deduplicator.is_duplicate(&first, 1));
+    assert!(!deduplicator.is_duplicate(&third, 1));
+    assert_eq!(deduplicator.tracked_signatures(), 2);
+
+    assert!(deduplicator.is_duplicate(&first, 1));
+    assert!(deduplicator.is_duplicate(&third, 1));
+    assert!(!deduplicator.is_duplicate(&second, 1));
+}
+
+#[test]
+fn test_repeated_duplicates_stay_bounded() {
+    let deduplicator = SignatureDeduplicator::new(4);
+    let signatures: Vec<Signature> = (0..4).map(|_| Signature::new_unique()).collect();
+    for signature in &signatures {
+        assert!(!deduplicator.is_duplicate(signature, 7));
+    }
+
+    for _ in 0..1_000 {
+        for signature in &signatures {
+            assert!(deduplicator.is_duplicate(signature, 7));
+        }
+    }
+    assert_eq!(deduplicator.tracked_signatures(), 4);
+}
@@ -0,0 +1,74 @@
+use solana_geyser_plugin_nats::slot_tracker::{SlotDropReason, SlotTracker};
+
+#[test]
+fn test_dead_slot_dropped_with_signatures() {
+    let tracker = SlotTracker::new(16);
+    tracker.record_parent(11, 10);
+    tracker.record_transaction(11, "sig1".to_string());
+    tracker.record_transaction(11, "sig2".to_string());
+
+    let dropped = tracker.slot_dead(11).expect("slot 11 is tracked");
+    assert_eq!(dropped.slot, 11);
+    assert_eq!(dropped.parent, Some(10));
+    assert_eq!(dropped.reason, SlotDropReason::Dead);
+    assert_eq!(dropped.rooted_slot, None);
+    assert_eq!(dropped.signatures, vec!["sig1", "sig2"]);
+    assert_eq!(tracker.tracked_slots(), 0);
+
+    assert!(tracker.slot_dead(12).is_none());
+}
+
+#[test]
+fn test_competing_fork_abandoned_on_root() {
+    let tracker = SlotTracker::new(16);
+    // 10 <- 11 <- 13 is rooted, 10 <- 12 is a competing fork
+    tracker.record_parent(11, 10);
+    tracker.record_parent(12, 10);
+    tracker.record_parent(13, 11);
+    tracker.record_transaction(11, "kept".to_string());
+    tracker.record_transaction(12, "forked".to_string());
+
+    let dropped = tracker.slot_rooted(13);
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].slot, 12);
+    assert_eq!(dropped[0].reason, SlotDropReason::Abandoned);
+    assert_eq!(dropped[0].rooted_slot, Some(13));
+    assert_eq!(dropped[0].signatures, vec!["forked"]);
+    assert_eq!(tracker.tracked_slots(), 0);
+}
+
+#[test]
+fn test_slots_above_root_stay_tracked() {
+    let tracker = SlotTracker::new(16);
+    tracker.record_parent(11, 10);
+    tracker.record_parent(12, 11);
+    tracker.record_transaction(12, "pending".to_string());
+
+    assert!(tracker.slot_rooted(11).is_empty());
+    assert_eq!(tracker.tracked_slots(), 1);
+
+    assert!(tracker.slot_rooted(12).is_empty());
+    assert_eq!(tracker.tracked_slots(), 0);
+}
+
+#[test]
+fn test_slots_with_unknown_ancestry_stay_tracked() {
+    let tracker = SlotTracker::new(16);
+    tracker.record_transaction(5, "unknown".to_string());
+    tracker.record_parent(11, 10);
+
+    // Slot 5 is below the lowest known ancestor of the root
+    assert!(tracker.slot_rooted(11).is_empty());
+    assert_eq!(tracker.tracked_slots(), 1);
+}
+
+#[test]
+fn test_oldest_slots_evicted() {
+    let tracker = SlotTracker::new(2);
+    tracker.record_transaction(1, "a".to_string());
+    tracker.record_transaction(2, "b".to_string());
+    tracker.record_transaction(3, "c".to_string());
+
+    assert_eq!(tracker.tracked_slots(), 2);
+    assert!(tracker.slot_dead(1).is_none());
+}
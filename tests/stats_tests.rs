@@ -0,0 +1,167 @@
+use {
+    solana_geyser_plugin_nats::stats::{FilterStats, LatencyHistogram, PluginStats},
+    std::time::Duration,
+};
+
+#[test]
+fn test_empty_histogram_percentiles() {
+    let histogram = LatencyHistogram::default();
+
+    assert_eq!(histogram.count(), 0);
+    assert_eq!(histogram.percentile_micros(50.0), 0);
+    assert_eq!(histogram.percentile_micros(99.0), 0);
+}
+
+#[test]
+fn test_histogram_percentiles() {
+    let mut histogram = LatencyHistogram::default();
+    for _ in 0..90 {
+        histogram.record(Duration::from_micros(100));
+    }
+    for _ in 0..10 {
+        histogram.record(Duration::from_millis(50));
+    }
+
+    assert_eq!(histogram.count(), 100);
+    assert_eq!(histogram.max_micros(), 50_000);
+
+    // 100µs falls in the 64..127µs bucket
+    assert_eq!(histogram.percentile_micros(50.0), 127);
+    assert_eq!(histogram.percentile_micros(90.0), 127);
+    // The slowest messages are capped at the observed maximum
+    assert_eq!(histogram.percentile_micros(99.0), 50_000);
+}
+
+#[test]
+fn test_per_subject_stats() {
+    let stats = PluginStats::new();
+
+    stats.record_message_published("solana.transactions", 100, Duration::from_micros(10));
+    stats.record_message_published("solana.transactions", 300, Duration::from_micros(20));
+    stats.record_message_published("solana.blocks", 1000, Duration::from_millis(1));
+
+    assert_eq!(stats.messages_published(), 3);
+    assert_eq!(stats.bytes_published(), 1400);
+
+    let transactions = stats.subject_stats("solana.transactions").unwrap();
+    assert_eq!(transactions.messages, 2);
+    assert_eq!(transactions.bytes, 400);
+    assert_eq!(transactions.latency.count(), 2);
+
+    let blocks = stats.subject_stats("solana.blocks").unwrap();
+    assert_eq!(blocks.messages, 1);
+    assert!(stats.subject_stats("solana.unknown").is_none());
+}
+
+#[test]
+fn test_subject_group_shares_one_entry() {
+    let stats = PluginStats::new().with_subject_group("solana.address");
+
+    stats.record_message_published("solana.address.Vote111", 10, Duration::from_micros(10));
+    stats.record_message_published("solana.address.Stake11", 20, Duration::from_micros(10));
+    stats.record_slot_published("solana.address.Stake11", 42);
+    stats.record_message_published("solana.addresses", 30, Duration::from_micros(10));
+
+    let addresses = stats.subject_stats("solana.address.*").unwrap();
+    assert_eq!(addresses.messages, 2);
+    assert_eq!(addresses.bytes, 30);
+    assert_eq!(addresses.last_slot, Some(42));
+    assert!(stats.subject_stats("solana.address.Vote111").is_none());
+    assert_eq!(stats.subject_stats("solana.addresses").unwrap().messages, 1);
+    assert_eq!(stats.to_json()["subjects"].as_object().unwrap().len(), 2);
+}
+
+#[test]
+fn test_report_metrics_updates_rates() {
+    let stats = PluginStats::new();
+    for _ in 0..10 {
+        stats.record_message_published("solana.transactions", 50, Duration::from_micros(5));
+    }
+
+    stats.report_metrics(Duration::from_secs(2));
+
+    let transactions = stats.subject_stats("solana.transactions").unwrap();
+    assert_eq!(transactions.message_rate, 5.0);
+    assert_eq!(transactions.byte_rate, 250.0);
+
+    // Rates only count messages published since the previous report
+    stats.record_message_published("solana.transactions", 50, Duration::from_micros(5));
+    stats.report_metrics(Duration::from_secs(1));
+
+    let transactions = stats.subject_stats("solana.transactions").unwrap();
+    assert_eq!(transactions.message_rate, 1.0);
+}
+
+#[test]
+fn test_stats_json_includes_subjects() {
+    let stats = PluginStats::new();
+    stats.record_message_published("solana.transactions", 100, Duration::from_micros(10));
+
+    stats.record_message_offloaded();
+    stats.record_consumer_ack(Duration::from_micros(40));
+    stats.record_consumer_ack_timeout();
+
+    let json = stats.to_json();
+    assert_eq!(json["messagesPublished"], 1);
+    assert_eq!(json["messagesOffloaded"], 1);
+    assert_eq!(json["consumerAcks"], 1);
+    assert_eq!(json["consumerAckTimeouts"], 1);
+    assert_eq!(json["consumerAckLatencyMicros"]["max"], 40);
+    assert_eq!(json["subjects"]["solana.transactions"]["messages"], 1);
+    assert_eq!(
+        json["subjects"]["solana.transactions"]["latencyMicros"]["max"],
+        10
+    );
+}
+
+#[test]
+fn test_filter_and_route_stats() {
+    let stats = PluginStats::new();
+    assert!(stats.filter_rule_stats("addresses").is_none());
+
+    stats.record_filter_rule("addresses", true);
+    stats.record_filter_rule("addresses", true);
+    stats.record_filter_rule("addresses", false);
+    stats.record_route("wallets", false);
+
+    assert_eq!(
+        stats.filter_rule_stats("addresses"),
+        Some(FilterStats {
+            matched: 2,
+            filtered: 1
+        })
+    );
+    assert_eq!(stats.route_stats("wallets").unwrap().filtered, 1);
+    assert!(stats.route_stats("addresses").is_none());
+
+    let json = stats.to_json();
+    assert_eq!(json["filters"]["addresses"]["matched"], 2);
+    assert_eq!(json["routes"]["wallets"]["filtered"], 1);
+}
+
+#[test]
+fn test_last_published_slots_per_subject() {
+    let stats = PluginStats::new();
+    stats.record_slot_published("solana.transactions", 10);
+    stats.record_slot_published("solana.transactions", 12);
+    stats.record_slot_published("solana.transactions", 11);
+    stats.record_slot_published("solana.blocks", 9);
+
+    let slots = stats.last_published_slots();
+    assert_eq!(slots.get("solana.transactions"), Some(&12));
+    assert_eq!(slots.get("solana.blocks"), Some(&9));
+    assert_eq!(stats.last_published_slot(), 12);
+    assert_eq!(stats.to_json()["subjects"]["solana.blocks"]["lastSlot"], 9);
+}
+
+#[test]
+fn test_queued_bytes_gauge() {
+    let stats = PluginStats::new();
+
+    assert_eq!(stats.add_queued_bytes(100), 100);
+    assert_eq!(stats.add_queued_bytes(50), 150);
+    assert_eq!(stats.remove_queued_bytes(100), 50);
+    // The gauge never wraps below zero
+    assert_eq!(stats.remove_queued_bytes(80), 0);
+    assert_eq!(stats.to_json()["queuedBytes"], 0);
+}
@@ -0,0 +1,55 @@
+use {
+    solana_geyser_plugin_nats::{
+        connection::{ConnectionManager, NatsMessage},
+        status_file::StatusFileWriter,
+    },
+    std::{fs, sync::Arc, thread, time::Duration},
+    tempfile::tempdir,
+};
+
+fn create_test_connection_manager() -> Arc<ConnectionManager> {
+    // Nothing listens on this port, so queued messages stay queued
+    Arc::new(ConnectionManager::new("nats://127.0.0.1:9999", 100, 1).unwrap())
+}
+
+#[test]
+fn test_status_reports_queue_and_counters() {
+    let connection_manager = create_test_connection_manager();
+    connection_manager
+        .send_message(NatsMessage::new("test.subject".to_string(), vec![1, 2, 3]))
+        .unwrap();
+    connection_manager
+        .stats()
+        .record_slot_published("test.subject", 42);
+
+    let status = StatusFileWriter::status(&connection_manager);
+
+    assert_eq!(status["connected"], false);
+    assert_eq!(status["queueDepth"], 1);
+    assert_eq!(status["messagesQueued"], 1);
+    assert_eq!(status["lastPublishedSlot"], 42);
+    assert!(status["updatedAt"].is_string());
+}
+
+#[test]
+fn test_status_file_written_periodically() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("status.json");
+
+    let mut writer = StatusFileWriter::start(
+        path.clone(),
+        Duration::from_secs(60),
+        create_test_connection_manager(),
+    );
+    for _ in 0..50 {
+        if path.exists() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    writer.stop();
+
+    let status: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    assert_eq!(status["queueDepth"], 0);
+    assert!(!dir.path().join("status.json.tmp").exists());
+}
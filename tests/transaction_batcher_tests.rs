@@ -0,0 +1,99 @@
+use solana_geyser_plugin_nats::{
+    config::BatchingConfig,
+    connection::NatsMessage,
+    transaction_batcher::{TransactionBatcher, BATCH_OFFSETS_HEADER},
+};
+
+fn create_batcher(max_count: usize, max_bytes: usize) -> TransactionBatcher {
+    TransactionBatcher::new(&BatchingConfig {
+        enabled: true,
+        max_count,
+        max_bytes,
+    })
+    .expect("batching is enabled")
+}
+
+fn transaction(subject: &str, slot: u64, payload: &str) -> NatsMessage {
+    NatsMessage::new(subject.to_string(), payload.as_bytes().to_vec()).with_slot(slot)
+}
+
+fn header<'a>(message: &'a NatsMessage, name: &str) -> Option<&'a str> {
+    message
+        .headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+#[test]
+fn test_no_batcher_when_disabled() {
+    assert!(TransactionBatcher::new(&BatchingConfig::default()).is_none());
+}
+
+#[test]
+fn test_batch_closed_when_full() {
+    let batcher = create_batcher(3, 1024);
+
+    assert!(batcher
+        .add(transaction("solana.transactions", 5, "{\"a\":1}"))
+        .is_empty());
+    assert!(batcher
+        .add(transaction("solana.transactions", 5, "{}"))
+        .is_empty());
+    assert_eq!(batcher.pending(), 2);
+
+    let batches = batcher.add(transaction("solana.transactions", 5, "{\"b\":2}"));
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batcher.pending(), 0);
+
+    let batch = &batches[0];
+    assert_eq!(batch.subject, "solana.transactions");
+    assert_eq!(batch.slot, Some(5));
+    assert_eq!(batch.payload, b"{\"a\":1}{}{\"b\":2}");
+    assert_eq!(header(batch, "Solana-Slot"), Some("5"));
+    assert_eq!(header(batch, BATCH_OFFSETS_HEADER), Some("0,7,9"));
+}
+
+#[test]
+fn test_batch_closed_by_size_and_slot() {
+    let batcher = create_batcher(100, 10);
+
+    assert!(batcher
+        .add(transaction("solana.transactions", 5, "123456"))
+        .is_empty());
+    // Past max_bytes, the transaction starts the next batch
+    let batches = batcher.add(transaction("solana.transactions", 5, "7890ab"));
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].payload, b"123456");
+
+    // A transaction of another slot closes the batch too
+    let batches = batcher.add(transaction("solana.transactions", 6, "cd"));
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].payload, b"7890ab");
+    assert_eq!(batches[0].slot, Some(5));
+    assert_eq!(batcher.pending(), 1);
+}
+
+#[test]
+fn test_batches_per_subject_taken_through_slot() {
+    let batcher = create_batcher(100, 1024);
+
+    batcher.add(transaction("solana.transactions", 5, "a"));
+    batcher.add(transaction("solana.votes", 5, "b"));
+    batcher.add(transaction("solana.votes", 5, "c"));
+    batcher.add(transaction("solana.transactions.other", 7, "d"));
+    assert_eq!(batcher.pending(), 4);
+
+    let mut batches = batcher.take_through(6);
+    batches.sort_by(|a, b| a.subject.cmp(&b.subject));
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].subject, "solana.transactions");
+    assert_eq!(batches[1].subject, "solana.votes");
+    assert_eq!(header(&batches[1], BATCH_OFFSETS_HEADER), Some("0,1"));
+    assert_eq!(batcher.pending(), 1);
+
+    let batches = batcher.take_all();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].payload, b"d");
+    assert_eq!(batcher.pending(), 0);
+}
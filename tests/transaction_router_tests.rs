@@ -0,0 +1,537 @@
+use {
+    chrono::{DateTime, Duration, Utc},
+    solana_geyser_plugin_nats::{
+        config::{AddressMatch, RouteConfig, TransactionFilterConfig, TransactionVersion},
+        stats::PluginStats,
+        transaction_router::{
+            self, MessageShapeFilter, PublishWindow, TransactionFilter, TransactionRouter,
+        },
+    },
+    solana_sdk::{
+        hash::Hash,
+        instruction::CompiledInstruction,
+        message::{
+            v0::{self, LoadedAddresses, MessageAddressTableLookup},
+            Message, MessageHeader, SimpleAddressLoader, VersionedMessage,
+        },
+        pubkey::Pubkey,
+        signature::Signature,
+        system_instruction, system_program,
+        transaction::{
+            MessageHash, SanitizedTransaction, Transaction, TransactionError, VersionedTransaction,
+        },
+    },
+    solana_transaction_status::TransactionStatusMeta,
+    std::collections::HashSet,
+};
+
+fn create_transfer(from: &Pubkey, to: &Pubkey) -> SanitizedTransaction {
+    let message = Message::new(&[system_instruction::transfer(from, to, 1_000)], Some(from));
+    SanitizedTransaction::try_from_legacy_transaction(
+        Transaction {
+            signatures: vec![Signature::default()],
+            message,
+        },
+        &HashSet::new(),
+    )
+    .unwrap()
+}
+
+/// A v0 transaction passing one writable and one readonly account from a lookup table,
+/// sanitized with `message_loaded_addresses`
+fn create_v0_transaction(message_loaded_addresses: LoadedAddresses) -> SanitizedTransaction {
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        },
+        account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        recent_blockhash: Hash::new_unique(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(
+            1,
+            vec![],
+            vec![0, 2, 3],
+        )],
+        address_table_lookups: vec![MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            writable_indexes: vec![0],
+            readonly_indexes: vec![1],
+        }],
+    };
+
+    SanitizedTransaction::try_create(
+        VersionedTransaction {
+            message: VersionedMessage::V0(message),
+            signatures: vec![Signature::default()],
+        },
+        MessageHash::Compute,
+        Some(false),
+        SimpleAddressLoader::Enabled(message_loaded_addresses),
+        &HashSet::new(),
+    )
+    .unwrap()
+}
+
+fn create_meta(failed: bool) -> TransactionStatusMeta {
+    TransactionStatusMeta {
+        status: if failed {
+            Err(TransactionError::InsufficientFundsForFee)
+        } else {
+            Ok(())
+        },
+        ..TransactionStatusMeta::default()
+    }
+}
+
+fn create_route(name: &str, subject: &str, filter: TransactionFilterConfig) -> RouteConfig {
+    RouteConfig {
+        name: name.to_string(),
+        subject: subject.to_string(),
+        filter,
+    }
+}
+
+#[test]
+fn test_router_disabled_without_routes() {
+    assert!(TransactionRouter::new(&[]).unwrap().is_none());
+}
+
+#[test]
+fn test_routes_selecting_transaction() {
+    let wallet = Pubkey::new_unique();
+    let router = TransactionRouter::new(&[
+        create_route(
+            "wallets",
+            "team.wallets",
+            TransactionFilterConfig {
+                select_all_transactions: false,
+                mentioned_addresses: vec![wallet.to_string()],
+                ..TransactionFilterConfig::default()
+            },
+        ),
+        create_route(
+            "failures",
+            "team.failures",
+            TransactionFilterConfig {
+                only_failed: true,
+                ..TransactionFilterConfig::default()
+            },
+        ),
+        create_route(
+            "transfers",
+            "team.transfers",
+            TransactionFilterConfig {
+                program_ids: vec![system_program::id().to_string()],
+                include_failed: false,
+                ..TransactionFilterConfig::default()
+            },
+        ),
+    ])
+    .unwrap()
+    .unwrap();
+    assert_eq!(router.routes().len(), 3);
+    assert_eq!(router.routes()[1].name(), "failures");
+
+    let to_wallet = create_transfer(&Pubkey::new_unique(), &wallet);
+    let elsewhere = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+
+    assert_eq!(
+        router.subjects(false, &to_wallet, &create_meta(false), 100),
+        ["team.wallets", "team.transfers"]
+    );
+    assert_eq!(
+        router.subjects(false, &to_wallet, &create_meta(true), 100),
+        ["team.wallets", "team.failures"]
+    );
+    assert_eq!(
+        router.subjects(false, &elsewhere, &create_meta(false), 100),
+        ["team.transfers"]
+    );
+}
+
+#[test]
+fn test_routes_sharing_subject_publish_once() {
+    let router = TransactionRouter::new(&[
+        create_route("all", "solana.shared", TransactionFilterConfig::default()),
+        create_route(
+            "transfers",
+            "solana.shared",
+            TransactionFilterConfig {
+                program_ids: vec![system_program::id().to_string()],
+                ..TransactionFilterConfig::default()
+            },
+        ),
+    ])
+    .unwrap()
+    .unwrap();
+
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    assert_eq!(
+        router.subjects(false, &transaction, &create_meta(false), 100),
+        ["solana.shared"]
+    );
+}
+
+#[test]
+fn test_route_excluded_programs() {
+    let router = TransactionRouter::new(&[create_route(
+        "no-transfers",
+        "solana.other",
+        TransactionFilterConfig {
+            excluded_program_ids: vec![system_program::id().to_string()],
+            ..TransactionFilterConfig::default()
+        },
+    )])
+    .unwrap()
+    .unwrap();
+
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    assert!(router
+        .subjects(false, &transaction, &create_meta(false), 100)
+        .is_empty());
+}
+
+#[test]
+fn test_filter_counts_rules_it_reaches() {
+    let filter = TransactionFilter::new(&TransactionFilterConfig {
+        program_ids: vec![Pubkey::new_unique().to_string()],
+        only_failed: true,
+        ..TransactionFilterConfig::default()
+    })
+    .unwrap();
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let stats = PluginStats::new();
+
+    assert!(!filter.matches(false, &transaction, &create_meta(true), 100, Some(&stats)));
+    assert!(!filter.matches(false, &transaction, &create_meta(false), 100, Some(&stats)));
+    assert!(!filter.matches(false, &transaction, &create_meta(false), 100, None));
+
+    assert_eq!(stats.filter_rule_stats("addresses").unwrap().matched, 2);
+    let status = stats.filter_rule_stats("status").unwrap();
+    assert_eq!((status.matched, status.filtered), (1, 1));
+    // Only the failed transaction reached the program rule
+    assert_eq!(stats.filter_rule_stats("programs").unwrap().filtered, 1);
+}
+
+#[test]
+fn test_address_match_modes() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let transaction = create_transfer(&sender, &recipient);
+    let meta = create_meta(false);
+
+    let cases = [
+        (sender, AddressMatch::SignerOnly, true),
+        (recipient, AddressMatch::AnyMention, true),
+        (recipient, AddressMatch::SignerOnly, false),
+        (recipient, AddressMatch::WritableOnly, true),
+        (system_program::id(), AddressMatch::AnyMention, true),
+        (system_program::id(), AddressMatch::WritableOnly, false),
+    ];
+    for (address, address_match, selected) in cases {
+        let router = TransactionRouter::new(&[create_route(
+            "wallet",
+            "team.wallet",
+            TransactionFilterConfig {
+                select_all_transactions: false,
+                mentioned_addresses: vec![address.to_string()],
+                address_match,
+                ..TransactionFilterConfig::default()
+            },
+        )])
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            !router.subjects(false, &transaction, &meta, 100).is_empty(),
+            selected,
+            "{address} with {address_match:?}"
+        );
+    }
+}
+
+#[test]
+fn test_route_log_filter() {
+    let router = TransactionRouter::new(&[
+        create_route(
+            "swaps",
+            "team.swaps",
+            TransactionFilterConfig {
+                log_contains: vec!["Instruction: Swap".to_string()],
+                ..TransactionFilterConfig::default()
+            },
+        ),
+        create_route(
+            "events",
+            "team.events",
+            TransactionFilterConfig {
+                log_patterns: vec!["^Program data: 5Xb2".to_string()],
+                ..TransactionFilterConfig::default()
+            },
+        ),
+    ])
+    .unwrap()
+    .unwrap();
+
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let with_logs = |log_messages: &[&str]| TransactionStatusMeta {
+        log_messages: Some(log_messages.iter().map(ToString::to_string).collect()),
+        ..create_meta(false)
+    };
+
+    assert_eq!(
+        router.subjects(
+            false,
+            &transaction,
+            &with_logs(&["Program log: Instruction: Swap", "Program data: 5Xb2AAE="]),
+            100
+        ),
+        vec!["team.swaps", "team.events"]
+    );
+    assert_eq!(
+        router.subjects(
+            false,
+            &transaction,
+            &with_logs(&["Program log: Instruction: Swap"]),
+            100
+        ),
+        vec!["team.swaps"]
+    );
+    assert!(router
+        .subjects(
+            false,
+            &transaction,
+            &with_logs(&["Program log: data 5Xb2"]),
+            100
+        )
+        .is_empty());
+    assert!(router
+        .subjects(false, &transaction, &create_meta(false), 100)
+        .is_empty());
+}
+
+#[test]
+fn test_sampling_by_signature() {
+    let signatures: Vec<Signature> = (0..10_000).map(|_| Signature::new_unique()).collect();
+    assert!(signatures
+        .iter()
+        .all(|signature| transaction_router::is_sampled(signature, 1)));
+
+    // Roughly one in ten, and the same transactions every time
+    let sampled: Vec<&Signature> = signatures
+        .iter()
+        .filter(|signature| transaction_router::is_sampled(signature, 10))
+        .collect();
+    assert!((800..1_200).contains(&sampled.len()), "{}", sampled.len());
+    assert!(sampled
+        .iter()
+        .all(|signature| transaction_router::is_sampled(signature, 10)));
+}
+
+#[test]
+fn test_route_sample_rate() {
+    let router = TransactionRouter::new(&[
+        create_route("all", "team.all", TransactionFilterConfig::default()),
+        create_route(
+            "sample",
+            "team.sample",
+            TransactionFilterConfig {
+                sample_rate: 100,
+                ..TransactionFilterConfig::default()
+            },
+        ),
+    ])
+    .unwrap()
+    .unwrap();
+
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let expected = if transaction_router::is_sampled(transaction.signature(), 100) {
+        vec!["team.all", "team.sample"]
+    } else {
+        vec!["team.all"]
+    };
+    assert_eq!(
+        router.subjects(false, &transaction, &create_meta(false), 100),
+        expected
+    );
+}
+
+#[test]
+fn test_publish_window() {
+    assert!(PublishWindow::new(&TransactionFilterConfig::default()).is_none());
+
+    let start_time: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+    let end_time = start_time + Duration::hours(1);
+    let publish_window = PublishWindow::new(&TransactionFilterConfig {
+        start_slot: Some(100),
+        end_slot: Some(200),
+        start_time: Some(start_time),
+        end_time: Some(end_time),
+        ..TransactionFilterConfig::default()
+    })
+    .unwrap();
+
+    let during = start_time + Duration::minutes(30);
+    assert!(!publish_window.contains(99, during));
+    assert!(publish_window.contains(100, during));
+    assert!(publish_window.contains(200, during));
+    assert!(!publish_window.contains(201, during));
+
+    assert!(!publish_window.contains(150, start_time - Duration::seconds(1)));
+    assert!(publish_window.contains(150, start_time));
+    assert!(!publish_window.contains(150, end_time));
+
+    let from_slot = PublishWindow::new(&TransactionFilterConfig {
+        start_slot: Some(100),
+        ..TransactionFilterConfig::default()
+    })
+    .unwrap();
+    assert!(from_slot.contains(u64::MAX, Utc::now()));
+}
+
+#[test]
+fn test_message_shape_filter() {
+    assert!(MessageShapeFilter::new(&TransactionFilterConfig::default()).is_none());
+
+    // A legacy transfer has one signature and three account keys; the v0 transaction one
+    // signature, two static keys and two lookup-table addresses
+    let legacy = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let loaded_addresses = LoadedAddresses {
+        writable: vec![Pubkey::new_unique()],
+        readonly: vec![Pubkey::new_unique()],
+    };
+    let v0 = create_v0_transaction(LoadedAddresses::default());
+    let meta = create_meta(false);
+    let v0_meta = TransactionStatusMeta {
+        loaded_addresses,
+        ..create_meta(false)
+    };
+
+    let cases = [
+        (
+            Some(TransactionVersion::Legacy),
+            None,
+            None,
+            None,
+            true,
+            false,
+        ),
+        (Some(TransactionVersion::V0), None, None, None, false, true),
+        (None, Some(2), None, None, false, false),
+        (None, None, Some(1), None, true, true),
+        (None, None, None, Some(4), false, true),
+        (
+            Some(TransactionVersion::V0),
+            None,
+            Some(1),
+            Some(4),
+            false,
+            true,
+        ),
+    ];
+    for (version, min_signatures, max_signatures, min_account_keys, legacy_selected, v0_selected) in
+        cases
+    {
+        let message_shape = MessageShapeFilter::new(&TransactionFilterConfig {
+            transaction_version: version,
+            min_signatures,
+            max_signatures,
+            min_account_keys,
+            max_account_keys: min_account_keys.map(|_| 4),
+            ..TransactionFilterConfig::default()
+        })
+        .unwrap();
+
+        assert_eq!(message_shape.matches(&legacy, &meta), legacy_selected);
+        assert_eq!(message_shape.matches(&v0, &v0_meta), v0_selected);
+    }
+}
+
+#[test]
+fn test_route_slot_range() {
+    let router = TransactionRouter::new(&[
+        create_route("live", "team.live", TransactionFilterConfig::default()),
+        create_route(
+            "backfill",
+            "team.backfill",
+            TransactionFilterConfig {
+                start_slot: Some(100),
+                end_slot: Some(200),
+                ..TransactionFilterConfig::default()
+            },
+        ),
+    ])
+    .unwrap()
+    .unwrap();
+
+    let transaction = create_transfer(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let meta = create_meta(false);
+    assert_eq!(
+        router.subjects(false, &transaction, &meta, 150),
+        ["team.live", "team.backfill"]
+    );
+    assert_eq!(
+        router.subjects(false, &transaction, &meta, 201),
+        ["team.live"]
+    );
+}
+
+#[test]
+fn test_lookup_table_addresses_matched() {
+    let loaded_addresses = LoadedAddresses {
+        writable: vec![Pubkey::new_unique()],
+        readonly: vec![Pubkey::new_unique()],
+    };
+    let meta = TransactionStatusMeta {
+        loaded_addresses: loaded_addresses.clone(),
+        ..create_meta(false)
+    };
+    let cases = [
+        (loaded_addresses.writable[0], AddressMatch::AnyMention, true),
+        (loaded_addresses.readonly[0], AddressMatch::AnyMention, true),
+        (
+            loaded_addresses.writable[0],
+            AddressMatch::WritableOnly,
+            true,
+        ),
+        (
+            loaded_addresses.readonly[0],
+            AddressMatch::WritableOnly,
+            false,
+        ),
+        (
+            loaded_addresses.writable[0],
+            AddressMatch::SignerOnly,
+            false,
+        ),
+    ];
+
+    // Whether the message was sanitized with the resolved addresses or without them, the
+    // addresses recorded in the meta match the same way
+    for transaction in [
+        create_v0_transaction(loaded_addresses.clone()),
+        create_v0_transaction(LoadedAddresses::default()),
+    ] {
+        for (address, address_match, selected) in cases {
+            let router = TransactionRouter::new(&[create_route(
+                "lookups",
+                "team.lookups",
+                TransactionFilterConfig {
+                    select_all_transactions: false,
+                    mentioned_addresses: vec![address.to_string()],
+                    address_match,
+                    ..TransactionFilterConfig::default()
+                },
+            )])
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(
+                !router.subjects(false, &transaction, &meta, 100).is_empty(),
+                selected,
+                "{address} with {address_match:?}"
+            );
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use solana_geyser_plugin_nats::transaction_selector::TransactionSelector;
+use solana_geyser_plugin_nats::{
+    config::TransactionFilterConfig,
+    transaction_selector::{SelectorError, TransactionSelector},
+};
 use solana_sdk::pubkey::Pubkey;
 
 #[test]
@@ -12,7 +15,7 @@ fn test_select_specific_transaction() {
     let pubkey1 = Pubkey::new_unique();
     let pubkey2 = Pubkey::new_unique();
 
-    let selector = TransactionSelector::new(&[pubkey1.to_string()]);
+    let selector = TransactionSelector::new(&[pubkey1.to_string()]).unwrap();
 
     assert!(selector.is_enabled());
     assert!(!selector.select_all_transactions);
@@ -27,7 +30,7 @@ fn test_select_specific_transaction() {
 #[test]
 fn test_select_all_with_wildcard() {
     let pubkey = Pubkey::new_unique();
-    let selector = TransactionSelector::new(&["*".to_string()]);
+    let selector = TransactionSelector::new(&["*".to_string()]).unwrap();
 
     assert!(selector.is_enabled());
     assert!(selector.select_all_transactions);
@@ -40,7 +43,7 @@ fn test_select_all_with_wildcard() {
 #[test]
 fn test_vote_transaction_filtering() {
     let pubkey = Pubkey::new_unique();
-    let selector = TransactionSelector::new(&[pubkey.to_string()]);
+    let selector = TransactionSelector::new(&[pubkey.to_string()]).unwrap();
 
     let addresses = [pubkey];
     // Should select non-vote transactions that mention this address
@@ -48,3 +51,70 @@ fn test_vote_transaction_filtering() {
     // Should also select vote transactions that mention this address
     assert!(selector.is_transaction_selected(true, Box::new(addresses.iter())));
 }
+
+#[test]
+fn test_invalid_address_returns_error() {
+    let result = TransactionSelector::new(&[Pubkey::new_unique().to_string(), "0OIl".to_string()]);
+
+    assert!(matches!(result, Err(SelectorError::InvalidAddress { .. })));
+}
+
+#[test]
+fn test_short_base58_address_returns_error() {
+    // Valid base58, but not the 32 bytes of a pubkey, so it could never match
+    let result = TransactionSelector::new(&["3yZe7d".to_string()]);
+
+    assert!(matches!(result, Err(SelectorError::InvalidAddress { .. })));
+}
+
+#[test]
+fn test_all_votes_combined_with_mentioned_addresses() {
+    let wallet = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let selector = TransactionSelector::from_filter(&TransactionFilterConfig {
+        select_all_transactions: false,
+        select_vote_transactions: true,
+        mentioned_addresses: vec![wallet.to_string()],
+        ..TransactionFilterConfig::default()
+    })
+    .unwrap();
+
+    assert!(selector.is_transaction_selected(true, Box::new([other].iter())));
+    assert!(selector.is_transaction_selected(false, Box::new([wallet].iter())));
+    assert!(!selector.is_transaction_selected(false, Box::new([other].iter())));
+}
+
+#[test]
+fn test_vote_addresses_combined_with_mentioned_addresses() {
+    let validator = Pubkey::new_unique();
+    let wallet = Pubkey::new_unique();
+    let selector = TransactionSelector::from_filter(&TransactionFilterConfig {
+        select_all_transactions: false,
+        vote_addresses: vec![validator.to_string()],
+        mentioned_addresses: vec![wallet.to_string()],
+        ..TransactionFilterConfig::default()
+    })
+    .unwrap();
+
+    // Votes of the validator, not votes mentioning the wallet
+    assert!(selector.is_transaction_selected(true, Box::new([validator].iter())));
+    assert!(!selector.is_transaction_selected(true, Box::new([wallet].iter())));
+    // Non-votes mentioning the wallet, not those mentioning the validator
+    assert!(selector.is_transaction_selected(false, Box::new([wallet].iter())));
+    assert!(!selector.is_transaction_selected(false, Box::new([validator].iter())));
+}
+
+#[test]
+fn test_vote_addresses_alone_select_only_votes() {
+    let validator = Pubkey::new_unique();
+    let selector = TransactionSelector::from_filter(&TransactionFilterConfig {
+        select_all_transactions: false,
+        vote_addresses: vec![validator.to_string()],
+        ..TransactionFilterConfig::default()
+    })
+    .unwrap();
+
+    assert!(selector.is_enabled());
+    assert!(selector.is_transaction_selected(true, Box::new([validator].iter())));
+    assert!(!selector.is_transaction_selected(false, Box::new([validator].iter())));
+}
@@ -0,0 +1,253 @@
+use {
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        ReplicaTransactionInfo, ReplicaTransactionInfoV2,
+    },
+    serde_json::{json, Value},
+    solana_geyser_plugin_nats::{
+        config::PayloadFormat, serializer::TransactionSerializer,
+        transaction_writer::TransactionWriter,
+    },
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::CompiledInstruction,
+        message::{
+            v0::{self, LoadedAddresses, MessageAddressTableLookup},
+            Message, MessageHeader, SimpleAddressLoader, VersionedMessage,
+        },
+        pubkey::Pubkey,
+        signature::Signature,
+        system_instruction,
+        transaction::{MessageHash, SanitizedTransaction, TransactionError, VersionedTransaction},
+        transaction_context::TransactionReturnData,
+    },
+    solana_transaction_status::{
+        InnerInstruction, InnerInstructions, Reward, RewardType, TransactionStatusMeta,
+    },
+    std::collections::HashSet,
+};
+
+const FORMATS: [PayloadFormat; 3] = [
+    PayloadFormat::Json,
+    PayloadFormat::Msgpack,
+    PayloadFormat::Cbor,
+];
+
+fn create_legacy_transaction() -> SanitizedTransaction {
+    let payer = Pubkey::new_unique();
+    let message = Message::new(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            system_instruction::transfer(&payer, &Pubkey::new_unique(), 1_000_000),
+        ],
+        Some(&payer),
+    );
+
+    SanitizedTransaction::try_create(
+        VersionedTransaction {
+            message: VersionedMessage::Legacy(message),
+            signatures: vec![Signature::new_unique()],
+        },
+        MessageHash::Compute,
+        Some(false),
+        SimpleAddressLoader::Disabled,
+        &HashSet::new(),
+    )
+    .unwrap()
+}
+
+fn create_v0_transaction(loaded_addresses: LoadedAddresses) -> SanitizedTransaction {
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        },
+        account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        recent_blockhash: Hash::new_unique(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(
+            1,
+            vec![7, 8, 9],
+            vec![0, 2, 3],
+        )],
+        address_table_lookups: vec![MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            writable_indexes: vec![4],
+            readonly_indexes: vec![9],
+        }],
+    };
+
+    SanitizedTransaction::try_create(
+        VersionedTransaction {
+            message: VersionedMessage::V0(message),
+            signatures: vec![Signature::new_unique()],
+        },
+        MessageHash::Compute,
+        Some(false),
+        SimpleAddressLoader::Enabled(loaded_addresses),
+        &HashSet::new(),
+    )
+    .unwrap()
+}
+
+fn create_meta() -> TransactionStatusMeta {
+    TransactionStatusMeta {
+        status: Ok(()),
+        fee: 5000,
+        pre_balances: vec![1_000_000, 0, 1],
+        post_balances: vec![994_000, 1_000_000, 1],
+        log_messages: Some(vec![
+            "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+            "Program log: \"quoted\" \u{1F600}".to_string(),
+            "Program 11111111111111111111111111111111 success".to_string(),
+        ]),
+        compute_units_consumed: Some(150),
+        ..Default::default()
+    }
+}
+
+fn create_full_meta(loaded_addresses: LoadedAddresses) -> TransactionStatusMeta {
+    TransactionStatusMeta {
+        status: Err(TransactionError::InsufficientFundsForFee),
+        loaded_addresses,
+        inner_instructions: Some(vec![InnerInstructions {
+            index: 0,
+            instructions: vec![
+                InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(2, vec![1, 2], vec![0]),
+                    stack_height: Some(2),
+                },
+                InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(3, vec![], vec![1]),
+                    stack_height: None,
+                },
+            ],
+        }]),
+        rewards: Some(vec![
+            Reward {
+                pubkey: Pubkey::new_unique().to_string(),
+                lamports: -5000,
+                post_balance: 995_000,
+                reward_type: Some(RewardType::Rent),
+                commission: None,
+            },
+            Reward {
+                pubkey: Pubkey::new_unique().to_string(),
+                lamports: 12,
+                post_balance: 42,
+                reward_type: None,
+                commission: Some(7),
+            },
+        ]),
+        return_data: Some(TransactionReturnData {
+            program_id: Pubkey::new_unique(),
+            data: vec![1, 2, 3],
+        }),
+        log_messages: None,
+        compute_units_consumed: None,
+        ..create_meta()
+    }
+}
+
+/// The payload the `Value` path produces for the same transaction
+fn encode_value(
+    mut value: Value,
+    slot: u64,
+    index: Option<usize>,
+    sequence: u64,
+    network: Option<&str>,
+    format: PayloadFormat,
+) -> Vec<u8> {
+    let object = value.as_object_mut().unwrap();
+    object.insert(
+        "orderingKey".to_string(),
+        json!(TransactionSerializer::ordering_key(slot, index)),
+    );
+    object.insert("sequence".to_string(), json!(sequence));
+    if let Some(network) = network {
+        object.insert("network".to_string(), json!(network));
+    }
+    TransactionSerializer::set_schema_version(&mut value);
+    TransactionSerializer::encode(&value, format, "transaction").unwrap()
+}
+
+fn assert_matches_value_path_v2(
+    transaction: &SanitizedTransaction,
+    meta: &TransactionStatusMeta,
+    network: Option<&str>,
+) {
+    let (slot, index, sequence) = (123_456, 17, 99);
+    let transaction_info = ReplicaTransactionInfoV2 {
+        signature: &transaction.signatures()[0],
+        is_vote: false,
+        transaction,
+        transaction_status_meta: meta,
+        index,
+    };
+    let value = TransactionSerializer::serialize_transaction_v2(&transaction_info, slot).unwrap();
+
+    for format in FORMATS {
+        let written = TransactionWriter::new(transaction, meta, slot, Some(index), false)
+            .with_sequence(sequence)
+            .with_network(network)
+            .encode(format)
+            .unwrap();
+
+        assert_eq!(
+            written,
+            encode_value(value.clone(), slot, Some(index), sequence, network, format),
+            "{format:?} payloads differ"
+        );
+    }
+}
+
+#[test]
+fn test_writes_legacy_transaction_like_value_path() {
+    let transaction = create_legacy_transaction();
+
+    assert_matches_value_path_v2(&transaction, &create_meta(), None);
+    assert_matches_value_path_v2(&transaction, &create_meta(), Some("mainnet-beta"));
+}
+
+#[test]
+fn test_writes_v0_transaction_with_full_meta_like_value_path() {
+    let loaded_addresses = LoadedAddresses {
+        writable: vec![Pubkey::new_unique()],
+        readonly: vec![Pubkey::new_unique()],
+    };
+    let transaction = create_v0_transaction(loaded_addresses.clone());
+
+    assert_matches_value_path_v2(
+        &transaction,
+        &create_full_meta(loaded_addresses),
+        Some("devnet"),
+    );
+}
+
+#[test]
+fn test_writes_v1_transaction_without_index() {
+    let transaction = create_legacy_transaction();
+    let meta = create_meta();
+    let transaction_info = ReplicaTransactionInfo {
+        signature: &transaction.signatures()[0],
+        is_vote: true,
+        transaction: &transaction,
+        transaction_status_meta: &meta,
+    };
+    let value = TransactionSerializer::serialize_transaction_v1(&transaction_info, 5).unwrap();
+
+    let written = TransactionWriter::new(&transaction, &meta, 5, None, true)
+        .with_sequence(3)
+        .encode(PayloadFormat::Json)
+        .unwrap();
+
+    assert_eq!(
+        written,
+        encode_value(value, 5, None, 3, None, PayloadFormat::Json)
+    );
+    let payload: Value = serde_json::from_slice(&written).unwrap();
+    assert_eq!(payload["index"], Value::Null);
+    assert_eq!(payload["isVote"], true);
+    assert_eq!(payload["orderingKey"], 5u64 << 20);
+}